@@ -0,0 +1,155 @@
+use http::HeaderMap;
+use tonic::metadata::errors::InvalidMetadataValue;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::Interceptor;
+
+/// The standard header of the access token, `authorization: Bearer <token>`.
+pub const AUTHORIZATION: &str = "authorization";
+/// The header of the raw access token, accepted for the clients written before `authorization`.
+pub const LEGACY_AUTHORIZATION: &str = "auth";
+/// The header telling the caller how to authenticate, see
+/// [RFC 6750](https://www.rfc-editor.org/rfc/rfc6750#section-3).
+pub const WWW_AUTHENTICATE: &str = "www-authenticate";
+
+const REALM: &str = "avocado";
+
+/// Why a request could not be authenticated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Challenge {
+    /// The request has no access token.
+    Missing,
+    /// The access token is not sent the way it should be, e.g. with another scheme than `Bearer`.
+    InvalidRequest,
+    /// The access token is expired, revoked, malformed or otherwise invalid.
+    InvalidToken,
+}
+
+impl Challenge {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Challenge::Missing => "an access token is required",
+            Challenge::InvalidRequest => "the authorization header is not a bearer token",
+            Challenge::InvalidToken => "the access token is invalid or expired",
+        }
+    }
+
+    /// The `www-authenticate` value, with an error code only when a token was sent.
+    pub fn www_authenticate(&self) -> String {
+        let error = match self {
+            Challenge::Missing => return format!("Bearer realm=\"{}\"", REALM),
+            Challenge::InvalidRequest => "invalid_request",
+            Challenge::InvalidToken => "invalid_token",
+        };
+        format!(
+            "Bearer realm=\"{}\", error=\"{}\", error_description=\"{}\"",
+            REALM,
+            error,
+            self.description()
+        )
+    }
+}
+
+/// The access token of a request, from `authorization: Bearer <token>` or else the legacy `auth`
+/// header.
+pub fn bearer_token(headers: &HeaderMap) -> Result<&str, Challenge> {
+    if let Some(value) = headers.get(AUTHORIZATION) {
+        let value = value.to_str().map_err(|_| Challenge::InvalidRequest)?;
+        return match value.trim().split_once(' ') {
+            Some((scheme, token))
+                if scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty() =>
+            {
+                Ok(token.trim())
+            }
+            _ => Err(Challenge::InvalidRequest),
+        };
+    }
+    match headers.get(LEGACY_AUTHORIZATION) {
+        Some(value) => value.to_str().map_err(|_| Challenge::InvalidRequest),
+        None => Err(Challenge::Missing),
+    }
+}
+
+/// Sends an access token as `authorization: Bearer <token>` with every call of a client.
+#[derive(Debug, Clone)]
+pub struct Bearer(MetadataValue<Ascii>);
+
+impl Bearer {
+    pub fn new(token: &str) -> Result<Self, InvalidMetadataValue> {
+        format!("Bearer {}", token).parse().map(Self)
+    }
+}
+
+impl Interceptor for Bearer {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        request.metadata_mut().insert(AUTHORIZATION, self.0.clone());
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::{bearer_token, Bearer, Challenge};
+    use http::HeaderMap;
+    use tonic::service::Interceptor;
+
+    #[test]
+    fn test_bearer_token() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, value.parse().unwrap());
+            }
+            headers
+        };
+
+        assert_eq!(
+            bearer_token(&headers(&[("authorization", "Bearer abc")])),
+            Ok("abc")
+        );
+        assert_eq!(
+            bearer_token(&headers(&[("authorization", "bearer  abc ")])),
+            Ok("abc")
+        );
+        assert_eq!(bearer_token(&headers(&[("auth", "abc")])), Ok("abc"));
+        // The standard header wins over the legacy one
+        assert_eq!(
+            bearer_token(&headers(&[
+                ("authorization", "Bearer abc"),
+                ("auth", "def")
+            ])),
+            Ok("abc")
+        );
+        assert_eq!(
+            bearer_token(&headers(&[("authorization", "Basic YWJj")])),
+            Err(Challenge::InvalidRequest)
+        );
+        assert_eq!(
+            bearer_token(&headers(&[("authorization", "Bearer ")])),
+            Err(Challenge::InvalidRequest)
+        );
+        assert_eq!(bearer_token(&headers(&[])), Err(Challenge::Missing));
+
+        assert_eq!(
+            Challenge::Missing.www_authenticate(),
+            "Bearer realm=\"avocado\""
+        );
+        assert_eq!(
+            Challenge::InvalidToken.www_authenticate(),
+            "Bearer realm=\"avocado\", error=\"invalid_token\", \
+             error_description=\"the access token is invalid or expired\""
+        );
+    }
+
+    #[test]
+    fn test_interceptor() {
+        let request = Bearer::new("abc")
+            .unwrap()
+            .call(tonic::Request::new(()))
+            .unwrap();
+        let headers = request.metadata().clone().into_headers();
+        assert_eq!(bearer_token(&headers), Ok("abc"));
+    }
+}
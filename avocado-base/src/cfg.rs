@@ -0,0 +1,373 @@
+use config::{Environment, File, FileFormat, Source, Value, ValueKind};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+/// The prefix of the environment variables overriding the configuration.
+pub const ENV_PREFIX: &str = "AVOCADO";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("unable to load the configuration: {0}")]
+    Load(#[from] config::ConfigError),
+    #[error("invalid configuration of {key}: {message}")]
+    Invalid { key: String, message: String },
+}
+
+impl ConfigError {
+    pub fn invalid(key: &str, message: impl Display) -> Self {
+        ConfigError::Invalid {
+            key: key.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Checks a loaded configuration as a whole, so mistakes surface at startup rather than on use.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ConfigError>;
+}
+
+/// Loads the configuration of a service from layers where later ones override earlier ones: the
+/// YAML defaults in order, the optional YAML file, then the environment variables such as
+/// `AVOCADO_JWT__ACCESS_TOKEN_EXPIRE_IN`, where `__` separates nested keys.
+#[derive(Debug, Clone)]
+pub struct Loader {
+    defaults: Vec<&'static str>,
+    file: PathBuf,
+    list_keys: Vec<&'static str>,
+}
+
+impl Loader {
+    /// The file is named by the `file_var` environment variable when set.
+    pub fn new(defaults: &[&'static str], file: &str, file_var: &str) -> Self {
+        Self {
+            defaults: defaults.to_vec(),
+            file: std::env::var(file_var)
+                .unwrap_or_else(|_| file.to_string())
+                .into(),
+            list_keys: vec![],
+        }
+    }
+
+    /// Reads the environment variable of the key as a comma separated list.
+    pub fn with_list_key(self, key: &'static str) -> Self {
+        let mut list_keys = self.list_keys;
+        list_keys.push(key);
+        Self { list_keys, ..self }
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn load<T>(&self) -> Result<(T, Settings), ConfigError>
+    where
+        T: DeserializeOwned + Validate,
+    {
+        let builder = self
+            .defaults
+            .iter()
+            .fold(config::Config::builder(), |builder, defaults| {
+                builder.add_source(File::from_str(defaults, FileFormat::Yaml))
+            })
+            .add_source(File::from(self.file.as_path()).required(false))
+            .add_source(
+                Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator("__")
+                    .ignore_empty(true),
+            );
+        let sources = self
+            .list_keys
+            .iter()
+            .try_fold(builder, |builder, key| {
+                let var = format!("{}_{}", ENV_PREFIX, key.replace('.', "__")).to_uppercase();
+                match std::env::var(var) {
+                    Ok(list) if !list.is_empty() => builder.set_override(
+                        *key,
+                        list.split(',')
+                            .map(|s| s.trim().to_string())
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => Ok(builder),
+                }
+            })?
+            .build()?;
+        let settings = Settings::of(sources.collect()?);
+        let config = sources.try_deserialize::<T>()?;
+        config.validate()?;
+        Ok((config, settings))
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.file)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+}
+
+/// Loads a configuration, see [`Loader`].
+pub fn load<T>(defaults: &[&'static str], file: &str, file_var: &str) -> Result<T, ConfigError>
+where
+    T: DeserializeOwned + Validate,
+{
+    Loader::new(defaults, file, file_var)
+        .load()
+        .map(|(config, _)| config)
+}
+
+/// Reads a file holding a secret, such as a key mounted by the orchestrator.
+pub fn read_secret_file(key: &str, path: &Path) -> Result<String, ConfigError> {
+    std::fs::read_to_string(path)
+        .map_err(|e| ConfigError::invalid(key, format!("unable to read {}: {}", path.display(), e)))
+}
+
+/// The settings of a loaded configuration by their dotted keys, to tell what a reload changed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Settings(BTreeMap<String, String>);
+
+impl Settings {
+    fn of(table: config::Map<String, Value>) -> Self {
+        fn flatten(prefix: &str, table: config::Map<String, Value>, settings: &mut Settings) {
+            for (key, value) in table {
+                let key = match prefix {
+                    "" => key,
+                    _ => format!("{}.{}", prefix, key),
+                };
+                match value.kind {
+                    ValueKind::Table(table) => flatten(&key, table, settings),
+                    ValueKind::Array(values) => {
+                        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                        settings.0.insert(key, format!("[{}]", values.join(", ")));
+                    }
+                    kind => {
+                        settings.0.insert(key, kind.to_string());
+                    }
+                }
+            }
+        }
+
+        let mut settings = Settings::default();
+        flatten("", table, &mut settings);
+        settings
+    }
+
+    /// Describes the settings added, removed or changed in the other settings, without the
+    /// values of secrets.
+    pub fn diff(&self, other: &Settings) -> Vec<String> {
+        let secret = |key: &str| {
+            ["password", "private_key", "secret"]
+                .iter()
+                .any(|s| key.contains(s))
+        };
+        let mut changes = vec![];
+        for (key, value) in &self.0 {
+            match other.0.get(key) {
+                None => changes.push(format!("{} removed", key)),
+                Some(v) if v != value && secret(key) => changes.push(format!("{} changed", key)),
+                Some(v) if v != value => {
+                    changes.push(format!("{} changed from {} to {}", key, value, v))
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in &other.0 {
+            if !self.0.contains_key(key) {
+                match secret(key) {
+                    true => changes.push(format!("{} added", key)),
+                    false => changes.push(format!("{} added as {}", key, value)),
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// A value which readers take snapshots of, while a writer swaps it as a whole.
+#[derive(Debug)]
+pub struct Shared<T>(RwLock<Arc<T>>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Self(RwLock::new(Arc::new(value)))
+    }
+
+    pub fn get(&self) -> Arc<T> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn set(&self, value: T) {
+        *self.0.write().unwrap() = Arc::new(value);
+    }
+}
+
+#[cfg(unix)]
+async fn hangup(signal: &mut Option<tokio::signal::unix::Signal>) {
+    match signal {
+        Some(signal) => {
+            signal.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Reloads the configuration when its file changes, checked every interval, or when the process
+/// receives SIGHUP, and hands it to `apply` when its settings changed. Invalid configurations are
+/// logged and ignored, so the current one stays in place.
+pub async fn watch<T, F>(loader: Loader, mut settings: Settings, interval: Duration, apply: F)
+where
+    T: DeserializeOwned + Validate,
+    F: Fn(T),
+{
+    #[cfg(unix)]
+    let mut signal = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).ok();
+    let mut modified = loader.modified();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = hangup(&mut signal) => modified = None,
+        }
+        #[cfg(not(unix))]
+        ticker.tick().await;
+
+        let current = loader.modified();
+        if current == modified {
+            continue;
+        }
+        modified = current;
+
+        match loader.load::<T>() {
+            Ok((config, loaded)) => {
+                let changes = settings.diff(&loaded);
+                if changes.is_empty() {
+                    continue;
+                }
+                for change in &changes {
+                    tracing::info!("configuration reloaded, {}", change);
+                }
+                apply(config);
+                settings = loaded;
+            }
+            Err(e) => tracing::error!("keeping the current configuration, {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cfg::{load, watch, ConfigError, Loader, Validate};
+    use serde::Deserialize;
+    use std::time::Duration;
+    use tokio::sync::{mpsc, Mutex};
+
+    // Keeps the tests from seeing each other's environment variables
+    static ENV: Mutex<()> = Mutex::const_new(());
+
+    #[derive(Deserialize, Debug)]
+    struct Sample {
+        name: String,
+        port: u16,
+        #[serde(default)]
+        password: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    impl Validate for Sample {
+        fn validate(&self) -> Result<(), ConfigError> {
+            if self.port == 0 {
+                return Err(ConfigError::invalid("port", "needs to be positive"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_load() {
+        let _env = ENV.blocking_lock();
+        let sample: Sample =
+            load(&["name: a\nport: 1"], "missing.yaml", "AVOCADO_TEST_CONFIG").unwrap();
+        assert_eq!(sample.name, "a");
+        assert_eq!(sample.port, 1);
+
+        std::env::set_var("AVOCADO_PORT", "2");
+        let sample: Sample =
+            load(&["name: a\nport: 1"], "missing.yaml", "AVOCADO_TEST_CONFIG").unwrap();
+        assert_eq!(sample.port, 2);
+        std::env::remove_var("AVOCADO_PORT");
+
+        std::env::set_var("AVOCADO_NAME", "a,b");
+        std::env::set_var("AVOCADO_TAGS", "a, b");
+        let (sample, _) = Loader::new(&["port: 1"], "missing.yaml", "AVOCADO_TEST_CONFIG")
+            .with_list_key("tags")
+            .load::<Sample>()
+            .unwrap();
+        assert_eq!(sample.name, "a,b");
+        assert_eq!(sample.tags, vec!["a", "b"]);
+        std::env::remove_var("AVOCADO_NAME");
+        std::env::remove_var("AVOCADO_TAGS");
+
+        let error = load::<Sample>(
+            &["name: a", "port: 0"],
+            "missing.yaml",
+            "AVOCADO_TEST_CONFIG",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid configuration of port: needs to be positive"
+        );
+        assert!(load::<Sample>(&["name: a"], "missing.yaml", "AVOCADO_TEST_CONFIG").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_watch() {
+        let _env = ENV.lock().await;
+        let file = std::env::temp_dir().join(format!("avocado-{}.yaml", std::process::id()));
+        std::fs::write(&file, "port: 1").unwrap();
+        let loader = Loader::new(&["name: a"], file.to_str().unwrap(), "AVOCADO_TEST_CONFIG");
+        let (sample, settings) = loader.load::<Sample>().unwrap();
+        assert_eq!(sample.port, 1);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(watch(
+            loader,
+            settings.clone(),
+            Duration::from_millis(50),
+            move |s: Sample| {
+                tx.send(s).unwrap();
+            },
+        ));
+        // Invalid updates are skipped
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        std::fs::write(&file, "port: 0").unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        std::fs::write(&file, "port: 2\npassword: secret").unwrap();
+        let sample = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.port, 2);
+        assert_eq!(sample.password, "secret");
+        std::fs::remove_file(&file).unwrap();
+
+        let (_, reloaded) = Loader::new(
+            &["name: a", "port: 2\npassword: secret"],
+            "missing.yaml",
+            "AVOCADO_TEST_CONFIG",
+        )
+        .load::<Sample>()
+        .unwrap();
+        assert_eq!(
+            settings.diff(&reloaded),
+            vec!["port changed from 1 to 2", "password added"]
+        );
+    }
+}
@@ -1,3 +1,10 @@
+pub mod auth;
+pub mod cfg;
 pub mod error;
 pub mod log;
+pub mod metrics;
+pub mod rate_limit;
 pub mod secret;
+pub mod shutdown;
+pub mod tls;
+pub mod trace;
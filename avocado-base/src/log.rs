@@ -1,16 +1,625 @@
-use tracing::subscriber::set_global_default;
+use crate::cfg::{ConfigError, Loader, Validate};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{
+    HttpExporterBuilder, LogExporterBuilder, SpanExporterBuilder, TonicExporterBuilder,
+    WithExportConfig,
+};
+use opentelemetry_sdk::resource::{EnvResourceDetector, TelemetryResourceDetector};
+use opentelemetry_sdk::{logs, runtime, trace, Resource};
+use serde::Deserialize;
+use std::any::TypeId;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::field::{display, DisplayValue, Field, Value, ValueSet, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::{set_global_default, Interest};
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation as Rolling};
+use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
+use tracing_subscriber::filter::Targets;
 use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
 
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// The fields whose name holds one of these, such as `access_token`, are always redacted.
+const REDACTED: [&str; 4] = ["password", "private_key", "secret", "token"];
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Compact,
+    Pretty,
+    /// Bunyan's JSON, one object per line
+    Json,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+impl From<Rotation> for Rolling {
+    fn from(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::Minutely => Rolling::MINUTELY,
+            Rotation::Hourly => Rolling::HOURLY,
+            Rotation::Daily => Rolling::DAILY,
+            Rotation::Never => Rolling::NEVER,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LogFile {
+    pub directory: PathBuf,
+    /// Starts the file names, followed by the date of the rotation
+    pub prefix: String,
+    #[serde(default)]
+    pub rotation: Rotation,
+}
+
+/// The `log` section of the configuration of a service.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LogConfig {
+    pub format: Format,
+    /// `EnvFilter` directives such as `info,avocado_user=debug`, `RUST_LOG` takes precedence
+    pub filter: String,
+    /// Writes to rolling files instead of stdout
+    pub file: Option<LogFile>,
+    /// Redacts the fields whose name holds one of these too
+    pub redact: Vec<String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            format: Format::default(),
+            filter: "info".to_string(),
+            file: None,
+            redact: vec![],
+        }
+    }
+}
+
+impl Validate for LogConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        EnvFilter::try_new(&self.filter).map_err(|e| ConfigError::invalid("log.filter", e))?;
+        if self.file.as_ref().is_some_and(|f| f.prefix.is_empty()) {
+            return Err(ConfigError::invalid("log.file.prefix", "cannot be empty"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct Section {
+    #[serde(default)]
+    log: LogConfig,
+}
+
+impl Validate for Section {
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.log.validate()
+    }
+}
+
+impl LogConfig {
+    /// Reads the `log` section alone, for the logging to start before the rest of the
+    /// configuration is checked.
+    pub fn load(loader: &Loader) -> Result<Self, ConfigError> {
+        loader.load::<Section>().map(|(section, _)| section.log)
+    }
+}
+
+/// Logs to stdout with the default settings, as the tests do.
 pub fn init_subscriber() {
-    LogTracer::init().expect("Failed to set logger");
-    let subscriber = tracing_subscriber::fmt()
-        .compact()
-        .with_file(true)
-        .with_line_number(true)
-        .with_thread_ids(false)
-        .with_target(false)
-        .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
-        .finish();
-    set_global_default(subscriber).expect("Failed to set subscriber");
+    // Without a file or an export, the guard has nothing to flush
+    let _ = Builder::new("avocado", LogConfig::default())
+        .init()
+        .expect("Failed to set subscriber");
+}
+
+/// Sets up the logging of a service.
+pub struct Builder {
+    service: &'static str,
+    version: Option<&'static str>,
+    config: LogConfig,
+}
+
+impl Builder {
+    pub fn new(service: &'static str, config: LogConfig) -> Self {
+        Builder {
+            service,
+            version: None,
+            config,
+        }
+    }
+
+    /// Exports the spans and logs over OTLP too when `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+    ///
+    /// `OTEL_EXPORTER_OTLP_PROTOCOL` picks `grpc` (the default) or `http/protobuf`, while
+    /// `OTEL_TRACES_SAMPLER` and `OTEL_TRACES_SAMPLER_ARG` set the sampling and
+    /// `OTEL_RESOURCE_ATTRIBUTES` adds to the service name and version.
+    pub fn with_telemetry(self, version: &'static str) -> Self {
+        Builder {
+            version: Some(version),
+            ..self
+        }
+    }
+
+    /// Installs the subscriber. Dropping the returned guard flushes the logs not written and
+    /// the spans and logs not exported yet.
+    pub fn init(self) -> Result<LogGuard, ConfigError> {
+        let (writer, writing) = match &self.config.file {
+            Some(file) => {
+                let appender = RollingFileAppender::builder()
+                    .rotation(file.rotation.into())
+                    .filename_prefix(&file.prefix)
+                    .build(&file.directory)
+                    .map_err(|e| ConfigError::invalid("log.file.directory", e))?;
+                let (writer, guard) = tracing_appender::non_blocking(appender);
+                (BoxMakeWriter::new(writer), Some(guard))
+            }
+            None => (BoxMakeWriter::new(std::io::stdout), None),
+        };
+        let mut layers = vec![self.output(writer)];
+        let (exporting, failed) = match self.version {
+            Some(version) if env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some() => {
+                match export(self.service, version) {
+                    Ok(export) => {
+                        layers.extend(export);
+                        (true, None)
+                    }
+                    Err(e) => (false, Some(e)),
+                }
+            }
+            _ => (false, None),
+        };
+        let redacted = REDACTED
+            .iter()
+            .map(|r| r.to_string())
+            .chain(self.config.redact.iter().map(|r| r.to_lowercase()))
+            .collect();
+        let filter = EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new(&self.config.filter));
+
+        LogTracer::init().expect("Failed to set logger");
+        let subscriber = tracing_subscriber::registry()
+            .with(Redact {
+                redacted,
+                inner: layers,
+            })
+            .with(filter);
+        set_global_default(subscriber).expect("Failed to set subscriber");
+        if let Some(e) = failed {
+            tracing::error!("unable to export over OTLP, only logging, {}", e);
+        }
+        Ok(LogGuard {
+            _writing: writing,
+            exporting,
+        })
+    }
+
+    fn output(&self, writer: BoxMakeWriter) -> BoxedLayer {
+        match self.config.format {
+            Format::Compact => tracing_subscriber::fmt::layer()
+                .compact()
+                .with_file(true)
+                .with_line_number(true)
+                .with_thread_ids(false)
+                .with_target(false)
+                .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
+                .with_writer(writer)
+                .boxed(),
+            Format::Pretty => tracing_subscriber::fmt::layer()
+                .pretty()
+                .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
+                .with_writer(writer)
+                .boxed(),
+            Format::Json => JsonStorageLayer
+                .and_then(BunyanFormattingLayer::new(self.service.to_string(), writer))
+                .boxed(),
+        }
+    }
+}
+
+/// Flushes the logs not written and the spans and logs not exported yet when dropped.
+#[must_use]
+pub struct LogGuard {
+    _writing: Option<WorkerGuard>,
+    exporting: bool,
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        if self.exporting {
+            opentelemetry::global::shutdown_tracer_provider();
+            opentelemetry::global::shutdown_logger_provider();
+        }
+    }
+}
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The exporter of one signal, for the protocol asked for.
+fn exporter<B>() -> Result<B, Box<dyn std::error::Error>>
+where
+    B: From<HttpExporterBuilder> + From<TonicExporterBuilder>,
+{
+    match env::var("OTEL_EXPORTER_OTLP_PROTOCOL").as_deref() {
+        Ok("http/protobuf") => Ok(opentelemetry_otlp::new_exporter()
+            .http()
+            .with_timeout(EXPORT_TIMEOUT)
+            .into()),
+        Ok("grpc") | Err(_) => Ok(opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_timeout(EXPORT_TIMEOUT)
+            .into()),
+        Ok(protocol) => Err(format!("unsupported OTLP protocol {}", protocol).into()),
+    }
+}
+
+fn export(
+    service: &'static str,
+    version: &'static str,
+) -> Result<Vec<BoxedLayer>, Box<dyn std::error::Error>> {
+    let resource = Resource::new([
+        KeyValue::new("service.name", service),
+        KeyValue::new("service.version", version),
+    ])
+    .merge(&Resource::from_detectors(
+        Duration::from_secs(0),
+        vec![
+            Box::new(TelemetryResourceDetector),
+            Box::new(EnvResourceDetector::new()),
+        ],
+    ));
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter::<SpanExporterBuilder>()?)
+        .with_trace_config(trace::config().with_resource(resource.clone()))
+        .install_batch(runtime::Tokio)?;
+    opentelemetry_otlp::new_pipeline()
+        .logging()
+        .with_exporter(exporter::<LogExporterBuilder>()?)
+        .with_log_config(logs::config().with_resource(resource))
+        .install_batch(runtime::Tokio)?;
+
+    // The exporters' own HTTP/2 traffic is not worth exporting, and would never settle
+    let exported = || {
+        Targets::new()
+            .with_default(LevelFilter::TRACE)
+            .with_target("h2", LevelFilter::OFF)
+            .with_target("hyper", LevelFilter::OFF)
+            .with_target("tonic", LevelFilter::OFF)
+            .with_target("tower", LevelFilter::OFF)
+            .with_target("reqwest", LevelFilter::OFF)
+            .with_target("opentelemetry", LevelFilter::OFF)
+    };
+    Ok(vec![
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(exported())
+            .boxed(),
+        opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new(
+            &opentelemetry::global::logger_provider(),
+        )
+        .with_filter(exported())
+        .boxed(),
+    ])
+}
+
+fn is_redacted(redacted: &[String], name: &str) -> bool {
+    let name = name.to_lowercase();
+    redacted.iter().any(|r| name.contains(r.as_str()))
+}
+
+/// A value of a field, kept to be passed on in place of the original.
+enum Captured {
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    Formatted(DisplayValue<String>),
+}
+
+impl Captured {
+    fn value(&self) -> &dyn Value {
+        match self {
+            Captured::I64(v) => v,
+            Captured::U64(v) => v,
+            Captured::I128(v) => v,
+            Captured::U128(v) => v,
+            Captured::F64(v) => v,
+            Captured::Bool(v) => v,
+            Captured::Str(v) => v,
+            Captured::Formatted(v) => v,
+        }
+    }
+}
+
+/// Copies the values of the fields, but the redacted ones.
+struct Capture<'a> {
+    redacted: &'a [String],
+    fields: Vec<(Field, Captured)>,
+}
+
+impl Capture<'_> {
+    fn push(&mut self, field: &Field, value: Captured) {
+        let value = match is_redacted(self.redacted, field.name()) {
+            true => Captured::Formatted(display("[redacted]".to_string())),
+            false => value,
+        };
+        self.fields.push((field.clone(), value));
+    }
+}
+
+impl Visit for Capture<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, Captured::F64(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, Captured::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, Captured::U64(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.push(field, Captured::I128(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.push(field, Captured::U128(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, Captured::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, Captured::Str(value.to_string()));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.push(field, Captured::Formatted(display(value.to_string())));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.push(field, Captured::Formatted(display(format!("{:?}", value))));
+    }
+}
+
+/// A callsite has at most 32 fields, the slots left over are skipped.
+const MAX_FIELDS: usize = 32;
+
+/// Hands the inner layers the events and spans with the values of the fields named like a
+/// secret replaced, so no format or export can leak them.
+struct Redact<L> {
+    redacted: Vec<String>,
+    inner: L,
+}
+
+impl<L> Redact<L> {
+    fn redacts(&self, metadata: &Metadata<'_>) -> bool {
+        metadata
+            .fields()
+            .iter()
+            .any(|f| is_redacted(&self.redacted, f.name()))
+    }
+
+    /// Calls `f` with a copy of the values recorded by `record`, the secrets redacted.
+    fn redact(
+        &self,
+        metadata: &'static Metadata<'static>,
+        record: impl FnOnce(&mut dyn Visit),
+        f: impl FnOnce(&ValueSet<'_>),
+    ) {
+        let mut capture = Capture {
+            redacted: &self.redacted,
+            fields: vec![],
+        };
+        record(&mut capture);
+        let Some(unused) = metadata.fields().iter().next() else {
+            return;
+        };
+        let mut values: [(&Field, Option<&dyn Value>); MAX_FIELDS] = [(&unused, None); MAX_FIELDS];
+        for (slot, (field, value)) in values.iter_mut().zip(&capture.fields) {
+            *slot = (field, Some(value.value()));
+        }
+        f(&metadata.fields().value_set(&values));
+    }
+}
+
+impl<S, L> Layer<S> for Redact<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S>,
+{
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if !self.redacts(attrs.metadata()) {
+            return self.inner.on_new_span(attrs, id, ctx);
+        }
+        self.redact(
+            attrs.metadata(),
+            |visitor| attrs.record(visitor),
+            |values| {
+                let redacted = match attrs.parent() {
+                    Some(parent) => Attributes::child_of(parent.clone(), attrs.metadata(), values),
+                    None if attrs.is_root() => Attributes::new_root(attrs.metadata(), values),
+                    None => Attributes::new(attrs.metadata(), values),
+                };
+                self.inner.on_new_span(&redacted, id, ctx)
+            },
+        );
+    }
+
+    fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        match ctx.metadata(span) {
+            Some(metadata) if self.redacts(metadata) => self.redact(
+                metadata,
+                |visitor| values.record(visitor),
+                |redacted| self.inner.on_record(span, &Record::new(redacted), ctx),
+            ),
+            _ => self.inner.on_record(span, values, ctx),
+        }
+    }
+
+    fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if !self.redacts(event.metadata()) {
+            return self.inner.on_event(event, ctx);
+        }
+        self.redact(
+            event.metadata(),
+            |visitor| event.record(visitor),
+            |values| {
+                let redacted = match event.parent() {
+                    Some(parent) => Event::new_child_of(parent.clone(), event.metadata(), values),
+                    None if event.is_root() => Event::new_child_of(None, event.metadata(), values),
+                    None => Event::new(event.metadata(), values),
+                };
+                self.inner.on_event(&redacted, ctx)
+            },
+        );
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        match id {
+            id if id == TypeId::of::<Self>() => Some(self as *const _ as *const ()),
+            _ => self.inner.downcast_raw(id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cfg::Validate;
+    use crate::log::{Builder, Format, LogConfig, Redact, REDACTED};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::writer::BoxMakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_redact() {
+        for format in [Format::Compact, Format::Pretty, Format::Json] {
+            let buffer = Buffer::default();
+            let writer = buffer.clone();
+            let config = LogConfig {
+                format,
+                redact: vec!["Email".to_string()],
+                ..LogConfig::default()
+            };
+            let output = Builder::new("test", config.clone())
+                .output(BoxMakeWriter::new(move || writer.clone()));
+            let redacted = REDACTED
+                .iter()
+                .map(|r| r.to_string())
+                .chain(config.redact.iter().map(|r| r.to_lowercase()))
+                .collect();
+            let subscriber = tracing_subscriber::registry().with(Redact {
+                redacted,
+                inner: vec![output],
+            });
+
+            tracing::subscriber::with_default(subscriber, || {
+                let span = tracing::info_span!("login", user_email = "someone@avocado.com");
+                let _entered = span.enter();
+                tracing::info!(password = "kIxv4NomLT0WwGKF", attempts = 3, "logging in");
+                tracing::info!(access_token = %"eyJhbGciOiJSUzI1NiJ9", "logged in");
+            });
+
+            let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+            assert!(output.contains("logging in"), "{}", output);
+            assert!(output.contains("attempts"), "{}", output);
+            assert!(output.contains("[redacted]"), "{}", output);
+            assert!(!output.contains("kIxv4NomLT0WwGKF"), "{}", output);
+            assert!(!output.contains("eyJhbGciOiJSUzI1NiJ9"), "{}", output);
+            assert!(!output.contains("someone@avocado.com"), "{}", output);
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(LogConfig::default().validate().is_ok());
+        let config = LogConfig {
+            filter: "info,avocado_user=loud".to_string(),
+            ..LogConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
 }
@@ -0,0 +1,291 @@
+use http::header::CONTENT_TYPE;
+use http::request::Parts;
+use http::{Request, Response, StatusCode};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::Body;
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower::{Layer, Service};
+
+type Scrape = Box<dyn Fn() + Send + Sync>;
+
+/// Names the method or route of a request, none for the unknown ones.
+pub type Label = fn(&Parts) -> Option<&str>;
+
+/// The method label of the requests a [`Label`] doesn't name, so random paths can't add series.
+pub const UNKNOWN: &str = "unknown";
+
+/// The metrics of a service, named `avocado_*` and labelled with the service, in the Prometheus
+/// text format.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    /// Update the gauges only worth reading when scraped
+    scrapes: Arc<Mutex<Vec<Scrape>>>,
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics").finish_non_exhaustive()
+    }
+}
+
+impl Metrics {
+    pub fn new(service: &str) -> Self {
+        let registry = Registry::new_custom(
+            Some("avocado".to_string()),
+            Some(HashMap::from([(
+                "service".to_string(),
+                service.to_string(),
+            )])),
+        )
+        .expect("invalid metrics registry");
+        let metrics = Metrics {
+            registry,
+            requests: IntCounterVec::new(
+                Opts::new("requests_total", "Requests by method and status"),
+                &["method", "status"],
+            )
+            .unwrap(),
+            request_duration: HistogramVec::new(
+                HistogramOpts::new("request_duration_seconds", "Request latencies by method"),
+                &["method"],
+            )
+            .unwrap(),
+            scrapes: Arc::new(Mutex::new(vec![])),
+        };
+        metrics.register(metrics.requests.clone());
+        metrics.register(metrics.request_duration.clone());
+        metrics
+    }
+
+    /// Adds a metric of the service, the names are checked once at startup so a clash panics.
+    pub fn register<C: Collector + Clone + 'static>(&self, collector: C) -> C {
+        self.registry
+            .register(Box::new(collector.clone()))
+            .expect("metric registered twice");
+        collector
+    }
+
+    /// Reports the open and idle connections of the pool.
+    pub fn observe_pool<DB: sqlx::Database>(&self, pool: sqlx::Pool<DB>) {
+        let connections = self.register(
+            IntGaugeVec::new(
+                Opts::new("db_pool_connections", "Connections of the database pool"),
+                &["state"],
+            )
+            .unwrap(),
+        );
+        self.scrapes.lock().unwrap().push(Box::new(move || {
+            let idle = pool.num_idle() as i64;
+            connections.with_label_values(&["idle"]).set(idle);
+            connections
+                .with_label_values(&["in_use"])
+                .set(pool.size() as i64 - idle);
+        }));
+    }
+
+    /// The current metrics in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        for scrape in self.scrapes.lock().unwrap().iter() {
+            scrape();
+        }
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("unable to encode the metrics");
+        String::from_utf8(buffer).expect("metrics are not utf-8")
+    }
+
+    /// Records the count and latency of the requests passing through, by the method `label`
+    /// names.
+    pub fn layer(&self, label: Label) -> MetricsLayer {
+        MetricsLayer {
+            metrics: self.clone(),
+            label,
+        }
+    }
+
+    fn respond<B>(&self, request: Request<B>) -> Response<Body> {
+        match request.uri().path() {
+            "/metrics" => Response::builder()
+                .header(CONTENT_TYPE, prometheus::TEXT_FORMAT)
+                .body(Body::from(self.encode())),
+            _ => Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty()),
+        }
+        .unwrap()
+    }
+}
+
+/// Serves the metrics at `/metrics`, for services without an HTTP server of their own.
+pub async fn serve(address: SocketAddr, metrics: Metrics) -> hyper::Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = metrics.respond(request);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+    hyper::Server::try_bind(&address)?.serve(make_service).await
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    metrics: Metrics,
+    label: Label,
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+            label: self.label,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: Metrics,
+    label: Label,
+}
+
+/// The gRPC status of calls failing upfront, `0` for the others as a stream may still fail in
+/// its trailers, or else the HTTP status.
+fn status<B>(response: &Response<B>, grpc: bool) -> String {
+    match response.headers().get("grpc-status") {
+        Some(status) => status.to_str().unwrap_or_default().to_string(),
+        None if grpc => "0".to_string(),
+        None => response.status().as_u16().to_string(),
+    }
+}
+
+impl<S, B, ResB> Service<Request<B>> for MetricsService<S>
+where
+    S: Service<Request<B>, Response = Response<ResB>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let (parts, body) = request.into_parts();
+        let method = (self.label)(&parts).unwrap_or(UNKNOWN).to_string();
+        let request = Request::from_parts(parts, body);
+        let grpc = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .is_some_and(|t| t.as_bytes().starts_with(b"application/grpc"));
+        let metrics = self.metrics.clone();
+        let start = Instant::now();
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await;
+            let status = match &response {
+                Ok(response) => status(response, grpc),
+                Err(_) => "error".to_string(),
+            };
+            metrics
+                .requests
+                .with_label_values(&[&method, &status])
+                .inc();
+            metrics
+                .request_duration
+                .with_label_values(&[&method])
+                .observe(start.elapsed().as_secs_f64());
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::Metrics;
+    use http::request::Parts;
+    use http::{Request, Response};
+    use prometheus::{IntCounter, Opts};
+    use std::convert::Infallible;
+    use tower::{service_fn, Layer, ServiceExt};
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let metrics = Metrics::new("test");
+        let logins =
+            metrics.register(IntCounter::with_opts(Opts::new("logins_total", "Logins")).unwrap());
+        logins.inc();
+
+        fn label(parts: &Parts) -> Option<&str> {
+            match parts.uri.path() {
+                path @ ("/user.User/Add" | "/health-check") => Some(path),
+                _ => None,
+            }
+        }
+        let service = metrics
+            .layer(label)
+            .layer(service_fn(|request: Request<()>| async move {
+                let response = Response::builder();
+                let response = match request.uri().path() {
+                    "/user.User/Add" => response.header("grpc-status", "3"),
+                    _ => response,
+                };
+                Ok::<_, Infallible>(response.body(()).unwrap())
+            }));
+        for path in [
+            "/user.User/Add",
+            "/health-check",
+            "/health-check",
+            "/random",
+        ] {
+            let request = Request::builder().uri(path);
+            let request = match path {
+                "/user.User/Add" => request.header("content-type", "application/grpc"),
+                _ => request,
+            };
+            service
+                .clone()
+                .oneshot(request.body(()).unwrap())
+                .await
+                .unwrap();
+        }
+
+        let text = metrics.encode();
+        assert!(text.contains(r#"avocado_logins_total{service="test"} 1"#));
+        assert!(text.contains(
+            r#"avocado_requests_total{method="/user.User/Add",status="3",service="test"} 1"#
+        ));
+        assert!(text.contains(
+            r#"avocado_requests_total{method="/health-check",status="200",service="test"} 2"#
+        ));
+        assert!(text.contains(
+            r#"avocado_request_duration_seconds_count{method="/health-check",service="test"} 2"#
+        ));
+        assert!(text
+            .contains(r#"avocado_requests_total{method="unknown",status="200",service="test"} 1"#));
+        assert!(!text.contains("/random"));
+    }
+}
@@ -0,0 +1,352 @@
+use crate::cfg::ConfigError;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// The header of the seconds to wait before calling again, in HTTP responses and gRPC metadata.
+pub const RETRY_AFTER: &str = "retry-after";
+
+/// A token bucket allowing `burst` calls at once, refilled with `per_minute` calls a minute.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// The rates of a route by what the calls are counted by, the missing ones don't limit.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Limits {
+    /// By the IP address of the caller
+    pub per_client: Option<Rate>,
+    /// By the email signing in
+    pub per_email: Option<Rate>,
+    /// By the authenticated user
+    pub per_user: Option<Rate>,
+}
+
+impl Limits {
+    /// Checks the rates are positive, `key` names the limits in the error.
+    pub fn validate(&self, key: &str) -> Result<(), ConfigError> {
+        let rates = [
+            ("per_client", &self.per_client),
+            ("per_email", &self.per_email),
+            ("per_user", &self.per_user),
+        ];
+        for (name, rate) in rates {
+            if let Some(rate) = rate {
+                if rate.burst == 0 || rate.per_minute == 0 {
+                    return Err(ConfigError::invalid(
+                        &format!("{}.{}", key, name),
+                        "needs a positive burst and per_minute",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn client(&self, backend: &dyn Backend, route: &str, ip: IpAddr) -> Result<(), Limited> {
+        take(backend, &self.per_client, || {
+            format!("{} client {}", route, ip)
+        })
+    }
+
+    /// Emails are counted regardless of their case.
+    pub fn email(&self, backend: &dyn Backend, route: &str, email: &str) -> Result<(), Limited> {
+        take(backend, &self.per_email, || {
+            format!("{} email {}", route, email.to_lowercase())
+        })
+    }
+
+    pub fn user(&self, backend: &dyn Backend, route: &str, user_id: &str) -> Result<(), Limited> {
+        take(backend, &self.per_user, || {
+            format!("{} user {}", route, user_id)
+        })
+    }
+}
+
+fn take(
+    backend: &dyn Backend,
+    rate: &Option<Rate>,
+    key: impl FnOnce() -> String,
+) -> Result<(), Limited> {
+    match rate {
+        Some(rate) => backend.take(&key(), rate),
+        None => Ok(()),
+    }
+}
+
+/// A call refused for exceeding its rate.
+#[derive(Debug, Error, Clone, Copy, PartialEq)]
+#[error("too many requests, retry in {secs} seconds", secs = self.retry_after_secs())]
+pub struct Limited {
+    pub retry_after: Duration,
+}
+
+impl Limited {
+    /// The whole seconds to wait, at least one.
+    pub fn retry_after_secs(&self) -> u64 {
+        (self.retry_after.as_millis() as u64).div_ceil(1000).max(1)
+    }
+}
+
+impl From<Limited> for tonic::Status {
+    fn from(limited: Limited) -> Self {
+        let mut status = tonic::Status::resource_exhausted(limited.to_string());
+        status
+            .metadata_mut()
+            .insert(RETRY_AFTER, limited.retry_after_secs().into());
+        status
+    }
+}
+
+/// Where the buckets are kept.
+pub trait Backend: Debug + Send + Sync {
+    /// Takes a call from the bucket of `key`, or tells how long until it has one.
+    fn take(&self, key: &str, rate: &Rate) -> Result<(), Limited>;
+}
+
+/// How often the buckets full again are dropped, they are the same as new ones.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many buckets are kept at most by default.
+const CAPACITY: usize = 100000;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    calls: f64,
+    updated_at: Instant,
+    /// When the bucket is refilled up to its burst
+    full_at: Instant,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    by_key: HashMap<String, Bucket>,
+    /// The keys in the order their buckets were made, the oldest first
+    order: VecDeque<String>,
+    pruned_at: Instant,
+}
+
+impl Buckets {
+    fn prune(&mut self, now: Instant) {
+        self.by_key.retain(|_, bucket| bucket.full_at > now);
+        let by_key = &self.by_key;
+        self.order.retain(|key| by_key.contains_key(key));
+        self.pruned_at = now;
+    }
+}
+
+/// Keeps the buckets in memory, each node of a deployment counts the calls it gets on its own.
+#[derive(Debug)]
+pub struct Memory {
+    buckets: Mutex<Buckets>,
+    capacity: usize,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Memory::with_capacity(CAPACITY)
+    }
+}
+
+impl Memory {
+    /// Keeps at most `capacity` buckets, the oldest ones make room for new ones when full.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Memory {
+            buckets: Mutex::new(Buckets {
+                by_key: HashMap::new(),
+                order: VecDeque::new(),
+                pruned_at: Instant::now(),
+            }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn take_at(&self, key: &str, rate: &Rate, now: Instant) -> Result<(), Limited> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.pruned_at) >= PRUNE_INTERVAL {
+            buckets.prune(now);
+        }
+        if !buckets.by_key.contains_key(key) {
+            while buckets.by_key.len() >= self.capacity {
+                let Some(oldest) = buckets.order.pop_front() else {
+                    break;
+                };
+                buckets.by_key.remove(&oldest);
+            }
+            buckets.order.push_back(key.to_string());
+            buckets.by_key.insert(
+                key.to_string(),
+                Bucket {
+                    calls: rate.burst as f64,
+                    updated_at: now,
+                    full_at: now,
+                },
+            );
+        }
+        let per_second = rate.per_minute as f64 / 60.0;
+        let bucket = buckets.by_key.get_mut(key).unwrap();
+        let refilled = now
+            .saturating_duration_since(bucket.updated_at)
+            .as_secs_f64()
+            * per_second;
+        bucket.calls = (bucket.calls + refilled).min(rate.burst as f64);
+        bucket.updated_at = now;
+        if bucket.calls < 1.0 {
+            return Err(Limited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.calls) / per_second),
+            });
+        }
+        bucket.calls -= 1.0;
+        bucket.full_at =
+            now + Duration::from_secs_f64((rate.burst as f64 - bucket.calls) / per_second);
+        Ok(())
+    }
+}
+
+impl Backend for Memory {
+    fn take(&self, key: &str, rate: &Rate) -> Result<(), Limited> {
+        self.take_at(key, rate, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rate_limit::{Limits, Memory, Rate, PRUNE_INTERVAL};
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_bucket() {
+        let memory = Memory::default();
+        let rate = Rate {
+            burst: 2,
+            per_minute: 6,
+        };
+        let now = Instant::now();
+        assert!(memory.take_at("a", &rate, now).is_ok());
+        assert!(memory.take_at("a", &rate, now).is_ok());
+        let limited = memory.take_at("a", &rate, now).unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_secs(10));
+        assert_eq!(limited.retry_after_secs(), 10);
+        // Other keys have buckets of their own
+        assert!(memory.take_at("b", &rate, now).is_ok());
+
+        // One call comes back every 10 seconds
+        let later = now + Duration::from_secs(5);
+        let limited = memory.take_at("a", &rate, later).unwrap_err();
+        assert_eq!(limited.retry_after_secs(), 5);
+        assert!(memory
+            .take_at("a", &rate, now + Duration::from_secs(10))
+            .is_ok());
+        assert!(memory
+            .take_at("a", &rate, now + Duration::from_secs(10))
+            .is_err());
+
+        // Never more than the burst
+        let much_later = now + Duration::from_secs(3600);
+        assert!(memory.take_at("a", &rate, much_later).is_ok());
+        assert!(memory.take_at("a", &rate, much_later).is_ok());
+        assert!(memory.take_at("a", &rate, much_later).is_err());
+    }
+
+    #[test]
+    fn test_prune() {
+        let memory = Memory::default();
+        let rate = Rate {
+            burst: 1,
+            per_minute: 1,
+        };
+        let now = memory.buckets.lock().unwrap().pruned_at;
+        for key in ["0", "1", "2"] {
+            memory.take_at(key, &rate, now).unwrap();
+        }
+        memory
+            .take_at("later", &rate, now + Duration::from_secs(30))
+            .unwrap();
+        assert_eq!(memory.buckets.lock().unwrap().by_key.len(), 4);
+
+        // The buckets full again are dropped once the interval passed, the others kept
+        memory.take_at("new", &rate, now + PRUNE_INTERVAL).unwrap();
+        let buckets = memory.buckets.lock().unwrap();
+        assert_eq!(buckets.by_key.len(), 2);
+        assert_eq!(buckets.order, ["later", "new"]);
+        drop(buckets);
+        memory
+            .take_at("later", &rate, now + PRUNE_INTERVAL)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_capacity() {
+        let memory = Memory::with_capacity(2);
+        let rate = Rate {
+            burst: 1,
+            per_minute: 1,
+        };
+        let now = Instant::now();
+        memory.take_at("a", &rate, now).unwrap();
+        memory.take_at("b", &rate, now).unwrap();
+        // The oldest bucket makes room for the new one
+        memory.take_at("c", &rate, now).unwrap();
+        assert_eq!(memory.buckets.lock().unwrap().by_key.len(), 2);
+        assert!(memory.take_at("b", &rate, now).is_err());
+        assert!(memory.take_at("c", &rate, now).is_err());
+        assert!(memory.take_at("a", &rate, now).is_ok());
+    }
+
+    #[test]
+    fn test_limits() {
+        let memory = Memory::default();
+        let rate = Some(Rate {
+            burst: 1,
+            per_minute: 1,
+        });
+        let limits = Limits {
+            per_client: rate,
+            per_email: rate,
+            per_user: None,
+        };
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert!(limits.client(&memory, "/login", ip).is_ok());
+        assert!(limits.client(&memory, "/login", ip).is_err());
+        assert!(limits.client(&memory, "/refresh", ip).is_ok());
+        assert!(limits.email(&memory, "/login", "Admin@avocado.com").is_ok());
+        assert!(limits
+            .email(&memory, "/login", "admin@avocado.com")
+            .is_err());
+        for _ in 0..3 {
+            assert!(limits.user(&memory, "/login", "someone").is_ok());
+        }
+
+        let status = tonic::Status::from(
+            limits
+                .email(&memory, "/login", "admin@avocado.com")
+                .unwrap_err(),
+        );
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(status.metadata().get("retry-after").unwrap(), "60");
+
+        let invalid = Limits {
+            per_user: Some(Rate {
+                burst: 0,
+                per_minute: 1,
+            }),
+            ..Limits::default()
+        };
+        assert_eq!(
+            invalid
+                .validate("rate_limit.login")
+                .unwrap_err()
+                .to_string(),
+            "invalid configuration of rate_limit.login.per_user: needs a positive burst and \
+             per_minute"
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use std::future::Future;
+use std::time::Duration;
+
+/// Resolves once the process is asked to stop, by SIGTERM or Ctrl-C.
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("unable to listen for SIGTERM");
+        tokio::select! {
+            _ = terminate.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("unable to listen for Ctrl-C");
+}
+
+/// Awaits a server which stops accepting connections once `stopping` resolves, then gives the
+/// requests in flight until the deadline to finish before dropping them.
+pub async fn drain<E>(
+    server: impl Future<Output = Result<(), E>>,
+    stopping: impl Future,
+    deadline: Duration,
+) -> Result<(), E> {
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => return result,
+        _ = stopping => {}
+    }
+    match tokio::time::timeout(deadline, server).await {
+        Ok(result) => result,
+        Err(_) => {
+            tracing::warn!(
+                "dropping the requests still in flight after {}ms",
+                deadline.as_millis()
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shutdown::drain;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+    use tokio::time::{sleep, Instant};
+
+    #[tokio::test]
+    async fn test_drain() {
+        // Servers finishing their requests in time are awaited
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = async {
+            sleep(Duration::from_millis(50)).await;
+            let _ = tx.send(());
+            sleep(Duration::from_millis(50)).await;
+            Ok::<(), ()>(())
+        };
+        let start = Instant::now();
+        drain(server, rx, Duration::from_secs(5)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        // The others are dropped at the deadline
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = async {
+            let _ = tx.send(());
+            std::future::pending::<Result<(), ()>>().await
+        };
+        let start = Instant::now();
+        drain(server, rx, Duration::from_millis(100)).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
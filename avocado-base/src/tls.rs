@@ -0,0 +1,40 @@
+use crate::cfg::{read_secret_file, ConfigError};
+use serde::Deserialize;
+use std::path::PathBuf;
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+/// The PEM files to connect to a server over TLS with.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClientTls {
+    /// The CA which issued the certificate of the server
+    pub ca_file: PathBuf,
+    /// The name in the certificate of the server, the host of the address when omitted
+    pub domain: Option<String>,
+    /// The certificate and key of the client, for servers verifying their clients
+    pub cert_file: Option<PathBuf>,
+    pub key_file: Option<PathBuf>,
+}
+
+impl ClientTls {
+    /// Reads the files, `key` names these settings in the errors.
+    pub fn load(&self, key: &str) -> Result<ClientTlsConfig, ConfigError> {
+        let ca = read_secret_file(&format!("{}.ca_file", key), &self.ca_file)?;
+        let config = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(ca));
+        let config = match &self.domain {
+            Some(domain) => config.domain_name(domain),
+            None => config,
+        };
+        match (&self.cert_file, &self.key_file) {
+            (Some(cert_file), Some(key_file)) => {
+                let cert = read_secret_file(&format!("{}.cert_file", key), cert_file)?;
+                let key = read_secret_file(&format!("{}.key_file", key), key_file)?;
+                Ok(config.identity(Identity::from_pem(cert, key)))
+            }
+            (None, None) => Ok(config),
+            _ => Err(ConfigError::invalid(
+                key,
+                "cert_file and key_file go together",
+            )),
+        }
+    }
+}
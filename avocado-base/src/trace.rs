@@ -0,0 +1,361 @@
+use http::header::HeaderName;
+use http::{HeaderMap, HeaderValue, Request, Response};
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tonic::service::Interceptor;
+use tower::{Layer, Service};
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+pub const REQUEST_ID: &str = "x-request-id";
+pub const TRACEPARENT: &str = "traceparent";
+pub const TRACESTATE: &str = "tracestate";
+
+tokio::task_local! {
+    static CURRENT: TraceContext;
+}
+
+/// The [W3C trace context](https://www.w3.org/TR/trace-context/) of a request, with the id
+/// linking its logs across the services.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceContext {
+    pub request_id: String,
+    pub trace_id: String,
+    /// The span of this service, the parent of the calls it makes
+    pub span_id: String,
+    /// The span of the caller, none when the trace starts here
+    pub parent_id: Option<String>,
+    pub flags: u8,
+    pub tracestate: Option<String>,
+}
+
+fn random_hex(len: usize) -> String {
+    Uuid::new_v4().simple().to_string()[..len].to_string()
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        && value.bytes().any(|b| b != b'0')
+}
+
+/// The trace id, parent id and flags of a valid `traceparent`.
+fn parse_traceparent(value: &str) -> Option<(String, String, u8)> {
+    let parts: Vec<_> = value.split('-').collect();
+    match parts[..] {
+        [version, trace_id, parent_id, flags, ..]
+            if version.len() == 2
+                && version != "ff"
+                && version.bytes().all(|b| b.is_ascii_hexdigit())
+                && (version != "00" || parts.len() == 4)
+                && is_hex(trace_id, 32)
+                && is_hex(parent_id, 16)
+                && flags.len() == 2 =>
+        {
+            let flags = u8::from_str_radix(flags, 16).ok()?;
+            Some((trace_id.to_string(), parent_id.to_string(), flags))
+        }
+        _ => None,
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+impl TraceContext {
+    /// Continues the trace of the caller, or starts a new one when it sent none or an invalid
+    /// one. The request id of the caller is kept too.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let request_id = header(headers, REQUEST_ID)
+            .filter(|id| !id.is_empty() && id.len() <= 128)
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let (trace_id, parent_id, flags, tracestate) =
+            match header(headers, TRACEPARENT).and_then(parse_traceparent) {
+                Some((trace_id, parent_id, flags)) => (
+                    trace_id,
+                    Some(parent_id),
+                    flags,
+                    header(headers, TRACESTATE).map(str::to_string),
+                ),
+                None => (random_hex(32), None, 1, None),
+            };
+        TraceContext {
+            request_id,
+            trace_id,
+            span_id: random_hex(16),
+            parent_id,
+            flags,
+            tracestate,
+        }
+    }
+
+    /// The context of the request being handled, if any.
+    pub fn current() -> Option<TraceContext> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// The span of the caller, which the exported span continues.
+    fn remote(&self) -> opentelemetry::Context {
+        let trace_id = TraceId::from_hex(&self.trace_id);
+        let parent_id = self.parent_id.as_deref().map(SpanId::from_hex);
+        match (trace_id, parent_id) {
+            (Ok(trace_id), Some(Ok(parent_id))) => opentelemetry::Context::new()
+                .with_remote_span_context(SpanContext::new(
+                    trace_id,
+                    parent_id,
+                    TraceFlags::new(self.flags),
+                    true,
+                    self.tracestate
+                        .as_deref()
+                        .and_then(|s| s.parse().ok())
+                        .unwrap_or_default(),
+                )),
+            _ => opentelemetry::Context::new(),
+        }
+    }
+
+    /// Takes the ids of the span exported over OTLP, if any, for the calls made to be its
+    /// children.
+    fn adopt(&mut self, span: &Span) {
+        let context = span.context();
+        let exported = context.span().span_context().clone();
+        if exported.is_valid() {
+            self.trace_id = exported.trace_id().to_string();
+            self.span_id = exported.span_id().to_string();
+            self.flags = exported.trace_flags().to_u8();
+        }
+    }
+
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.flags)
+    }
+
+    /// Writes the context for the next service, or back to the caller.
+    pub fn inject(&self, headers: &mut HeaderMap) {
+        let mut insert = |name: &'static str, value: &str| {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(HeaderName::from_static(name), value);
+            }
+        };
+        insert(REQUEST_ID, &self.request_id);
+        insert(TRACEPARENT, &self.traceparent());
+        if let Some(tracestate) = &self.tracestate {
+            insert(TRACESTATE, tracestate);
+        }
+    }
+}
+
+/// Spans each request with its trace context, which is also in the request extensions and
+/// [`TraceContext::current`], and echoed in the response headers.
+#[derive(Debug, Clone, Default)]
+pub struct TraceLayer;
+
+impl<S> Layer<S> for TraceLayer {
+    type Service = TraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceService<S> {
+    inner: S,
+}
+
+impl<S, B, ResB> Service<Request<B>> for TraceService<S>
+where
+    S: Service<Request<B>, Response = Response<ResB>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let mut context = TraceContext::from_headers(request.headers());
+        let span = {
+            let _remote = context.remote().attach();
+            tracing::info_span!(
+                "request",
+                request_id = %context.request_id,
+                trace_id = Empty,
+                span_id = Empty,
+                parent_id = context.parent_id.as_deref().unwrap_or_default(),
+                path = %request.uri().path(),
+            )
+        };
+        context.adopt(&span);
+        span.record("trace_id", context.trace_id.as_str());
+        span.record("span_id", context.span_id.as_str());
+        request.extensions_mut().insert(context.clone());
+        let response = CURRENT.sync_scope(context.clone(), || {
+            span.in_scope(|| self.inner.call(request))
+        });
+        Box::pin(CURRENT.scope(context.clone(), async move {
+            let mut response = response.instrument(span).await?;
+            context.inject(response.headers_mut());
+            Ok(response)
+        }))
+    }
+}
+
+/// Passes the trace context of the request being handled on to the gRPC calls it makes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Propagate;
+
+impl Interceptor for Propagate {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        if let Some(context) = TraceContext::current() {
+            let mut headers = std::mem::take(request.metadata_mut()).into_headers();
+            context.inject(&mut headers);
+            *request.metadata_mut() = tonic::metadata::MetadataMap::from_headers(headers);
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trace::{Propagate, TraceContext, TraceLayer};
+    use http::{HeaderMap, Request, Response};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use std::convert::Infallible;
+    use tonic::service::Interceptor;
+    use tower::{service_fn, Layer, ServiceExt};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+                .parse()
+                .unwrap(),
+        );
+        headers.insert("tracestate", "congo=t61rcWkgMzE".parse().unwrap());
+        headers.insert("x-request-id", "abc".parse().unwrap());
+        let context = TraceContext::from_headers(&headers);
+        assert_eq!(context.request_id, "abc");
+        assert_eq!(context.trace_id, "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(context.parent_id.as_deref(), Some("b7ad6b7169203331"));
+        assert_ne!(context.span_id, "b7ad6b7169203331");
+        assert_eq!(context.tracestate.as_deref(), Some("congo=t61rcWkgMzE"));
+        assert_eq!(
+            context.traceparent(),
+            format!("00-0af7651916cd43dd8448eb211c80319c-{}-01", context.span_id)
+        );
+
+        // An invalid trace parent starts a new trace, dropping the trace state
+        for invalid in [
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "garbage",
+        ] {
+            headers.insert("traceparent", invalid.parse().unwrap());
+            let context = TraceContext::from_headers(&headers);
+            assert_ne!(context.trace_id, "0af7651916cd43dd8448eb211c80319c");
+            assert_eq!(context.trace_id.len(), 32);
+            assert_eq!(context.parent_id, None);
+            assert_eq!(context.tracestate, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layer() {
+        let service = TraceLayer.layer(service_fn(|request: Request<()>| async move {
+            let context = request.extensions().get::<TraceContext>().cloned().unwrap();
+            assert_eq!(TraceContext::current(), Some(context.clone()));
+
+            // The calls made while handling the request continue its trace
+            let outgoing = Propagate.call(tonic::Request::new(())).unwrap();
+            let parent = outgoing.metadata().get("traceparent").unwrap();
+            assert_eq!(parent.to_str().unwrap(), context.traceparent());
+            Ok::<_, Infallible>(Response::new(context))
+        }));
+        let request = Request::builder()
+            .header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )
+            .body(())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        let context = response.body();
+        assert_eq!(context.trace_id, "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(
+            response.headers()["x-request-id"],
+            context.request_id.as_str()
+        );
+        assert_eq!(
+            response.headers()["traceparent"],
+            context.traceparent().as_str()
+        );
+
+        assert_eq!(TraceContext::current(), None);
+        let outgoing = Propagate.call(tonic::Request::new(())).unwrap();
+        assert!(outgoing.metadata().get("traceparent").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exported_span() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _subscriber = tracing::subscriber::set_default(subscriber);
+
+        let service = TraceLayer.layer(service_fn(|_: Request<()>| async {
+            Ok::<_, Infallible>(Response::new(()))
+        }));
+        let request = Request::builder()
+            .header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )
+            .body(())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+
+        // The exported span continues the caller's trace, and is the parent of the next calls
+        provider.force_flush();
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(
+            span.span_context.trace_id().to_string(),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        assert_eq!(span.parent_span_id.to_string(), "b7ad6b7169203331");
+        assert_eq!(
+            response.headers()["traceparent"],
+            format!(
+                "00-0af7651916cd43dd8448eb211c80319c-{}-01",
+                span.span_context.span_id()
+            )
+            .as_str()
+        );
+    }
+}
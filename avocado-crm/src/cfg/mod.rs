@@ -1,33 +1,153 @@
+use avocado_base::cfg::{ConfigError, Loader, Validate};
+use avocado_base::rate_limit::Limits;
+use avocado_base::tls::ClientTls;
+use avocado_base::trace::Propagate;
+use axum::http::HeaderValue;
 use serde::Deserialize;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::{Channel, Endpoint, Uri};
+
+/// A channel to the user service, passing the trace context of the request on.
+pub(crate) type UserChannel = InterceptedService<Channel, Propagate>;
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct Server {
+    pub(crate) address: SocketAddr,
+    /// How long the requests in flight get to finish when shutting down
+    drain_timeout_ms: u64,
+}
+
+impl Server {
+    pub(crate) fn drain_timeout(&self) -> Duration {
+        Duration::from_millis(self.drain_timeout_ms)
+    }
+}
+
+const TLS: &str = "service_address.tls";
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct ServiceAddress {
     pub(crate) user: String,
+    /// Needed by `https` addresses
+    pub(crate) tls: Option<ClientTls>,
+}
+
+impl ServiceAddress {
+    /// Connects to the user service.
+    pub(crate) async fn connect(&self) -> anyhow::Result<UserChannel> {
+        let endpoint = Endpoint::from_shared(self.user.clone())?;
+        let endpoint = match &self.tls {
+            Some(tls) => endpoint.tls_config(tls.load(TLS)?)?,
+            None => endpoint,
+        };
+        Ok(InterceptedService::new(
+            endpoint.connect().await?,
+            Propagate,
+        ))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct Cors {
+    /// The origins of the web apps allowed to call the API, a comma separated list when set
+    /// through `AVOCADO_CORS__ALLOWED_ORIGINS`
+    pub(crate) allowed_origins: Vec<String>,
+}
+
+impl Cors {
+    pub(crate) fn allows(&self, origin: &HeaderValue) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|o| o.as_bytes() == origin.as_bytes())
+    }
+}
+
+/// The limits of the login, which is open to guessing passwords.
+#[derive(Deserialize, Debug)]
+pub(crate) struct RateLimit {
+    pub(crate) login: Limits,
 }
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct Config {
+    pub(crate) server: Server,
     pub(crate) service_address: ServiceAddress,
+    pub(crate) cors: Cors,
+    pub(crate) rate_limit: RateLimit,
 }
 
+/// The defaults of every build, files and environment variables override these.
+const DEFAULTS: &str = r#"
+server:
+  address: "127.0.0.1:3000"
+  drain_timeout_ms: 30000
+service_address:
+  user: "http://[::1]:50051"
+cors:
+  allowed_origins:
+    - "http://127.0.0.1:5173"
+rate_limit:
+  login:
+    per_client: { burst: 10, per_minute: 10 }
+    per_email: { burst: 10, per_minute: 10 }
+"#;
+
 impl Config {
-    #[cfg(not(any(test, debug_assertions)))]
-    pub(crate) fn new() -> Self {
-        let config = config::Config::builder()
-            .add_source(config::File::new(
-                "config.crm.yaml",
-                config::FileFormat::Yaml,
-            ))
-            .build()
-            .unwrap();
-        config.try_deserialize::<Config>().unwrap()
+    /// Loads the configuration from `config.crm.yaml`, or the file named by
+    /// `AVOCADO_CRM_CONFIG`, and the `AVOCADO_` environment variables.
+    pub(crate) fn loader() -> Loader {
+        Loader::new(&[DEFAULTS], "config.crm.yaml", "AVOCADO_CRM_CONFIG")
+            .with_list_key("cors.allowed_origins")
     }
+}
 
-    #[cfg(any(test, debug_assertions))]
-    pub(crate) fn new() -> Self {
-        let service_address = ServiceAddress {
-            user: "http://[::1]:50051".to_string(),
-        };
-        Config { service_address }
+impl Validate for Config {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.server.drain_timeout_ms == 0 {
+            return Err(ConfigError::invalid(
+                "server.drain_timeout_ms",
+                "needs to be positive",
+            ));
+        }
+        let uri = self
+            .service_address
+            .user
+            .parse::<Uri>()
+            .map_err(|e| ConfigError::invalid("service_address.user", e))?;
+        if !matches!(uri.scheme_str(), Some("http") | Some("https")) {
+            return Err(ConfigError::invalid(
+                "service_address.user",
+                "needs to be an http or https address",
+            ));
+        }
+        match &self.service_address.tls {
+            Some(tls) => {
+                tls.load(TLS)?;
+            }
+            None if uri.scheme_str() == Some("https") => {
+                return Err(ConfigError::invalid(TLS, "is needed by an https address"));
+            }
+            None => {}
+        }
+        self.rate_limit.login.validate("rate_limit.login")?;
+        for origin in &self.cors.allowed_origins {
+            let uri = origin
+                .parse::<Uri>()
+                .map_err(|e| ConfigError::invalid("cors.allowed_origins", e))?;
+            if uri.scheme().is_none()
+                || uri.host().is_none()
+                || uri.path() != "/"
+                || uri.query().is_some()
+                || origin.ends_with('/')
+            {
+                return Err(ConfigError::invalid(
+                    "cors.allowed_origins",
+                    format!("{} needs to be a scheme and host without a path", origin),
+                ));
+            }
+        }
+        Ok(())
     }
 }
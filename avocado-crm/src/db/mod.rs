@@ -12,4 +12,6 @@ pub(crate) trait SessionStore: Send + Sync + Debug {
     async fn get(&self, session_id: &SessionId) -> Result<Option<Session>>;
     async fn delete(&self, session_id: &SessionId) -> Result<()>;
     async fn logout(&self, user_id: &UserId) -> Result<()>;
+    /// Waits for the queries in flight, when shutting down.
+    async fn close(&self);
 }
@@ -22,7 +22,7 @@ enum SessionTable {
     Email,
     FirstName,
     LastName,
-    Role,
+    Roles,
 }
 
 #[derive(sqlx::FromRow, Debug)]
@@ -37,7 +37,7 @@ struct SessionSqlite {
     email: String,
     first_name: String,
     last_name: String,
-    role: String,
+    roles: String,
 }
 
 impl From<SessionSqlite> for Session {
@@ -53,7 +53,7 @@ impl From<SessionSqlite> for Session {
             email: value.email,
             first_name: value.first_name,
             last_name: value.last_name,
-            role: value.role,
+            roles: serde_json::from_str(&value.roles).unwrap_or_default(),
         }
     }
 }
@@ -89,7 +89,7 @@ impl Store {
             .col(ColumnDef::new(SessionTable::Email).string())
             .col(ColumnDef::new(SessionTable::FirstName).string())
             .col(ColumnDef::new(SessionTable::LastName).string())
-            .col(ColumnDef::new(SessionTable::Role).string())
+            .col(ColumnDef::new(SessionTable::Roles).string())
             .build(SqliteQueryBuilder);
         sqlx::query(&sql).execute(&pool).await.unwrap();
         Index::create()
@@ -111,9 +111,14 @@ impl Store {
             SessionTable::Email,
             SessionTable::FirstName,
             SessionTable::LastName,
-            SessionTable::Role,
+            SessionTable::Roles,
         ]
     }
+
+    /// The pool behind the store, for the metrics to report on.
+    pub(crate) fn pool(&self) -> &Pool<Sqlite> {
+        &self.pool
+    }
 }
 
 #[async_trait]
@@ -133,7 +138,7 @@ impl SessionStore for Store {
                 session.email.into(),
                 session.first_name.into(),
                 session.last_name.into(),
-                session.role.into(),
+                serde_json::to_string(&session.roles)?.into(),
             ])?
             .build_sqlx(SqliteQueryBuilder);
         sqlx::query_with(&sql, values).execute(&self.pool).await?;
@@ -159,7 +164,10 @@ impl SessionStore for Store {
                 (SessionTable::Email, session.email.into()),
                 (SessionTable::FirstName, session.first_name.into()),
                 (SessionTable::LastName, session.last_name.into()),
-                (SessionTable::Role, session.role.into()),
+                (
+                    SessionTable::Roles,
+                    serde_json::to_string(&session.roles)?.into(),
+                ),
             ])
             .and_where(Expr::col(SessionTable::Id).eq(session.id))
             .build_sqlx(SqliteQueryBuilder);
@@ -197,4 +205,8 @@ impl SessionStore for Store {
         sqlx::query_with(&sql, values).execute(&self.pool).await?;
         Ok(())
     }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
 }
@@ -1,41 +1,61 @@
+use avocado_base::rate_limit::{Limited, RETRY_AFTER};
+use avocado_base::trace::TraceContext;
 use axum::extract::rejection::JsonRejection;
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde_json::{json, Value};
-use std::collections::HashMap;
 use tonic::{Code, Status};
 
 pub(crate) struct JsonError(anyhow::Error);
 
+/// The body of an error response, with the request id to find its logs by.
+pub(crate) fn error_body(error: impl Into<Value>) -> Json<Value> {
+    let request_id = TraceContext::current().map(|c| c.request_id);
+    Json(json!({"error": error.into(), "request_id": request_id}))
+}
+
 impl IntoResponse for JsonError {
     fn into_response(self) -> Response {
         tracing::error!("App Error: {:?}", self.0);
         if let Some(s) = self.0.downcast_ref::<Status>() {
             tonic_status_to_response(s)
+        } else if let Some(l) = self.0.downcast_ref::<Limited>() {
+            too_many_requests(l.to_string(), Some(l.retry_after_secs().into()))
         } else if let Some(j) = self.0.downcast_ref::<JsonRejection>() {
             json_rejection_to_response(j)
         } else {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": format!("{}", self.0),
-                })),
+                error_body(format!("{}", self.0)),
             )
                 .into_response()
         }
     }
 }
 
+/// A 429 telling when to retry, from a limit of the CRM or of the user service.
+fn too_many_requests(message: String, retry_after: Option<HeaderValue>) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, error_body(message)).into_response();
+    if let Some(retry_after) = retry_after {
+        response.headers_mut().insert(RETRY_AFTER, retry_after);
+    }
+    response
+}
+
 fn tonic_status_to_response(status: &Status) -> Response {
-    let message = Json(json!({"error": status.message()}));
+    let message = error_body(status.message());
     match status.code() {
+        Code::ResourceExhausted => {
+            let retry_after = status
+                .metadata()
+                .get(RETRY_AFTER)
+                .and_then(|value| HeaderValue::from_bytes(value.as_bytes()).ok());
+            return too_many_requests(status.message().to_string(), retry_after);
+        }
         Code::InvalidArgument => {
             if let Ok(error) = serde_json::from_str::<Value>(status.message()) {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(serde_json::to_value(HashMap::from([("error", error)])).unwrap()),
-                )
+                (StatusCode::BAD_REQUEST, error_body(error))
             } else {
                 (StatusCode::BAD_REQUEST, message)
             }
@@ -50,7 +70,7 @@ fn tonic_status_to_response(status: &Status) -> Response {
 }
 
 fn json_rejection_to_response(error: &JsonRejection) -> Response {
-    let message = Json(json!({"error": error.to_string()}));
+    let message = error_body(error.to_string());
     match error {
         JsonRejection::JsonDataError(_) => (StatusCode::UNPROCESSABLE_ENTITY, message),
         JsonRejection::JsonSyntaxError(_) => (StatusCode::BAD_REQUEST, message),
@@ -1,17 +1,28 @@
+use crate::cfg::Config;
 use crate::db::sqlite::session::Store as SessionStore;
 use crate::middleware::auth::auth;
-use crate::state::State;
+use crate::middleware::rate_limit::{rate_limit, LOGIN};
+use crate::state::State as AppState;
+use avocado_base::cfg::{watch, ConfigError};
+use avocado_base::log::LogConfig;
+use avocado_base::metrics::Metrics;
+use avocado_base::shutdown::{self, drain};
+use avocado_base::trace::TraceLayer;
+use axum::extract::{MatchedPath, State};
 use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, COOKIE};
-use axum::http::{HeaderValue, Method, StatusCode};
+use axum::http::request::Parts;
+use axum::http::{Method, StatusCode};
 use axum::middleware::from_fn_with_state;
 use axum::response::IntoResponse;
-use axum::routing::{get, post, IntoMakeService};
+use axum::routing::{get, post};
 use axum::Router;
-use hyper::server::conn::AddrIncoming;
-use hyper::Server;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::oneshot;
 use tower::ServiceBuilder;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
 mod cfg;
 mod cmd;
@@ -22,29 +33,103 @@ mod session;
 mod state;
 mod user;
 
-pub async fn run(address: SocketAddr) -> Server<AddrIncoming, IntoMakeService<Router>> {
-    let state = State::new(SessionStore::new().await);
+/// How often the configuration file is checked for changes, SIGHUP reloads it right away.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reads the `log` section, which is needed before the subscriber is installed and the server
+/// starts.
+pub fn log_config() -> Result<LogConfig, ConfigError> {
+    LogConfig::load(&Config::loader())
+}
+
+/// Builds the server, which shuts down gracefully on SIGTERM or Ctrl-C.
+pub async fn run() -> Result<impl Future<Output = hyper::Result<()>>, ConfigError> {
+    run_until(shutdown::signal()).await
+}
+
+/// Builds the server, which stops accepting connections once `shutdown` resolves. The requests in
+/// flight get until `server.drain_timeout_ms` to finish, then the session store is closed.
+pub async fn run_until(
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<impl Future<Output = hyper::Result<()>>, ConfigError> {
+    let loader = Config::loader();
+    let (config, settings) = loader.load::<Config>()?;
+    let address = config.server.address;
+    let drain_timeout = config.server.drain_timeout();
+    let session_store = SessionStore::new().await;
+    let metrics = Metrics::new("crm");
+    metrics.observe_pool(session_store.pool().clone());
+    let state = AppState::new(session_store, config, metrics);
+    let reloaded = state.clone();
+    tokio::spawn(watch(
+        loader,
+        settings,
+        CONFIG_POLL_INTERVAL,
+        move |config| reloaded.config.set(config),
+    ));
+    let origins = state.clone();
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::DELETE])
         .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE, COOKIE])
         .allow_credentials(true)
-        .allow_origin("http://127.0.0.1:5173".parse::<HeaderValue>().unwrap());
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            origins.config().cors.allows(origin)
+        }));
 
     let layer = ServiceBuilder::new()
+        .layer(TraceLayer)
         .layer(cors)
-        .layer(from_fn_with_state(state.clone(), auth));
+        .layer(from_fn_with_state(state.clone(), auth))
+        .layer(from_fn_with_state(state.clone(), rate_limit));
 
     let app = Router::new()
         .route("/health-check", get(health_check))
-        .route("/api/user/login", post(user::json::login::login))
+        .route("/metrics", get(scrape))
+        .route(LOGIN, post(user::json::login::login))
         .route("/api/user/logout", post(user::json::logout::logout))
         .route("/api/user/list", get(user::json::list::list))
+        .route_layer(state.metrics.layer(route))
         .layer(layer)
-        .with_state(state);
+        .with_state(state.clone());
+
+    let (stopping_tx, stopping_rx) = oneshot::channel();
+    let serving = state.serving.clone();
+    let server = axum::Server::bind(&address)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown.await;
+            tracing::info!("shutting down, draining the requests in flight");
+            serving.store(false, Ordering::Relaxed);
+            let _ = stopping_tx.send(());
+        });
+
+    Ok(async move {
+        drain(server, stopping_rx, drain_timeout).await?;
+        state.session_store.close().await;
+        tracing::info!("shut down");
+        Ok(())
+    })
+}
+
+/// Labels the requests by the route they matched, rather than by the paths callers made up.
+fn route(parts: &Parts) -> Option<&str> {
+    parts
+        .extensions
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+}
 
-    axum::Server::bind(&address).serve(app.into_make_service())
+async fn health_check(State(state): State<AppState>) -> axum::response::Response {
+    match state.serving.load(Ordering::Relaxed) {
+        true => StatusCode::OK.into_response(),
+        false => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    }
 }
 
-async fn health_check() -> axum::response::Response {
-    StatusCode::OK.into_response()
+async fn scrape(State(state): State<AppState>) -> axum::response::Response {
+    (
+        [(CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        state.metrics.encode(),
+    )
+        .into_response()
 }
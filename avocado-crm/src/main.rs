@@ -1,11 +1,25 @@
-use avocado_base::log::init_subscriber;
-use avocado_crm::run;
-use std::net::SocketAddr;
+use avocado_base::log::Builder;
+use avocado_crm::{log_config, run};
 
 #[tokio::main]
 async fn main() -> hyper::Result<()> {
-    init_subscriber();
+    let _log = match log_config().and_then(|log| {
+        Builder::new("avocado-crm", log)
+            .with_telemetry(env!("CARGO_PKG_VERSION"))
+            .init()
+    }) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("unable to start: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
-    run(addr).await.await
+    match run().await {
+        Ok(server) => server.await,
+        Err(e) => {
+            tracing::error!("unable to start: {}", e);
+            std::process::exit(1);
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::err::JsonError;
+use crate::err::{error_body, JsonError};
 use crate::session::cmd::refresh_token::RefreshToken;
 use crate::session::SessionId;
 use crate::state::State as AppState;
@@ -6,12 +6,13 @@ use axum::extract::State;
 use axum::http::{Request, StatusCode};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
-use axum::Json;
 use axum_extra::extract::cookie::{Cookie, CookieJar};
-use serde_json::json;
 use std::str::FromStr;
 use uuid::Uuid;
 
+/// Paths reachable without a session, the load balancer and Prometheus have none.
+const PUBLIC_PATHS: [&str; 3] = ["/api/user/login", "/health-check", "/metrics"];
+
 pub(crate) async fn auth<B>(
     State(state): State<AppState>,
     cookie: CookieJar,
@@ -19,7 +20,7 @@ pub(crate) async fn auth<B>(
     next: Next<B>,
 ) -> Result<Response, JsonError> {
     let path = req.uri().path();
-    if path != "/api/user/login" {
+    if !PUBLIC_PATHS.contains(&path) {
         tracing::info!("receiving cookie {:?}", cookie);
         match get_session_id(&cookie) {
             Some(session_id) => match state.session_store.get(&session_id).await {
@@ -67,7 +68,7 @@ async fn clear_session(
     Ok((
         StatusCode::UNAUTHORIZED,
         cookie,
-        Json(json!({"error": "user is unauthenticated"})),
+        error_body("user is unauthenticated"),
     )
         .into_response())
 }
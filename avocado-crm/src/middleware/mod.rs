@@ -1 +1,2 @@
 pub(crate) mod auth;
+pub(crate) mod rate_limit;
@@ -0,0 +1,42 @@
+use crate::cfg::RateLimit;
+use crate::err::JsonError;
+use crate::session::Session;
+use crate::state::State as AppState;
+use avocado_base::rate_limit::Limits;
+use axum::extract::{ConnectInfo, State};
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::net::SocketAddr;
+
+pub(crate) const LOGIN: &str = "/api/user/login";
+
+type Route = (&'static str, fn(&RateLimit) -> &Limits);
+
+/// The limited paths and their limits.
+const ROUTES: [Route; 1] = [(LOGIN, |rate_limit| &rate_limit.login)];
+
+/// Limits the requests by client and by signed in user, so it goes after the auth middleware.
+/// The emails signing in are limited by the login itself.
+pub(crate) async fn rate_limit<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Result<Response, JsonError> {
+    let path = req.uri().path();
+    let config = state.config();
+    if let Some((_, limits)) = ROUTES.iter().find(|(route, _)| *route == path) {
+        let limits = limits(&config.rate_limit);
+        if let Some(ConnectInfo(address)) = req.extensions().get::<ConnectInfo<SocketAddr>>() {
+            limits.client(state.rate_limiter.as_ref(), path, address.ip())?;
+        }
+        if let Some(session) = req.extensions().get::<Session>() {
+            limits.user(
+                state.rate_limiter.as_ref(),
+                path,
+                &session.user_id.to_string(),
+            )?;
+        }
+    }
+    Ok(next.run(req).await)
+}
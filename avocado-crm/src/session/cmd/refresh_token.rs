@@ -1,11 +1,12 @@
+use crate::cfg::UserChannel;
 use crate::session::{verify_jwt_token, Session};
 use crate::state::State;
 use anyhow::anyhow;
 use anyhow::Result;
+use avocado_base::auth::Bearer;
 use avocado_proto::grpc::jwt::jwt_client::JwtClient;
 use avocado_proto::grpc::jwt::RefreshRequest;
 use chrono::{DateTime, TimeZone, Utc};
-use tonic::metadata::MetadataValue;
 
 pub(crate) struct RefreshToken {
     pub(crate) session: Session,
@@ -24,7 +25,7 @@ impl RefreshToken {
             // If access token nearly expires, ask the user service to refresh
             let (access_token, access_token_exp, refresh_token, refresh_token_exp) =
                 refresh_tokens(
-                    state.config.service_address.user.clone(),
+                    state.config().service_address.connect().await?,
                     self.session.access_token.clone(),
                     self.session.refresh_token.clone(),
                 )
@@ -48,17 +49,18 @@ fn tokens_expired(session: &Session) -> bool {
 }
 
 async fn refresh_tokens(
-    address: String,
+    channel: UserChannel,
     access_token: String,
     refresh_token: String,
 ) -> Result<(String, DateTime<Utc>, String, DateTime<Utc>)> {
-    let mut jwt_client = JwtClient::connect(address).await?;
-    let access_token: MetadataValue<_> = access_token.parse()?;
-    let mut request = tonic::Request::new(RefreshRequest {
+    let jwt_client = JwtClient::new(channel.clone());
+    let request = tonic::Request::new(RefreshRequest {
         refresh_token: refresh_token.clone(),
     });
-    request.metadata_mut().insert("auth", access_token.clone());
-    let refresh_reply = jwt_client.refresh(request).await?.into_inner();
+    let refresh_reply = JwtClient::with_interceptor(channel, Bearer::new(&access_token)?)
+        .refresh(request)
+        .await?
+        .into_inner();
     let access_token_verify_reply =
         verify_jwt_token(jwt_client.clone(), refresh_reply.access_token.clone()).await?;
 
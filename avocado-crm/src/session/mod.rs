@@ -1,7 +1,7 @@
+use crate::cfg::UserChannel;
 use avocado_proto::grpc::jwt::jwt_client::JwtClient;
 use avocado_proto::grpc::jwt::{VerifyReply, VerifyRequest};
 use chrono::{DateTime, Utc};
-use tonic::transport::Channel;
 use ulid::Ulid;
 use uuid::Uuid;
 
@@ -20,11 +20,11 @@ pub(crate) struct Session {
     pub(crate) email: String,
     pub(crate) first_name: String,
     pub(crate) last_name: String,
-    pub(crate) role: String,
+    pub(crate) roles: Vec<String>,
 }
 
 pub async fn verify_jwt_token(
-    mut jwt_client: JwtClient<Channel>,
+    mut jwt_client: JwtClient<UserChannel>,
     token: String,
 ) -> anyhow::Result<VerifyReply> {
     let request = tonic::Request::new(VerifyRequest { token });
@@ -1,18 +1,40 @@
 use crate::cfg::Config;
 use crate::db::SessionStore;
+use avocado_base::cfg::Shared;
+use avocado_base::metrics::Metrics;
+use avocado_base::rate_limit::{Backend, Memory};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub(crate) struct State {
     pub(crate) session_store: Arc<dyn SessionStore>,
-    pub(crate) config: Arc<Config>,
+    /// Swapped as a whole when the configuration is reloaded
+    pub(crate) config: Arc<Shared<Config>>,
+    /// Turns false when shutting down, for the health check to tell the load balancer
+    pub(crate) serving: Arc<AtomicBool>,
+    pub(crate) metrics: Metrics,
+    /// The buckets of the rate limits, the limits themselves are configured
+    pub(crate) rate_limiter: Arc<dyn Backend>,
 }
 
 impl State {
-    pub(crate) fn new(session_store: impl SessionStore + 'static) -> Self {
+    pub(crate) fn new(
+        session_store: impl SessionStore + 'static,
+        config: Config,
+        metrics: Metrics,
+    ) -> Self {
         State {
             session_store: Arc::new(session_store),
-            config: Arc::new(Config::new()),
+            config: Arc::new(Shared::new(config)),
+            serving: Arc::new(AtomicBool::new(true)),
+            metrics,
+            rate_limiter: Arc::new(Memory::default()),
         }
     }
+
+    /// The current configuration, later reloads don't change the returned one.
+    pub(crate) fn config(&self) -> Arc<Config> {
+        self.config.get()
+    }
 }
@@ -1,10 +1,10 @@
 use crate::cmd::{Command, CommandResult};
 use crate::session::Session;
 use crate::state::State;
+use avocado_base::auth::Bearer;
 use avocado_proto::grpc::user::user_client::UserClient;
 use avocado_proto::grpc::user::{ListRequest, UserReply};
 use futures_util::StreamExt;
-use tonic::metadata::MetadataValue;
 
 #[derive(Debug)]
 pub(crate) struct List {
@@ -17,12 +17,11 @@ impl Command for List {
 
     #[tracing::instrument(name = "Executing 'user list' command", skip(self, state))]
     async fn execute(&self, state: State) -> Self::R {
-        let mut user_client =
-            UserClient::connect(state.config.service_address.user.clone()).await?;
-
-        let access_token: MetadataValue<_> = self.session.access_token.parse()?;
-        let mut request = tonic::Request::new(ListRequest {});
-        request.metadata_mut().insert("auth", access_token.clone());
+        let mut user_client = UserClient::with_interceptor(
+            state.config().service_address.connect().await?,
+            Bearer::new(&self.session.access_token)?,
+        );
+        let request = tonic::Request::new(ListRequest::default());
         let mut list_reply = user_client.list(request).await?.into_inner();
 
         let mut users: Vec<UserReply> = vec![];
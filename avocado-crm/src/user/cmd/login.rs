@@ -1,21 +1,24 @@
+use crate::cfg::UserChannel;
 use crate::cmd::Command;
 use crate::session::Session;
 use crate::session::{verify_jwt_token, SessionId};
 use crate::state::State;
 use anyhow::{anyhow, Result};
+use avocado_base::auth::Bearer;
 use avocado_base::secret::SecretString;
 use avocado_proto::grpc::jwt::jwt_client::JwtClient;
 use avocado_proto::grpc::user::user_client::UserClient;
 use avocado_proto::grpc::user::{LoginRequest, UserReply, WhoAmIRequest};
 use chrono::{TimeZone, Utc};
 use serde::Deserialize;
-use tonic::metadata::MetadataValue;
-use tonic::transport::Channel;
 use ulid::Ulid;
 use uuid::Uuid;
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct Login {
+    /// Name of the tenant, the platform tenant when omitted
+    #[serde(default)]
+    pub(crate) tenant: String,
     pub(crate) email: String,
     pub(crate) password: SecretString,
 }
@@ -26,21 +29,22 @@ impl Command for Login {
 
     #[tracing::instrument(name = "Executing 'user login' command", skip(state))]
     async fn execute(&self, state: State) -> Self::R {
-        let mut user_client =
-            UserClient::connect(state.config.service_address.user.clone()).await?;
+        let channel = state.config().service_address.connect().await?;
+        let mut user_client = UserClient::new(channel.clone());
         let request = tonic::Request::new(LoginRequest {
+            tenant: self.tenant.clone(),
             email: self.email.clone(),
             password: self.password.expose_secret().clone(),
         });
         let login_reply = user_client.login(request).await?.into_inner();
 
-        let jwt_client = JwtClient::connect(state.config.service_address.user.clone()).await?;
+        let jwt_client = JwtClient::new(channel.clone());
         let access_token_verify_reply =
             verify_jwt_token(jwt_client.clone(), login_reply.access_token.clone()).await?;
         let refresh_token_verify_reply =
             verify_jwt_token(jwt_client, login_reply.refresh_token.clone()).await?;
 
-        let who_reply = who_i_am(user_client, login_reply.access_token.clone()).await?;
+        let who_reply = who_i_am(channel, &login_reply.access_token).await?;
         let session_id = Uuid::new_v4();
         state
             .session_store
@@ -61,16 +65,15 @@ impl Command for Login {
                 email: who_reply.email,
                 first_name: who_reply.first_name,
                 last_name: who_reply.last_name,
-                role: who_reply.role,
+                roles: who_reply.roles,
             })
             .await?;
         Ok(session_id)
     }
 }
 
-async fn who_i_am(mut user_client: UserClient<Channel>, access_token: String) -> Result<UserReply> {
-    let access_token: MetadataValue<_> = access_token.parse()?;
-    let mut request = tonic::Request::new(WhoAmIRequest {});
-    request.metadata_mut().insert("auth", access_token.clone());
+async fn who_i_am(channel: UserChannel, access_token: &str) -> Result<UserReply> {
+    let mut user_client = UserClient::with_interceptor(channel, Bearer::new(access_token)?);
+    let request = tonic::Request::new(WhoAmIRequest {});
     Ok(user_client.who_am_i(request).await?.into_inner())
 }
@@ -1,5 +1,6 @@
 use crate::cmd::Command;
 use crate::err::JsonError;
+use crate::middleware::rate_limit::LOGIN;
 use crate::state::State as AppState;
 use crate::user::cmd::login::Login;
 use axum::extract::State;
@@ -21,8 +22,12 @@ pub(crate) async fn login(
     State(state): State<AppState>,
     WithRejection(Json(login), _): WithRejection<Json<Login>, JsonError>,
 ) -> Result<Response, JsonError> {
+    state
+        .config()
+        .rate_limit
+        .login
+        .email(state.rate_limiter.as_ref(), LOGIN, &login.email)?;
     let session_id = login.execute(state.clone()).await?;
-    let cookie = cookie
-        .add(Cookie::parse(format!("session_id={}; Path=/", session_id.to_string())).unwrap());
+    let cookie = cookie.add(Cookie::parse(format!("session_id={}; Path=/", session_id)).unwrap());
     Ok((cookie, Json(LoginReply { session_id })).into_response())
 }
@@ -1,10 +1,10 @@
 use avocado_base::log::init_subscriber;
 use avocado_crm::run;
-use avocado_proto::grpc::user::user_client::UserClient;
 use avocado_user::run as run_user_server;
 use once_cell::sync::Lazy;
 use reqwest::Client;
 use std::time::Duration;
+use tokio::net::TcpStream;
 use tokio::time::{sleep, timeout};
 
 pub static TRACING: Lazy<()> = Lazy::new(|| {
@@ -13,18 +13,32 @@ pub static TRACING: Lazy<()> = Lazy::new(|| {
     }
 });
 
+/// Points the server at the key pair and admin of the tests, no build comes with any.
+fn configure(keys: &str) {
+    std::env::set_var(
+        "AVOCADO_RSA__PRIVATE_KEY_FILE",
+        format!("{}/test.pem", keys),
+    );
+    std::env::set_var(
+        "AVOCADO_RSA__PUBLIC_KEY_FILE",
+        format!("{}/test.pub.pem", keys),
+    );
+    std::env::set_var("AVOCADO_BOOTSTRAP__ADMIN_PASSWORD", "kIxv4NomLT0WwGKF");
+}
+
 pub async fn start_user_server() {
-    let address = "[::1]:50051"
-        .parse()
-        .expect("invalid user server socket address");
-    let server = run_user_server(address)
+    configure(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../avocado-user/tests/keys"
+    ));
+    let server = run_user_server()
         .await
         .expect("failed to create avocado-user grpc router");
     tokio::spawn(server);
 
     async fn wait_server() {
         loop {
-            if UserClient::connect("http://[::1]:50051").await.is_ok() {
+            if TcpStream::connect("[::1]:50051").await.is_ok() {
                 break;
             }
             sleep(Duration::from_millis(300)).await;
@@ -36,17 +50,14 @@ pub async fn start_user_server() {
 }
 
 pub async fn start_crm_server() {
-    let address = "[::1]:3000"
-        .parse()
-        .expect("invalid crm server socket address");
-    let server = run(address).await;
+    let server = run().await.expect("failed to create avocado-crm router");
     tokio::spawn(server);
 
     async fn wait_server() {
         let client = Client::new();
         loop {
             if client
-                .get("http://[::1]:3000/health-check")
+                .get("http://127.0.0.1:3000/health-check")
                 .send()
                 .await
                 .is_ok()
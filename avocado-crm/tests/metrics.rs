@@ -0,0 +1,35 @@
+use avocado_crm::run;
+use reqwest::{Client, StatusCode};
+use std::time::Duration;
+use tokio::time::sleep;
+
+#[tokio::test]
+async fn metrics_works() {
+    let server = run().await.expect("failed to create avocado-crm router");
+    tokio::spawn(server);
+
+    let client = Client::new();
+    loop {
+        match client
+            .get("http://127.0.0.1:3000/health-check")
+            .send()
+            .await
+        {
+            Ok(_) => break,
+            Err(_) => sleep(Duration::from_millis(300)).await,
+        }
+    }
+
+    // Prometheus scrapes without a session
+    let response = client
+        .get("http://127.0.0.1:3000/metrics")
+        .send()
+        .await
+        .expect("failed to scrape the metrics");
+    assert_eq!(response.status(), StatusCode::OK);
+    let metrics = response.text().await.unwrap();
+    assert!(metrics.contains(
+        r#"avocado_requests_total{method="/health-check",status="200",service="crm"} 1"#
+    ));
+    assert!(metrics.contains(r#"avocado_db_pool_connections{state="idle",service="crm"}"#));
+}
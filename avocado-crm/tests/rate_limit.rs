@@ -0,0 +1,67 @@
+use crate::app::{start_crm_server, start_user_server, TRACING};
+use once_cell::sync::Lazy;
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use serde_json::Value;
+
+mod app;
+
+#[tokio::test]
+async fn rate_limit_works() {
+    Lazy::force(&TRACING);
+    // Read by both servers, the user service sees the CRM as its one client
+    std::env::set_var("AVOCADO_RATE_LIMIT__LOGIN__PER_CLIENT__BURST", "2");
+    std::env::set_var("AVOCADO_RATE_LIMIT__LOGIN__PER_CLIENT__PER_MINUTE", "1");
+
+    start_user_server().await;
+    start_crm_server().await;
+
+    #[derive(Serialize)]
+    struct LoginRequest {
+        email: String,
+        password: String,
+    }
+
+    let client = Client::new();
+    let login = |email: &str| {
+        client
+            .post("http://127.0.0.1:3000/api/user/login")
+            .json(&LoginRequest {
+                email: email.to_string(),
+                password: "kIxv4NomLT0WwGKF".to_string(),
+            })
+            .send()
+    };
+
+    let response = login("admin@avocado.com").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = login("nobody@avocado.com").await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // The client is out of logins, whatever the email
+    let response = login("admin@avocado.com").await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response
+        .headers()
+        .get("retry-after")
+        .expect("a Retry-After header should be sent")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+    let body = response.json::<Value>().await.unwrap();
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("too many requests"));
+    assert!(body["request_id"].is_string());
+
+    // The other paths aren't limited
+    let response = client
+        .get("http://127.0.0.1:3000/health-check")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,42 @@
+use avocado_crm::run_until;
+use reqwest::{Client, StatusCode};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn shutdown_works() {
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = run_until(async {
+        let _ = shutdown_rx.await;
+    })
+    .await
+    .expect("failed to create avocado-crm router");
+    let server = tokio::spawn(server);
+
+    // The health check needs no session
+    let client = Client::new();
+    let status = loop {
+        match client
+            .get("http://127.0.0.1:3000/health-check")
+            .send()
+            .await
+        {
+            Ok(response) => break response.status(),
+            Err(_) => sleep(Duration::from_millis(300)).await,
+        }
+    };
+    assert_eq!(status, StatusCode::OK);
+
+    shutdown_tx.send(()).unwrap();
+    timeout(Duration::from_secs(10), server)
+        .await
+        .expect("the server did not stop after draining")
+        .unwrap()
+        .expect("the server failed");
+    assert!(client
+        .get("http://127.0.0.1:3000/health-check")
+        .send()
+        .await
+        .is_err());
+}
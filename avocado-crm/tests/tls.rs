@@ -0,0 +1,53 @@
+use crate::app::{start_crm_server, start_user_server, TRACING};
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+mod app;
+
+#[tokio::test]
+async fn user_service_over_mutual_tls_works() {
+    Lazy::force(&TRACING);
+
+    // The test certificates of avocado-user, for both servers run in this process
+    let certs = format!("{}/../avocado-user/tests/certs", env!("CARGO_MANIFEST_DIR"));
+    let vars = [
+        ("AVOCADO_SERVER__TLS__CERT_FILE", "server.pem"),
+        ("AVOCADO_SERVER__TLS__KEY_FILE", "server.key"),
+        ("AVOCADO_SERVER__TLS__CLIENT_CA_FILE", "ca.pem"),
+        ("AVOCADO_SERVICE_ADDRESS__TLS__CA_FILE", "ca.pem"),
+        ("AVOCADO_SERVICE_ADDRESS__TLS__CERT_FILE", "client.pem"),
+        ("AVOCADO_SERVICE_ADDRESS__TLS__KEY_FILE", "client.key"),
+    ];
+    for (var, file) in vars {
+        std::env::set_var(var, format!("{}/{}", certs, file));
+    }
+    std::env::set_var("AVOCADO_SERVICE_ADDRESS__USER", "https://[::1]:50051");
+    std::env::set_var("AVOCADO_SERVICE_ADDRESS__TLS__DOMAIN", "localhost");
+
+    start_user_server().await;
+    start_crm_server().await;
+
+    #[derive(Serialize)]
+    struct LoginRequest {
+        email: String,
+        password: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct LoginReply {
+        session_id: String,
+    }
+
+    let client = Client::builder().cookie_store(true).build().unwrap();
+    let reply = client
+        .post("http://127.0.0.1:3000/api/user/login")
+        .json(&LoginRequest {
+            email: "admin@avocado.com".to_string(),
+            password: "kIxv4NomLT0WwGKF".to_string(),
+        })
+        .send()
+        .await;
+    let login_reply = reply.unwrap().json::<LoginReply>().await.unwrap();
+    assert_eq!(login_reply.session_id.len(), 36);
+}
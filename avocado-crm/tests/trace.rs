@@ -0,0 +1,45 @@
+use avocado_crm::run;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time::sleep;
+
+#[tokio::test]
+async fn trace_works() {
+    let server = run().await.expect("failed to create avocado-crm router");
+    tokio::spawn(server);
+
+    // A new trace starts without one from the caller
+    let client = Client::new();
+    let response = loop {
+        match client
+            .get("http://127.0.0.1:3000/health-check")
+            .send()
+            .await
+        {
+            Ok(response) => break response,
+            Err(_) => sleep(Duration::from_millis(300)).await,
+        }
+    };
+    assert!(response.headers().contains_key("x-request-id"));
+    assert!(response.headers().contains_key("traceparent"));
+
+    // The trace and request id of the caller are continued, and the error tells the request id
+    let response = client
+        .get("http://127.0.0.1:3000/api/user/list")
+        .header(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        )
+        .header("x-request-id", "list-users-1")
+        .send()
+        .await
+        .expect("failed to call list users");
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["x-request-id"], "list-users-1");
+    let traceparent = response.headers()["traceparent"].to_str().unwrap();
+    assert!(traceparent.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+    assert!(!traceparent.contains("b7ad6b7169203331"));
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["request_id"], "list-users-1");
+}
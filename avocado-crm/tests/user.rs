@@ -26,7 +26,7 @@ async fn user_api_works() {
 
     let client = Client::builder().cookie_store(true).build().unwrap();
     let reply = client
-        .post("http://[::1]:3000/api/user/login")
+        .post("http://127.0.0.1:3000/api/user/login")
         .json(&LoginRequest {
             email: "admin@avocado.com".to_string(),
             password: "kIxv4NomLT0WwGKF".to_string(),
@@ -44,17 +44,29 @@ async fn user_api_works() {
         pub email: String,
         pub first_name: String,
         pub last_name: String,
-        pub role: String,
+        pub roles: Vec<String>,
+        pub permissions: Vec<String>,
     }
 
-    let reply = client.get("http://[::1]:3000/api/user/list").send().await;
+    let reply = client
+        .get("http://127.0.0.1:3000/api/user/list")
+        .send()
+        .await;
     let list_reply = reply.unwrap().json::<Vec<UserReply>>().await.unwrap();
     tracing::info!("user list reply: {:?}", list_reply);
-    assert_eq!(list_reply.get(0).unwrap().email, "admin@avocado.com");
+    let admin = list_reply.first().unwrap();
+    assert_eq!(admin.id.len(), 26);
+    assert_eq!(admin.email, "admin@avocado.com");
+    assert_eq!(
+        (admin.first_name.as_str(), admin.last_name.as_str()),
+        ("System", "Admin")
+    );
+    assert_eq!(admin.roles, vec!["admin"]);
+    assert_eq!(admin.permissions, vec!["*"]);
 
     // Logout
     let reply = client
-        .post("http://[::1]:3000/api/user/logout")
+        .post("http://127.0.0.1:3000/api/user/logout")
         .send()
         .await;
     assert!(reply.is_ok());
@@ -1,11 +1,26 @@
+use std::path::PathBuf;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let descriptor_path = PathBuf::from(std::env::var("OUT_DIR")?).join("descriptor.bin");
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
         .type_attribute("UserReply", "#[derive(serde::Serialize)]")
+        .type_attribute("Actor", "#[derive(serde::Serialize)]")
+        .type_attribute("Profile", "#[derive(serde::Serialize)]")
         .out_dir("src/grpc")
+        .file_descriptor_set_path(descriptor_path)
         .compile(
-            &["src/user/jwt.proto", "src/user/user.proto"],
+            &[
+                "src/health/health.proto",
+                "src/reflection/reflection.proto",
+                "src/user/audit.proto",
+                "src/user/group.proto",
+                "src/user/jwt.proto",
+                "src/user/role.proto",
+                "src/user/tenant.proto",
+                "src/user/user.proto",
+            ],
             &["proto"],
         )
         .unwrap();
@@ -1,2 +1,17 @@
+pub mod audit;
+pub mod group;
 pub mod jwt;
+pub mod role;
+pub mod tenant;
 pub mod user;
+
+pub mod health {
+    include!("grpc.health.v1.rs");
+}
+
+pub mod reflection {
+    include!("grpc.reflection.v1alpha.rs");
+}
+
+/// The encoded `FileDescriptorSet` of all the services above, for server reflection.
+pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/descriptor.bin"));
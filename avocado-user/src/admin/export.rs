@@ -0,0 +1,71 @@
+use crate::admin::{Client, Format};
+use anyhow::Result;
+use avocado_proto::grpc::user::ExportUsersRequest;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug)]
+pub(super) struct Args {
+    /// Defaults to the standard output
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// Defaults to the format matching the output extension, or CSV
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+    /// Also export the disabled and deleted users
+    #[arg(long)]
+    include_inactive: bool,
+    /// Defaults to the tenant of the admin
+    #[arg(long, default_value = "")]
+    tenant_id: String,
+}
+
+pub(super) async fn run(mut client: Client, args: &Args) -> Result<()> {
+    let format = Format::resolve(args.format, args.output.as_deref());
+    let output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut stream = client
+        .export_users(ExportUsersRequest {
+            tenant_id: args.tenant_id.clone(),
+            include_inactive: args.include_inactive,
+        })
+        .await?
+        .into_inner();
+
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(output);
+            writer.write_record([
+                "id",
+                "tenant_id",
+                "email",
+                "first_name",
+                "last_name",
+                "status",
+                "roles",
+            ])?;
+            while let Some(user) = stream.message().await? {
+                writer.write_record([
+                    &user.id,
+                    &user.tenant_id,
+                    &user.email,
+                    &user.first_name,
+                    &user.last_name,
+                    &user.status,
+                    &user.roles.join(";"),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        Format::Jsonl => {
+            let mut output = output;
+            while let Some(user) = stream.message().await? {
+                writeln!(output, "{}", serde_json::to_string(&user)?)?;
+            }
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
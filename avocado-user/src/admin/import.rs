@@ -0,0 +1,162 @@
+use crate::admin::{Client, Format};
+use anyhow::{anyhow, bail, Context, Result};
+use avocado_proto::grpc::user::import_users_request::Item;
+use avocado_proto::grpc::user::{ImportOptions, ImportOutcome, ImportRow, ImportUsersRequest};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug)]
+pub(super) struct Args {
+    /// The file to import, with the columns email, first_name, last_name, password and roles
+    file: PathBuf,
+    /// Defaults to the format matching the file extension, or CSV
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+    /// Only validate the rows and report what would happen
+    #[arg(long)]
+    dry_run: bool,
+    /// Update the users whose email already exists instead of failing the row
+    #[arg(long)]
+    upsert: bool,
+    /// Defaults to the tenant of the admin
+    #[arg(long, default_value = "")]
+    tenant_id: String,
+}
+
+/// A CSV row, where the roles are separated by semicolons.
+#[derive(Deserialize, Debug)]
+struct CsvRow {
+    email: String,
+    first_name: String,
+    last_name: String,
+    password: String,
+    #[serde(default)]
+    roles: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct JsonRow {
+    email: String,
+    first_name: String,
+    last_name: String,
+    password: String,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+fn read_rows(reader: impl Read, format: Format) -> Result<Vec<ImportRow>> {
+    match format {
+        Format::Csv => csv::Reader::from_reader(reader)
+            .deserialize::<CsvRow>()
+            .enumerate()
+            .map(|(i, row)| {
+                let row = row.with_context(|| format!("invalid row {}", i + 1))?;
+                Ok(ImportRow {
+                    email: row.email,
+                    first_name: row.first_name,
+                    last_name: row.last_name,
+                    password: row.password,
+                    roles: row
+                        .roles
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|r| !r.is_empty())
+                        .map(String::from)
+                        .collect(),
+                })
+            })
+            .collect(),
+        Format::Jsonl => BufReader::new(reader)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|(i, line)| {
+                let row: JsonRow = serde_json::from_str(&line?)
+                    .with_context(|| format!("invalid line {}", i + 1))?;
+                Ok(ImportRow {
+                    email: row.email,
+                    first_name: row.first_name,
+                    last_name: row.last_name,
+                    password: row.password,
+                    roles: row.roles,
+                })
+            })
+            .collect(),
+    }
+}
+
+pub(super) async fn run(mut client: Client, args: &Args) -> Result<()> {
+    let format = Format::resolve(args.format, Some(&args.file));
+    let file = std::fs::File::open(&args.file)
+        .with_context(|| format!("cannot open {}", args.file.display()))?;
+    let rows = read_rows(file, format)?;
+
+    let options = ImportOptions {
+        dry_run: args.dry_run,
+        upsert: args.upsert,
+        tenant_id: args.tenant_id.clone(),
+    };
+    let messages = std::iter::once(Item::Options(options))
+        .chain(rows.into_iter().map(Item::Row))
+        .map(|item| ImportUsersRequest { item: Some(item) });
+    let reply = client
+        .import_users(tokio_stream::iter(messages))
+        .await?
+        .into_inner();
+
+    for result in &reply.results {
+        match result.outcome() {
+            ImportOutcome::Failed => {
+                println!(
+                    "row {} {}: failed: {}",
+                    result.row, result.email, result.error
+                )
+            }
+            outcome => println!(
+                "row {} {}: {}",
+                result.row,
+                result.email,
+                format!("{:?}", outcome).to_lowercase()
+            ),
+        }
+    }
+    println!(
+        "{}{} created, {} updated, {} failed",
+        if args.dry_run { "dry run: " } else { "" },
+        reply.created,
+        reply.updated,
+        reply.failed
+    );
+    if reply.failed > 0 {
+        bail!(anyhow!("{} rows failed to import", reply.failed));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::admin::import::read_rows;
+    use crate::admin::Format;
+
+    #[test]
+    fn test_read_rows() {
+        let csv = "email,first_name,last_name,password,roles\n\
+                   a@avocado.com,Ann,Lee,password,admin; user\n\
+                   b@avocado.com,Bob,Lee,password,\n";
+        let rows = read_rows(csv.as_bytes(), Format::Csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].roles, vec!["admin", "user"]);
+        assert!(rows[1].roles.is_empty());
+
+        let jsonl = r#"{"email":"a@avocado.com","first_name":"Ann","last_name":"Lee","password":"password","roles":["admin"]}
+
+{"email":"b@avocado.com","first_name":"Bob","last_name":"Lee","password":"password"}
+"#;
+        let rows = read_rows(jsonl.as_bytes(), Format::Jsonl).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].roles, vec!["admin"]);
+
+        assert!(read_rows("{}".as_bytes(), Format::Jsonl).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+use crate::admin::JwtClient;
+use anyhow::{Context, Result};
+use avocado_proto::grpc::jwt::RotateKeyRequest;
+use std::path::PathBuf;
+
+#[derive(clap::Subcommand, Debug)]
+pub(super) enum Command {
+    /// Signs new tokens with another RSA key pair, e.g. generated with
+    /// `openssl genrsa -out private.pem 4096 && openssl rsa -in private.pem -pubout -out public.pem`
+    Rotate {
+        /// The PEM file of the private key
+        #[arg(long)]
+        private_key: PathBuf,
+        /// The PEM file of the public key
+        #[arg(long)]
+        public_key: PathBuf,
+    },
+}
+
+pub(super) async fn run(mut client: JwtClient, command: &Command) -> Result<()> {
+    match command {
+        Command::Rotate {
+            private_key,
+            public_key,
+        } => {
+            let read = |path: &PathBuf| {
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("cannot read {}", path.display()))
+            };
+            let reply = client
+                .rotate_key(RotateKeyRequest {
+                    private_key: read(private_key)?,
+                    public_key: read(public_key)?,
+                })
+                .await?;
+            println!("signing tokens with key {}", reply.into_inner().kid);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crate::admin::{Client, PasswordInput};
+use anyhow::Result;
+use avocado_proto::grpc::user::LoginRequest;
+
+#[derive(clap::Args, Debug)]
+pub(super) struct Args {
+    #[arg(long)]
+    email: String,
+    #[command(flatten)]
+    password: PasswordInput,
+    /// Name of the tenant, the platform tenant when empty
+    #[arg(long, default_value = "")]
+    tenant: String,
+}
+
+/// Prints the access token, to be passed on in `AVOCADO_TOKEN`.
+pub(super) async fn run(mut client: Client, args: &Args) -> Result<()> {
+    let reply = client
+        .login(LoginRequest {
+            email: args.email.clone(),
+            password: args.password.read()?,
+            tenant: args.tenant.clone(),
+        })
+        .await?;
+    println!("{}", reply.into_inner().access_token);
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use crate::db::sqlite::{migration, open};
+use anyhow::Result;
+
+#[derive(clap::Args, Debug)]
+pub(super) struct Args {
+    /// e.g. sqlite://avocado-user.db, the file is created when missing. The `database.url` of
+    /// the server
+    #[arg(long, env = "AVOCADO_DATABASE__URL")]
+    database_url: String,
+}
+
+pub(super) async fn run(args: &Args) -> Result<()> {
+    let pool = open(&args.database_url).await?;
+    let version = migration::run(&pool).await?;
+    println!("{} is at version {}", args.database_url, version);
+    Ok(())
+}
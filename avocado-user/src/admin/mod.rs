@@ -0,0 +1,200 @@
+use anyhow::Result;
+use avocado_base::auth::Bearer;
+use avocado_base::tls::ClientTls;
+use avocado_proto::grpc::jwt::jwt_client::JwtClient as GrpcJwtClient;
+use avocado_proto::grpc::user::user_client::UserClient;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Status};
+
+mod export;
+mod import;
+mod keys;
+mod login;
+mod migrate;
+#[cfg(feature = "dev")]
+mod seed;
+mod user;
+
+/// Administers the avocado user service. The commands calling it take the access token of an
+/// admin from `AVOCADO_TOKEN`, as printed by the login command.
+#[derive(Parser, Debug)]
+#[command(name = "avocado-user-admin")]
+struct Cli {
+    /// Address of the avocado-user gRPC server
+    #[arg(
+        long,
+        global = true,
+        env = "AVOCADO_USER_ENDPOINT",
+        default_value = "http://[::1]:50051"
+    )]
+    endpoint: String,
+    /// CA which issued the certificate of an https server
+    #[arg(long, global = true, env = "AVOCADO_USER_CA_FILE")]
+    ca: Option<PathBuf>,
+    /// Name in the certificate of the server, the host of the endpoint when omitted
+    #[arg(long, global = true, env = "AVOCADO_USER_TLS_DOMAIN", requires = "ca")]
+    domain: Option<String>,
+    /// Certificate of the client, for servers verifying their clients
+    #[arg(
+        long,
+        global = true,
+        env = "AVOCADO_USER_CERT_FILE",
+        requires_all = ["ca", "key"]
+    )]
+    cert: Option<PathBuf>,
+    /// Key of the client certificate
+    #[arg(long, global = true, env = "AVOCADO_USER_KEY_FILE", requires = "cert")]
+    key: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Signs in and prints the access token
+    Login(login::Args),
+    /// Manages users
+    #[command(subcommand)]
+    User(user::Command),
+    /// Manages the keys signing the tokens
+    #[command(subcommand)]
+    Keys(keys::Command),
+    /// Creates or upgrades the schema of a database, without a running server
+    Migrate(migrate::Args),
+    /// Adds or updates users in bulk from a CSV or JSON Lines file
+    Import(import::Args),
+    /// Writes the users of a tenant as CSV or JSON Lines, without password hashes
+    Export(export::Args),
+    /// Adds users with fake details, for development only
+    #[cfg(feature = "dev")]
+    Seed(seed::Args),
+}
+
+/// The file formats of imports and exports.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Csv,
+    Jsonl,
+}
+
+impl Format {
+    /// The format given, or else the one matching the extension of the file.
+    fn resolve(format: Option<Format>, path: Option<&Path>) -> Format {
+        format.unwrap_or_else(
+            || match path.and_then(|p| p.extension()).and_then(|e| e.to_str()) {
+                Some("jsonl") | Some("ndjson") => Format::Jsonl,
+                _ => Format::Csv,
+            },
+        )
+    }
+}
+
+/// Where the access token is read from, secrets aren't taken as arguments since other users see
+/// those in the process list and shells keep them in their history.
+const TOKEN_ENV: &str = "AVOCADO_TOKEN";
+
+const PASSWORD_ENV: &str = "AVOCADO_USER_PASSWORD";
+
+/// Where the commands taking a password read it from.
+#[derive(clap::Args, Debug, Clone)]
+struct PasswordInput {
+    /// Reads the password from the first line of the standard input, otherwise it's read from
+    /// `AVOCADO_USER_PASSWORD` or prompted for
+    #[arg(long)]
+    password_stdin: bool,
+}
+
+impl PasswordInput {
+    fn read(&self) -> Result<String> {
+        if self.password_stdin {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        match std::env::var(PASSWORD_ENV) {
+            Ok(password) => Ok(password),
+            Err(_) => Ok(rpassword::prompt_password("Password: ")?),
+        }
+    }
+}
+
+/// Sends the access token along with every request.
+#[derive(Debug, Clone)]
+struct Auth {
+    token: Option<Bearer>,
+}
+
+impl Interceptor for Auth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        match &mut self.token {
+            Some(token) => token.call(request),
+            None => Ok(request),
+        }
+    }
+}
+
+type Client = UserClient<InterceptedService<Channel, Auth>>;
+type JwtClient = GrpcJwtClient<InterceptedService<Channel, Auth>>;
+
+impl Cli {
+    async fn connect(&self) -> Result<(Channel, Auth)> {
+        let token = match std::env::var(TOKEN_ENV) {
+            Ok(t) => Some(Bearer::new(&t)?),
+            Err(_) => None,
+        };
+        let endpoint = Endpoint::from_shared(self.endpoint.clone())?;
+        let endpoint = match &self.ca {
+            Some(ca_file) => endpoint.tls_config(
+                ClientTls {
+                    ca_file: ca_file.clone(),
+                    domain: self.domain.clone(),
+                    cert_file: self.cert.clone(),
+                    key_file: self.key.clone(),
+                }
+                .load("tls")?,
+            )?,
+            None => endpoint,
+        };
+        Ok((endpoint.connect().await?, Auth { token }))
+    }
+
+    async fn user_client(&self) -> Result<Client> {
+        let (channel, auth) = self.connect().await?;
+        Ok(UserClient::with_interceptor(channel, auth))
+    }
+
+    async fn jwt_client(&self) -> Result<JwtClient> {
+        let (channel, auth) = self.connect().await?;
+        Ok(GrpcJwtClient::with_interceptor(channel, auth))
+    }
+}
+
+/// Runs the admin command given on the command line.
+pub async fn run() -> Result<()> {
+    let cli = Cli::parse();
+    match &cli.command {
+        Commands::Login(args) => login::run(cli.user_client().await?, args).await,
+        Commands::User(command) => user::run(cli.user_client().await?, command).await,
+        Commands::Keys(command) => keys::run(cli.jwt_client().await?, command).await,
+        Commands::Migrate(args) => migrate::run(args).await,
+        Commands::Import(args) => import::run(cli.user_client().await?, args).await,
+        Commands::Export(args) => export::run(cli.user_client().await?, args).await,
+        #[cfg(feature = "dev")]
+        Commands::Seed(args) => seed::run(cli.user_client().await?, args).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::admin::Cli;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+}
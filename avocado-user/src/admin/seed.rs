@@ -0,0 +1,35 @@
+use crate::admin::Client;
+use anyhow::Result;
+use avocado_proto::grpc::user::AddRequest;
+use fake::faker::internet::en::{FreeEmail, Password};
+use fake::faker::name::en::{FirstName, LastName};
+use fake::Fake;
+
+#[derive(clap::Args, Debug)]
+pub(super) struct Args {
+    #[arg(long, default_value_t = 50)]
+    count: usize,
+    /// Defaults to the tenant of the admin
+    #[arg(long, default_value = "")]
+    tenant_id: String,
+}
+
+pub(super) async fn run(mut client: Client, args: &Args) -> Result<()> {
+    for _ in 0..args.count {
+        let request = AddRequest {
+            email: FreeEmail().fake(),
+            first_name: FirstName().fake(),
+            last_name: LastName().fake(),
+            password: Password(8..16).fake(),
+            tenant_id: args.tenant_id.clone(),
+            ..Default::default()
+        };
+        let email = request.email.clone();
+        match client.add(request).await {
+            Ok(reply) => println!("{} {}", reply.into_inner().user_id, email),
+            // Fake emails can collide, which is not worth stopping for
+            Err(status) => eprintln!("{}: {}", email, status.message()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use crate::admin::{Client, PasswordInput};
+use anyhow::Result;
+use avocado_proto::grpc::user::{
+    AddRequest, AssignRoleRequest, DisableRequest, EnableRequest, ResetPasswordRequest,
+    RevokeRoleRequest, UserReply,
+};
+
+#[derive(clap::Subcommand, Debug, Clone)]
+pub(super) enum Command {
+    /// Adds a user, with the normal user role when no roles are given
+    Add {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        first_name: String,
+        #[arg(long)]
+        last_name: String,
+        #[command(flatten)]
+        password: PasswordInput,
+        /// Can be repeated
+        #[arg(long = "role")]
+        roles: Vec<String>,
+        /// Defaults to the tenant of the admin
+        #[arg(long, default_value = "")]
+        tenant_id: String,
+    },
+    /// Sets a new password for a user
+    ResetPassword {
+        user_id: String,
+        #[command(flatten)]
+        password: PasswordInput,
+        #[arg(long, default_value = "")]
+        tenant_id: String,
+    },
+    /// Gives a user a role
+    AssignRole {
+        user_id: String,
+        role: String,
+        #[arg(long, default_value = "")]
+        tenant_id: String,
+    },
+    /// Takes a role away from a user
+    RevokeRole {
+        user_id: String,
+        role: String,
+        #[arg(long, default_value = "")]
+        tenant_id: String,
+    },
+    /// Blocks a user from signing in, until enabled again
+    Disable {
+        user_id: String,
+        #[arg(long, default_value = "")]
+        tenant_id: String,
+    },
+    /// Lets a disabled user sign in again
+    Enable {
+        user_id: String,
+        #[arg(long, default_value = "")]
+        tenant_id: String,
+    },
+}
+
+fn print_user(user: &UserReply) {
+    println!(
+        "{} {} {} [{}]",
+        user.id,
+        user.email,
+        user.status,
+        user.roles.join(", ")
+    );
+}
+
+pub(super) async fn run(mut client: Client, command: &Command) -> Result<()> {
+    match command.clone() {
+        Command::Add {
+            email,
+            first_name,
+            last_name,
+            password,
+            roles,
+            tenant_id,
+        } => {
+            let reply = client
+                .add(AddRequest {
+                    email,
+                    first_name,
+                    last_name,
+                    password: password.read()?,
+                    roles,
+                    tenant_id,
+                })
+                .await?;
+            println!("{}", reply.into_inner().user_id);
+        }
+        Command::ResetPassword {
+            user_id,
+            password,
+            tenant_id,
+        } => {
+            client
+                .reset_password(ResetPasswordRequest {
+                    user_id,
+                    new_password: password.read()?,
+                    tenant_id,
+                })
+                .await?;
+        }
+        Command::AssignRole {
+            user_id,
+            role,
+            tenant_id,
+        } => {
+            let reply = client
+                .assign_role(AssignRoleRequest {
+                    user_id,
+                    role,
+                    tenant_id,
+                })
+                .await?;
+            print_user(reply.get_ref());
+        }
+        Command::RevokeRole {
+            user_id,
+            role,
+            tenant_id,
+        } => {
+            let reply = client
+                .revoke_role(RevokeRoleRequest {
+                    user_id,
+                    role,
+                    tenant_id,
+                })
+                .await?;
+            print_user(reply.get_ref());
+        }
+        Command::Disable { user_id, tenant_id } => {
+            let reply = client
+                .disable(DisableRequest { user_id, tenant_id })
+                .await?;
+            print_user(reply.get_ref());
+        }
+        Command::Enable { user_id, tenant_id } => {
+            let reply = client.enable(EnableRequest { user_id, tenant_id }).await?;
+            print_user(reply.get_ref());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::audit::{AuditEvent, AuditEventId, AuditFilter};
+use crate::state::State;
+
+const DEFAULT_PAGE_SIZE: u64 = 50;
+const MAX_PAGE_SIZE: u64 = 500;
+
+#[derive(Debug)]
+pub(crate) struct List {
+    pub(crate) filter: AuditFilter,
+    /// The default page size is used when zero
+    pub(crate) page_size: u64,
+    /// The last event of the previous page
+    pub(crate) page_token: Option<AuditEventId>,
+}
+
+#[tonic::async_trait]
+impl Command for List {
+    /// A page of events, newest first, and the token of the next page if there is one
+    type R = CommandResult<(Vec<AuditEvent>, Option<AuditEventId>)>;
+
+    #[tracing::instrument(name = "Executing 'audit list' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let page_size = match self.page_size {
+            0 => DEFAULT_PAGE_SIZE,
+            size => size.min(MAX_PAGE_SIZE),
+        };
+        // Fetch one more event to find out whether there is a next page
+        let mut events = state
+            .audit_store
+            .list(&self.filter, self.page_token, page_size + 1)
+            .await?;
+        let next_page_token = if events.len() as u64 > page_size {
+            events.truncate(page_size as usize);
+            events.last().map(|e| e.id)
+        } else {
+            None
+        };
+        Ok((events, next_page_token))
+    }
+}
@@ -0,0 +1,18 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::audit::AuditEvent;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct Record {
+    pub(crate) event: AuditEvent,
+}
+
+#[tonic::async_trait]
+impl Command for Record {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(name = "Executing 'audit record' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        Ok(state.audit_store.append(self.event.clone()).await?)
+    }
+}
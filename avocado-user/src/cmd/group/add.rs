@@ -0,0 +1,53 @@
+use crate::cmd::group::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::group::{Group, GroupError, GroupId};
+use crate::domain::tenant::TenantId;
+use crate::state::State;
+use ulid::Ulid;
+use validator::Validate;
+
+#[derive(Debug, Validate)]
+pub(crate) struct Add {
+    pub(crate) tenant_id: TenantId,
+    #[validate(length(
+        min = 2,
+        max = 64,
+        message = "length of group name must between 2 to 64"
+    ))]
+    pub(crate) name: String,
+    pub(crate) parent_id: Option<GroupId>,
+}
+
+#[tonic::async_trait]
+impl Command for Add {
+    type R = CommandResult<GroupId>;
+
+    #[tracing::instrument(name = "Executing 'group add' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        if state
+            .group_store
+            .get_by_name(&self.tenant_id, &self.name)
+            .await?
+            .is_some()
+        {
+            return Err(GroupError::AlreadyExist(self.name.clone()).into());
+        }
+        if let Some(parent_id) = self.parent_id {
+            Get {
+                tenant_id: self.tenant_id,
+                group_id: parent_id,
+            }
+            .execute(state.clone())
+            .await?;
+        }
+        let group = Group {
+            id: Ulid::new(),
+            tenant_id: self.tenant_id,
+            name: self.name.clone(),
+            parent_id: self.parent_id,
+        };
+        Ok(state.group_store.insert(group).await?)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::cmd::group::get::Get as GetGroup;
+use crate::cmd::user::get::Get as GetUser;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::group::GroupId;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::UserId;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct AddMember {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) group_id: GroupId,
+    pub(crate) user_id: UserId,
+}
+
+#[tonic::async_trait]
+impl Command for AddMember {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(name = "Executing 'group add member' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let group = GetGroup {
+            tenant_id: self.tenant_id,
+            group_id: self.group_id,
+        }
+        .execute(state.clone())
+        .await?;
+        let user = GetUser {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        if state
+            .group_store
+            .groups_of(&user.id)
+            .await?
+            .contains(&group.id)
+        {
+            return Ok(());
+        }
+        let events = [DomainEvent::member_added(
+            &user.id,
+            &group.tenant_id,
+            &group.id,
+        )];
+        Ok(state
+            .group_store
+            .add_member(&group.id, &user.id, &events)
+            .await?)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::cmd::group::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::group::GroupId;
+use crate::domain::tenant::TenantId;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct Delete {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) group_id: GroupId,
+}
+
+#[tonic::async_trait]
+impl Command for Delete {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(name = "Executing 'group delete' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let group = Get {
+            tenant_id: self.tenant_id,
+            group_id: self.group_id,
+        }
+        .execute(state.clone())
+        .await?;
+        // The members leave the group along with its removal
+        let events: Vec<DomainEvent> = state
+            .group_store
+            .members(&[group.id])
+            .await?
+            .iter()
+            .map(|user_id| DomainEvent::member_removed(user_id, &group.tenant_id, &group.id))
+            .collect();
+        Ok(state
+            .group_store
+            .delete(&group.tenant_id, &group.id, &events)
+            .await?)
+    }
+}
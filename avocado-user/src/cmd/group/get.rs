@@ -0,0 +1,27 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::group::{Group, GroupError, GroupId};
+use crate::domain::tenant::TenantId;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct Get {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) group_id: GroupId,
+}
+
+#[tonic::async_trait]
+impl Command for Get {
+    type R = CommandResult<Group>;
+
+    #[tracing::instrument(name = "Executing 'group get' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        match state.group_store.get(&self.tenant_id, &self.group_id).await {
+            Ok(Some(g)) => Ok(g),
+            _ => Err(GroupError::NotExist {
+                field: "id".to_string(),
+                value: self.group_id.to_string(),
+            }
+            .into()),
+        }
+    }
+}
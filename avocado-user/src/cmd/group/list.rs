@@ -0,0 +1,19 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::group::Group;
+use crate::domain::tenant::TenantId;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct List {
+    pub(crate) tenant_id: TenantId,
+}
+
+#[tonic::async_trait]
+impl Command for List {
+    type R = CommandResult<Vec<Group>>;
+
+    #[tracing::instrument(name = "Executing 'group list' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        Ok(state.group_store.list(&self.tenant_id).await?)
+    }
+}
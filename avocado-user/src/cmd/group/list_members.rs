@@ -0,0 +1,43 @@
+use crate::cmd::group::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::group::{GroupId, Hierarchy};
+use crate::domain::tenant::TenantId;
+use crate::domain::user::User;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct ListMembers {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) group_id: GroupId,
+    /// Also include the members of all the subgroups
+    pub(crate) recursive: bool,
+}
+
+#[tonic::async_trait]
+impl Command for ListMembers {
+    type R = CommandResult<Vec<User>>;
+
+    #[tracing::instrument(name = "Executing 'group list members' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let group = Get {
+            tenant_id: self.tenant_id,
+            group_id: self.group_id,
+        }
+        .execute(state.clone())
+        .await?;
+        let mut group_ids = vec![group.id];
+        if self.recursive {
+            let hierarchy = Hierarchy::new(state.group_store.list(&self.tenant_id).await?);
+            group_ids.extend(hierarchy.descendants(&group.id).iter().map(|g| g.id));
+        }
+
+        let mut members = vec![];
+        for user_id in state.group_store.members(&group_ids).await? {
+            if let Some(user) = state.user_store.get(&self.tenant_id, &user_id).await? {
+                members.push(user);
+            }
+        }
+        members.sort_by(|a, b| a.email.cmp(&b.email));
+        Ok(members)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::group::{Group, Hierarchy};
+use crate::domain::tenant::TenantId;
+use crate::domain::user::UserId;
+use crate::state::State;
+
+/// Lists the groups the user belongs to, either directly or through a subgroup.
+#[derive(Debug)]
+pub(crate) struct ListUserGroups {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+}
+
+#[tonic::async_trait]
+impl Command for ListUserGroups {
+    type R = CommandResult<Vec<Group>>;
+
+    #[tracing::instrument(name = "Executing 'group list user groups' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let group_ids = state.group_store.groups_of(&self.user_id).await?;
+        if group_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let hierarchy = Hierarchy::new(state.group_store.list(&self.tenant_id).await?);
+        Ok(hierarchy.expand(&group_ids).into_iter().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::group::add::Add;
+    use crate::cmd::group::add_member::AddMember;
+    use crate::cmd::group::list_members::ListMembers;
+    use crate::cmd::group::list_user_groups::ListUserGroups;
+    use crate::cmd::group::update::Update;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+
+    #[tokio::test]
+    async fn test_nested_groups() {
+        let state = State::new(connect().await).await;
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let sales = Add {
+            tenant_id: PLATFORM_ID,
+            name: "sales".to_string(),
+            parent_id: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let emea = Add {
+            tenant_id: PLATFORM_ID,
+            name: "emea".to_string(),
+            parent_id: Some(sales),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        AddMember {
+            tenant_id: PLATFORM_ID,
+            group_id: emea,
+            user_id: admin.id,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+
+        let groups = ListUserGroups {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(
+            groups.iter().map(|g| g.id).collect::<Vec<_>>(),
+            vec![emea, sales]
+        );
+
+        let members = ListMembers {
+            tenant_id: PLATFORM_ID,
+            group_id: sales,
+            recursive: false,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert!(members.is_empty());
+        let members = ListMembers {
+            tenant_id: PLATFORM_ID,
+            group_id: sales,
+            recursive: true,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(members, vec![admin]);
+
+        // A group cannot be nested under its own subgroup
+        let result = Update {
+            tenant_id: PLATFORM_ID,
+            group_id: sales,
+            name: "sales".to_string(),
+            parent_id: Some(emea),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,9 @@
+pub(crate) mod add;
+pub(crate) mod add_member;
+pub(crate) mod delete;
+pub(crate) mod get;
+pub(crate) mod list;
+pub(crate) mod list_members;
+pub(crate) mod list_user_groups;
+pub(crate) mod remove_member;
+pub(crate) mod update;
@@ -0,0 +1,46 @@
+use crate::cmd::group::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::group::GroupId;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::UserId;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct RemoveMember {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) group_id: GroupId,
+    pub(crate) user_id: UserId,
+}
+
+#[tonic::async_trait]
+impl Command for RemoveMember {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(name = "Executing 'group remove member' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let group = Get {
+            tenant_id: self.tenant_id,
+            group_id: self.group_id,
+        }
+        .execute(state.clone())
+        .await?;
+        if !state
+            .group_store
+            .groups_of(&self.user_id)
+            .await?
+            .contains(&group.id)
+        {
+            return Ok(());
+        }
+        let events = [DomainEvent::member_removed(
+            &self.user_id,
+            &group.tenant_id,
+            &group.id,
+        )];
+        Ok(state
+            .group_store
+            .remove_member(&group.id, &self.user_id, &events)
+            .await?)
+    }
+}
@@ -0,0 +1,65 @@
+use crate::cmd::group::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::group::{Group, GroupError, GroupId, Hierarchy};
+use crate::domain::tenant::TenantId;
+use crate::state::State;
+use validator::Validate;
+
+#[derive(Debug, Validate)]
+pub(crate) struct Update {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) group_id: GroupId,
+    #[validate(length(
+        min = 2,
+        max = 64,
+        message = "length of group name must between 2 to 64"
+    ))]
+    pub(crate) name: String,
+    pub(crate) parent_id: Option<GroupId>,
+}
+
+#[tonic::async_trait]
+impl Command for Update {
+    type R = CommandResult<Group>;
+
+    #[tracing::instrument(name = "Executing 'group update' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        let group = Get {
+            tenant_id: self.tenant_id,
+            group_id: self.group_id,
+        }
+        .execute(state.clone())
+        .await?;
+        if group.name != self.name
+            && state
+                .group_store
+                .get_by_name(&self.tenant_id, &self.name)
+                .await?
+                .is_some()
+        {
+            return Err(GroupError::AlreadyExist(self.name.clone()).into());
+        }
+        if let Some(parent_id) = self.parent_id {
+            Get {
+                tenant_id: self.tenant_id,
+                group_id: parent_id,
+            }
+            .execute(state.clone())
+            .await?;
+            let hierarchy = Hierarchy::new(state.group_store.list(&self.tenant_id).await?);
+            if hierarchy.creates_cycle(&group.id, &parent_id) {
+                return Err(GroupError::Cycle(group.name).into());
+            }
+        }
+
+        let group = Group {
+            name: self.name.clone(),
+            parent_id: self.parent_id,
+            ..group
+        };
+        state.group_store.update(group.clone()).await?;
+        Ok(group)
+    }
+}
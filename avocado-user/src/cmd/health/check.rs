@@ -0,0 +1,61 @@
+use crate::cmd::{Command, CommandResult};
+use crate::state::State;
+
+/// Tells whether the service, or the server for an empty name, is serving, which takes a
+/// reachable database as well.
+#[derive(Debug)]
+pub(crate) struct Check {
+    pub(crate) service: String,
+}
+
+#[tonic::async_trait]
+impl Command for Check {
+    type R = CommandResult<bool>;
+
+    #[tracing::instrument(name = "Executing 'health check' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        if !state.health.serving(&self.service)? {
+            return Ok(false);
+        }
+        match state.health_store.ping().await {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                tracing::warn!("the database is unreachable: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::health::check::Check;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::health::HealthError;
+    use crate::state::State;
+
+    #[tokio::test]
+    async fn test_check() {
+        let pool = connect().await;
+        let state = State::new(pool.clone()).await;
+        let check = |service: &str| Check {
+            service: service.to_string(),
+        };
+
+        let error = check("user.User").execute(state.clone()).await.unwrap_err();
+        assert!(matches!(
+            error.0.downcast_ref::<HealthError>(),
+            Some(HealthError::UnknownService(_))
+        ));
+
+        state.health.set_serving("user.User", true);
+        assert!(check("user.User").execute(state.clone()).await.unwrap());
+        state.health.set_serving("user.User", false);
+        assert!(!check("user.User").execute(state.clone()).await.unwrap());
+
+        state.health.set_serving("", true);
+        pool.close().await;
+        assert!(!check("").execute(state.clone()).await.unwrap());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::cmd::user::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::jwt::Claims;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserError, UserId};
+use crate::state::State;
+use chrono::Utc;
+
+/// Issues a short-lived access token for the user, carrying the impersonating actor.
+#[derive(Debug)]
+pub(crate) struct Impersonate {
+    pub(crate) actor: User,
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+}
+
+#[tonic::async_trait]
+impl Command for Impersonate {
+    type R = CommandResult<String>;
+
+    #[tracing::instrument(name = "Executing 'jwt impersonate' command", skip(self, state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        if user.id == self.actor.id {
+            return Err(
+                UserError::ImpersonationDenied("cannot impersonate oneself".to_string()).into(),
+            );
+        }
+        // Impersonating must not give the actor any permission they do not hold themselves
+        if let Some(permission) = self.actor.missing_permission(&user.roles) {
+            return Err(UserError::ImpersonationDenied(format!(
+                "user holds permission {} the actor does not",
+                permission
+            ))
+            .into());
+        }
+
+        Ok(Claims::for_user(
+            &user,
+            state.config().jwt.impersonation_token_expire_time()?,
+            Utc::now().timestamp(),
+        )
+        .with_actor(&self.actor)
+        .into_jwt_token(state.keys.read().unwrap().current())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::jwt::impersonate::Impersonate;
+    use crate::cmd::jwt::who::Who;
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::get::Get;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::role::NORMAL_USER;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_impersonate() {
+        let state = State::new(connect().await).await;
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let user_id = Add {
+            tenant_id: PLATFORM_ID,
+            email: "customer@avocado.com".to_string(),
+            first_name: "Customer".to_string(),
+            last_name: "Test".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: vec![NORMAL_USER.to_string()],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let user = Get {
+            tenant_id: PLATFORM_ID,
+            user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+
+        let token = Impersonate {
+            actor: admin.clone(),
+            tenant_id: PLATFORM_ID,
+            user_id,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let (effective, impersonator) = Who { token }.execute(state.clone()).await.unwrap();
+        assert_eq!(effective.id, user_id);
+        assert_eq!(impersonator.unwrap().0.id, admin.id);
+
+        // A user cannot impersonate someone holding more permissions
+        let result = Impersonate {
+            actor: user,
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::cmd::group::list_user_groups::ListUserGroups;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::jwt::Claims;
+use crate::domain::user::User;
+use crate::state::State;
+use chrono::Utc;
+
+/// Issues a pair of access and refresh tokens for the user.
+#[derive(Debug)]
+pub(crate) struct Issue {
+    pub(crate) user: User,
+}
+
+#[tonic::async_trait]
+impl Command for Issue {
+    type R = CommandResult<(String, String)>;
+
+    #[tracing::instrument(name = "Executing 'jwt issue' command", skip(self, state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let groups = if state.config().jwt.embed_groups {
+            ListUserGroups {
+                tenant_id: self.user.tenant_id,
+                user_id: self.user.id,
+            }
+            .execute(state.clone())
+            .await?
+            .into_iter()
+            .map(|g| g.id.to_string())
+            .collect()
+        } else {
+            vec![]
+        };
+
+        let now = Utc::now().timestamp();
+        let access_token = Claims::for_user(
+            &self.user,
+            state.config().jwt.access_token_expire_time()?,
+            now,
+        )
+        .with_groups(groups.clone())
+        .into_jwt_token(state.keys.read().unwrap().current())?;
+        let refresh_token = Claims::for_user(
+            &self.user,
+            state.config().jwt.refresh_token_expire_time()?,
+            now,
+        )
+        .with_groups(groups)
+        .into_jwt_token(state.keys.read().unwrap().current())?;
+        Ok((access_token, refresh_token))
+    }
+}
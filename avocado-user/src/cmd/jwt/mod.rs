@@ -1,3 +1,6 @@
+pub(crate) mod impersonate;
+pub(crate) mod issue;
 pub(crate) mod refresh;
+pub(crate) mod rotate_key;
 pub(crate) mod verify;
 pub(crate) mod who;
@@ -1,8 +1,7 @@
-use chrono::Utc;
-
+use crate::cmd::jwt::issue::Issue;
 use crate::cmd::jwt::who::Who;
 use crate::cmd::{Command, CommandResult};
-use crate::domain::jwt::Claims;
+use crate::domain::user::UserError;
 use crate::state::State;
 
 #[derive(Debug)]
@@ -16,24 +15,17 @@ impl Command for Refresh {
 
     #[tracing::instrument(name = "Executing 'jwt refresh' command", skip(self, state))]
     async fn execute(&self, state: State) -> Self::R {
-        let user = Who {
+        let (user, impersonator) = Who {
             token: self.refresh_token.clone(),
         }
         .execute(state.clone())
         .await?;
-        let now = Utc::now().timestamp();
-        let access_token = Claims::new(
-            user.id.to_string(),
-            state.config.jwt.access_token_expire_time()?,
-            now,
-        )
-        .into_jwt_token(state.config.rsa.private_key())?;
-        let refresh_token = Claims::new(
-            user.id.to_string(),
-            state.config.jwt.refresh_token_expire_time()?,
-            now,
-        )
-        .into_jwt_token(state.config.rsa.private_key())?;
-        Ok((access_token, refresh_token))
+        if impersonator.is_some() {
+            return Err(UserError::ImpersonationDenied(
+                "impersonated tokens cannot be refreshed".to_string(),
+            )
+            .into());
+        }
+        Issue { user }.execute(state).await
     }
 }
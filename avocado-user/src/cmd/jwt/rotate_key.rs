@@ -0,0 +1,92 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::jwt::SigningKey;
+use crate::state::State;
+use avocado_base::secret::SecretString;
+use chrono::Utc;
+
+/// Signs new tokens with another key pair, while the tokens signed before stay valid until they
+/// expire.
+#[derive(Debug)]
+pub(crate) struct RotateKey {
+    pub(crate) private_key: SecretString,
+    pub(crate) public_key: String,
+}
+
+#[tonic::async_trait]
+impl Command for RotateKey {
+    type R = CommandResult<String>;
+
+    #[tracing::instrument(name = "Executing 'jwt rotate key' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let key = SigningKey::from_pem(
+            self.private_key.expose_secret().as_bytes(),
+            self.public_key.as_bytes(),
+        )?;
+        let kid = key.kid.clone();
+        state.keys.write().unwrap().rotate(
+            key,
+            Utc::now().timestamp(),
+            state.config().jwt.longest_token_lifetime(),
+        );
+        tracing::info!("signing tokens with key {}", kid);
+        Ok(kid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::jwt::rotate_key::RotateKey;
+    use crate::cmd::jwt::verify::Verify;
+    use crate::cmd::user::login::Login;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::jwt::Claims;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    const PRIVATE_KEY: &str = include_str!("../../../tests/keys/rotated.pem");
+    const PUBLIC_KEY: &str = include_str!("../../../tests/keys/rotated.pub.pem");
+
+    #[tokio::test]
+    async fn test_rotate_key() {
+        let state = State::new(connect().await).await;
+        let login = || Login {
+            tenant: "".to_string(),
+            email: "admin@avocado.com".to_string(),
+            password: SecretString::new("kIxv4NomLT0WwGKF".to_string()),
+        };
+        let (old_token, _) = login().execute(state.clone()).await.unwrap();
+        let old_kid = state.keys.read().unwrap().current().kid.clone();
+
+        // A mismatched pair is refused and the current key is kept
+        let result = RotateKey {
+            private_key: SecretString::new(PRIVATE_KEY.to_string()),
+            public_key: std::str::from_utf8(state.config().rsa.public_key())
+                .unwrap()
+                .to_string(),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+        assert_eq!(state.keys.read().unwrap().current().kid, old_kid);
+
+        let kid = RotateKey {
+            private_key: SecretString::new(PRIVATE_KEY.to_string()),
+            public_key: PUBLIC_KEY.to_string(),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_ne!(kid, old_kid);
+        assert_eq!(state.keys.read().unwrap().current().kid, kid);
+
+        // Tokens signed by the retired key stay valid, new ones are signed by the new key
+        let verify = |token: String| Verify { token };
+        assert!(verify(old_token).execute(state.clone()).await.is_ok());
+        let (new_token, _) = login().execute(state.clone()).await.unwrap();
+        let header = jsonwebtoken::decode_header(&new_token).unwrap();
+        assert_eq!(header.kid, Some(kid));
+        let claims = Claims::from_jwt_token(new_token, &state.keys.read().unwrap());
+        assert!(claims.is_ok());
+    }
+}
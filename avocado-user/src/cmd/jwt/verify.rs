@@ -15,7 +15,7 @@ impl Command for Verify {
     async fn execute(&self, state: State) -> Self::R {
         Ok(Claims::from_jwt_token(
             self.token.clone(),
-            state.config.rsa.public_key(),
+            &state.keys.read().unwrap(),
         )?)
     }
 }
@@ -24,23 +24,23 @@ impl Command for Verify {
 mod tests {
     use crate::cmd::jwt::verify::Verify;
     use crate::cmd::Command;
-    use crate::db::sqlite::user::Store as UserStore;
+    use crate::db::sqlite::connect;
     use crate::domain::jwt::Claims;
     use crate::state::State;
     use chrono::Utc;
 
     #[tokio::test]
     async fn test_verify() {
-        let state = State::new(UserStore::new().await);
+        let state = State::new(connect().await).await;
         let now = Utc::now().timestamp();
         let claims = Claims::new(
             "Test".to_string(),
-            state.config.jwt.access_token_expire_time().unwrap(),
+            state.config().jwt.access_token_expire_time().unwrap(),
             now,
         );
         let token = claims
             .clone()
-            .into_jwt_token(state.config.rsa.private_key())
+            .into_jwt_token(state.keys.read().unwrap().current())
             .unwrap();
         let jwt_verify_cmd = Verify { token };
         let verified_claims = jwt_verify_cmd.execute(state).await.unwrap();
@@ -1,9 +1,14 @@
 use crate::cmd::jwt::verify::Verify;
 use crate::cmd::user::get::Get;
 use crate::cmd::{Command, CommandResult};
-use crate::domain::user::User as DomainUser;
+use crate::domain::user::{Impersonator, User as DomainUser};
 use crate::state::State;
+use chrono::Utc;
+use std::time::{Duration, Instant};
 
+/// Resolves the user of a token, and the impersonating user if the token is an impersonated one.
+/// The principals are cached for `principal_cache.ttl_ms`, the changes to their users and roles
+/// evict them, whichever process makes them.
 #[derive(Debug)]
 pub(crate) struct Who {
     pub(crate) token: String,
@@ -11,10 +16,17 @@ pub(crate) struct Who {
 
 #[tonic::async_trait]
 impl Command for Who {
-    type R = CommandResult<DomainUser>;
+    type R = CommandResult<(DomainUser, Option<Impersonator>)>;
 
     #[tracing::instrument(name = "Executing 'jwt who' command", skip(self, state))]
     async fn execute(&self, state: State) -> Self::R {
+        if let Some(principal) = state.principals.get(&self.token) {
+            state.metrics.principal_lookup(true);
+            return Ok(principal);
+        }
+        state.metrics.principal_lookup(false);
+        let generation = state.principals.generation();
+
         let claims = Verify {
             token: self.token.to_string(),
         }
@@ -22,7 +34,39 @@ impl Command for Who {
         .await?;
 
         let user_id = claims.get_user_id()?;
+        let tenant_id = claims.get_tenant_id()?;
+
+        // Tokens of users disabled or deleted since they were issued are no longer honoured
+        let user = Get {
+            tenant_id,
+            user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        let impersonator = match claims.get_actor()? {
+            Some((tenant_id, user_id)) => Some(Impersonator(
+                Get {
+                    tenant_id,
+                    user_id,
+                    include_inactive: false,
+                }
+                .execute(state.clone())
+                .await?,
+            )),
+            None => None,
+        };
 
-        Ok(Get { user_id }.execute(state.clone()).await?)
+        let cache = &state.config().principal_cache;
+        let lifetime = (claims.exp - Utc::now().timestamp()).max(0) as u64;
+        let expire_at = Instant::now() + cache.ttl().min(Duration::from_secs(lifetime));
+        state.principals.insert(
+            &self.token,
+            (user.clone(), impersonator.clone()),
+            expire_at,
+            generation,
+            cache.capacity,
+        );
+        Ok((user, impersonator))
     }
 }
@@ -20,5 +20,10 @@ pub(crate) trait Command {
     async fn execute(&self, state: State) -> Self::R;
 }
 
+pub(crate) mod audit;
+pub(crate) mod group;
+pub(crate) mod health;
 pub(crate) mod jwt;
+pub(crate) mod role;
+pub(crate) mod tenant;
 pub(crate) mod user;
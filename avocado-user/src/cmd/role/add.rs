@@ -0,0 +1,38 @@
+use crate::cmd::role::validate_permissions;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::role::{Role, RoleError, RoleId};
+use crate::state::State;
+use ulid::Ulid;
+use validator::Validate;
+
+#[derive(Debug, Validate)]
+pub(crate) struct Add {
+    #[validate(length(
+        min = 2,
+        max = 32,
+        message = "length of role name must between 2 to 32"
+    ))]
+    pub(crate) name: String,
+    #[validate(custom = "validate_permissions")]
+    pub(crate) permissions: Vec<String>,
+}
+
+#[tonic::async_trait]
+impl Command for Add {
+    type R = CommandResult<RoleId>;
+
+    #[tracing::instrument(name = "Executing 'role add' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        if state.role_store.get_by_name(&self.name).await?.is_some() {
+            return Err(RoleError::AlreadyExist(self.name.clone()).into());
+        }
+        let role = Role {
+            id: Ulid::new(),
+            name: self.name.clone(),
+            permissions: self.permissions.iter().cloned().collect(),
+        };
+        Ok(state.role_store.insert(role).await?)
+    }
+}
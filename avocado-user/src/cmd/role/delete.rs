@@ -0,0 +1,30 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::role::{RoleError, RoleId};
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct Delete {
+    pub(crate) role_id: RoleId,
+}
+
+#[tonic::async_trait]
+impl Command for Delete {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(name = "Executing 'role delete' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        match state.role_store.get(&self.role_id).await? {
+            Some(r) if r.is_built_in() => Err(RoleError::BuiltIn(r.name).into()),
+            Some(r) => Ok(state
+                .role_store
+                .delete(&r.id, &[DomainEvent::role_deleted(&r.id)])
+                .await?),
+            None => Err(RoleError::NotExist {
+                field: "id".to_string(),
+                value: self.role_id.to_string(),
+            }
+            .into()),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::role::Role;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct List;
+
+#[tonic::async_trait]
+impl Command for List {
+    type R = CommandResult<Vec<Role>>;
+
+    #[tracing::instrument(name = "Executing 'role list' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        Ok(state.role_store.list().await?)
+    }
+}
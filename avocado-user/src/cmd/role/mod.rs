@@ -0,0 +1,27 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use validator::ValidationError;
+
+pub(crate) mod add;
+pub(crate) mod delete;
+pub(crate) mod list;
+pub(crate) mod update;
+
+fn validate_permissions(permissions: &[String]) -> Result<(), ValidationError> {
+    let valid = |permission: &String| {
+        !permission.is_empty()
+            && permission
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ":*_-".contains(c))
+    };
+    if !permissions.iter().all(valid) {
+        return Err(ValidationError {
+            code: Cow::from("permissions"),
+            message: Some(Cow::from(
+                "permission can only contain letters, digits and the characters ':*_-'",
+            )),
+            params: HashMap::new(),
+        });
+    }
+    Ok(())
+}
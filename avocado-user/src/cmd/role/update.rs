@@ -0,0 +1,63 @@
+use crate::cmd::role::validate_permissions;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::role::{Role, RoleError, RoleId, ADMIN};
+use crate::state::State;
+use validator::Validate;
+
+#[derive(Debug, Validate)]
+pub(crate) struct Update {
+    pub(crate) role_id: RoleId,
+    #[validate(length(
+        min = 2,
+        max = 32,
+        message = "length of role name must between 2 to 32"
+    ))]
+    pub(crate) name: String,
+    #[validate(custom = "validate_permissions")]
+    pub(crate) permissions: Vec<String>,
+}
+
+#[tonic::async_trait]
+impl Command for Update {
+    type R = CommandResult<Role>;
+
+    #[tracing::instrument(name = "Executing 'role update' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        let role = match state.role_store.get(&self.role_id).await? {
+            Some(r) => r,
+            None => {
+                return Err(RoleError::NotExist {
+                    field: "id".to_string(),
+                    value: self.role_id.to_string(),
+                }
+                .into())
+            }
+        };
+        if role.name != self.name {
+            if role.is_built_in() {
+                return Err(RoleError::BuiltIn(role.name).into());
+            }
+            if state.role_store.get_by_name(&self.name).await?.is_some() {
+                return Err(RoleError::AlreadyExist(self.name.clone()).into());
+            }
+        }
+        // The admin role always keeps every permission, so nobody can lock the system out
+        if role.name == ADMIN {
+            return Err(RoleError::BuiltIn(role.name).into());
+        }
+
+        let role = Role {
+            name: self.name.clone(),
+            permissions: self.permissions.iter().cloned().collect(),
+            ..role
+        };
+        state
+            .role_store
+            .update(role.clone(), &[DomainEvent::role_updated(&role.id)])
+            .await?;
+        Ok(role)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::tenant::{Tenant, TenantError, TenantId};
+use crate::state::State;
+use ulid::Ulid;
+use validator::Validate;
+
+#[derive(Debug, Validate)]
+pub(crate) struct Add {
+    #[validate(length(
+        min = 2,
+        max = 64,
+        message = "length of tenant name must between 2 to 64"
+    ))]
+    pub(crate) name: String,
+}
+
+#[tonic::async_trait]
+impl Command for Add {
+    type R = CommandResult<TenantId>;
+
+    #[tracing::instrument(name = "Executing 'tenant add' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        if state.tenant_store.get_by_name(&self.name).await?.is_some() {
+            return Err(TenantError::AlreadyExist(self.name.clone()).into());
+        }
+        let tenant = Tenant {
+            id: Ulid::new(),
+            name: self.name.clone(),
+        };
+        Ok(state.tenant_store.insert(tenant).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::tenant::add::Add;
+    use crate::cmd::user::add::Add as AddUser;
+    use crate::cmd::user::login::Login;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_add_tenant() {
+        let state = State::new(connect().await).await;
+
+        let tenant_id = Add {
+            name: "acme".to_string(),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let result = Add {
+            name: "acme".to_string(),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+
+        // The email of the platform admin is still free in the new tenant
+        AddUser {
+            tenant_id,
+            email: "admin@avocado.com".to_string(),
+            first_name: "Acme".to_string(),
+            last_name: "Admin".to_string(),
+            password: SecretString::new("acme-password".to_string()),
+            roles: vec![],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let result = Login {
+            tenant: "acme".to_string(),
+            email: "admin@avocado.com".to_string(),
+            password: SecretString::new("acme-password".to_string()),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_ok());
+    }
+}
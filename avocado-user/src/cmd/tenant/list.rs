@@ -0,0 +1,16 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::tenant::Tenant;
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct List;
+
+#[tonic::async_trait]
+impl Command for List {
+    type R = CommandResult<Vec<Tenant>>;
+
+    #[tracing::instrument(name = "Executing 'tenant list' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        Ok(state.tenant_store.list().await?)
+    }
+}
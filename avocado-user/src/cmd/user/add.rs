@@ -1,5 +1,8 @@
 use crate::cmd::{Command, CommandResult};
-use crate::domain::user::{Role, User, UserId};
+use crate::domain::event::DomainEvent;
+use crate::domain::role::{Role, RoleError, NORMAL_USER};
+use crate::domain::tenant::{TenantError, TenantId};
+use crate::domain::user::{normalize_email, Profile, User, UserError, UserId, UserStatus};
 use crate::state::State;
 use anyhow::Result;
 use avocado_base::secret::SecretString;
@@ -10,6 +13,7 @@ use validator::{Validate, ValidationError};
 
 #[derive(Debug, Validate)]
 pub(crate) struct Add {
+    pub(crate) tenant_id: TenantId,
     #[validate(email(message = "invalid email address"))]
     pub(crate) email: String,
     #[validate(length(
@@ -26,10 +30,13 @@ pub(crate) struct Add {
     pub(crate) last_name: String,
     #[validate(custom = "validate_password")]
     pub(crate) password: SecretString,
-    pub(crate) role: Role,
+    pub(crate) roles: Vec<String>,
+    /// The user adding, who can only grant the permissions they hold. None for the setup of the
+    /// first admin.
+    pub(crate) actor: Option<User>,
 }
 
-fn validate_password(password: &SecretString) -> Result<(), ValidationError> {
+pub(crate) fn validate_password(password: &SecretString) -> Result<(), ValidationError> {
     let password = password.expose_secret();
     if password.len() <= 6 {
         return Err(ValidationError {
@@ -45,24 +52,86 @@ fn validate_password(password: &SecretString) -> Result<(), ValidationError> {
 impl Command for Add {
     type R = CommandResult<UserId>;
 
-    #[tracing::instrument(name = "Executing 'user add' command", skip(state))]
+    #[tracing::instrument(name = "Executing 'user add' command", skip(self, state), fields(email = %self.email))]
     async fn execute(&self, state: State) -> Self::R {
         self.validate()?;
 
+        if state.tenant_store.get(&self.tenant_id).await?.is_none() {
+            return Err(TenantError::NotExist {
+                field: "id".to_string(),
+                value: self.tenant_id.to_string(),
+            }
+            .into());
+        }
+        let email = normalize_email(&self.email);
+        if state
+            .user_store
+            .get_by_email(&self.tenant_id, &email)
+            .await?
+            .is_some()
+        {
+            return Err(UserError::AlreadyExist(email).into());
+        }
+        let roles = resolve_roles(&state, &self.roles).await?;
+        if let Some(actor) = &self.actor {
+            check_grant(actor, &roles)?;
+        }
+
         let user = User {
             id: Ulid::new(),
-            email: self.email.clone(),
+            tenant_id: self.tenant_id,
+            email,
             first_name: self.first_name.clone(),
             last_name: self.last_name.clone(),
             password_hash: Self::hash_password(self.password.clone()).await?,
-            role: self.role.clone(),
+            roles,
+            status: UserStatus::Active,
+            profile: Profile::default(),
         };
-        Ok(state.user_store.insert(user).await?)
+        let events = [DomainEvent::user_created(&user)];
+        Ok(state.user_store.insert(user, &events).await?)
+    }
+}
+
+/// Looks up the roles by name, new users get the normal user role when none are given.
+pub(super) async fn resolve_roles(state: &State, names: &[String]) -> CommandResult<Vec<Role>> {
+    let names = if names.is_empty() {
+        vec![NORMAL_USER.to_string()]
+    } else {
+        names.to_vec()
+    };
+    let mut roles = vec![];
+    for name in names {
+        match state.role_store.get_by_name(&name).await? {
+            Some(r) => roles.push(r),
+            None => {
+                return Err(RoleError::NotExist {
+                    field: "name".to_string(),
+                    value: name,
+                }
+                .into())
+            }
+        }
+    }
+    Ok(roles)
+}
+
+/// Refuses granting roles with a permission the actor does not hold, but the normal user role
+/// every user gets.
+pub(super) fn check_grant(actor: &User, roles: &[Role]) -> CommandResult<()> {
+    let granted = roles.iter().filter(|r| r.name != NORMAL_USER);
+    match actor.missing_permission(granted) {
+        Some(permission) => Err(UserError::PermissionDenied(format!(
+            "cannot grant permission {} the actor does not hold",
+            permission
+        ))
+        .into()),
+        None => Ok(()),
     }
 }
 
 impl Add {
-    async fn hash_password(plain_password: SecretString) -> Result<String> {
+    pub(super) async fn hash_password(plain_password: SecretString) -> Result<String> {
         tokio::task::spawn_blocking(move || User::hash_password(plain_password)).await?
     }
 }
@@ -0,0 +1,60 @@
+use crate::cmd::user::add::check_grant;
+use crate::cmd::user::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::role::RoleError;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserId};
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct AssignRole {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    pub(crate) role: String,
+    /// The user assigning, who can only grant the permissions they hold. None for internal use.
+    pub(crate) actor: Option<User>,
+}
+
+#[tonic::async_trait]
+impl Command for AssignRole {
+    type R = CommandResult<User>;
+
+    #[tracing::instrument(
+        name = "Executing 'user assign role' command",
+        skip(self, state),
+        fields(user_id = %self.user_id, role = %self.role)
+    )]
+    async fn execute(&self, state: State) -> Self::R {
+        let mut user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        let role = match state.role_store.get_by_name(&self.role).await? {
+            Some(r) => r,
+            None => {
+                return Err(RoleError::NotExist {
+                    field: "name".to_string(),
+                    value: self.role.clone(),
+                }
+                .into())
+            }
+        };
+        if let Some(actor) = &self.actor {
+            check_grant(actor, std::slice::from_ref(&role))?;
+        }
+
+        if !user.roles.iter().any(|r| r.id == role.id) {
+            user.roles.push(role);
+            let events = [DomainEvent::role_assigned(&user, &self.role)];
+            state
+                .user_store
+                .update(&user.tenant_id, &user.id, user.clone(), &events)
+                .await?;
+        }
+        Ok(user)
+    }
+}
@@ -0,0 +1,140 @@
+use crate::cmd::user::add::Add;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::role::{RoleError, ADMIN};
+use crate::domain::tenant::PLATFORM_ID;
+use crate::domain::user::{normalize_email, Profile, User, UserStatus};
+use crate::state::State;
+use avocado_base::secret::SecretString;
+use ulid::Ulid;
+use uuid::Uuid;
+
+/// Creates the first platform admin when none exists. Without a configured password a one-time
+/// setup token is logged instead, which the setup call exchanges for the admin account.
+#[derive(Debug)]
+pub(crate) struct Bootstrap {
+    pub(crate) email: String,
+    pub(crate) password: Option<SecretString>,
+}
+
+/// Whether an active user of the platform tenant holds the admin role.
+pub(super) async fn admin_exists(state: &State) -> CommandResult<bool> {
+    Ok(state
+        .user_store
+        .list(&PLATFORM_ID, false)
+        .await?
+        .iter()
+        .any(|u| u.roles.iter().any(|r| r.name == ADMIN)))
+}
+
+#[tonic::async_trait]
+impl Command for Bootstrap {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(name = "Executing 'user bootstrap' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        if admin_exists(&state).await? {
+            tracing::info!("an admin exists, skipping the bootstrap");
+            return Ok(());
+        }
+
+        let password = match &self.password {
+            Some(p) => p.clone(),
+            None => {
+                let token = Uuid::new_v4().simple().to_string();
+                tracing::warn!(
+                    "no admin exists, complete the setup with the one-time token {}",
+                    token
+                );
+                *state.setup_token.lock().unwrap() = Some(SecretString::new(token));
+                return Ok(());
+            }
+        };
+        let role = state
+            .role_store
+            .get_by_name(ADMIN)
+            .await?
+            .ok_or(RoleError::NotExist {
+                field: "name".to_string(),
+                value: ADMIN.to_string(),
+            })?;
+        let admin = User {
+            id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
+            first_name: "System".to_string(),
+            last_name: "Admin".to_string(),
+            email: normalize_email(&self.email),
+            password_hash: Add::hash_password(password).await?,
+            roles: vec![role],
+            status: UserStatus::Active,
+            profile: Profile::default(),
+        };
+        state.user_store.insert(admin, &[]).await?;
+        tracing::info!("created the admin {}", self.email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::bootstrap::Bootstrap;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_bootstrap() {
+        let state = State::new(connect().await).await;
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Skipped while an admin exists
+        Bootstrap {
+            email: "root@avocado.com".to_string(),
+            password: Some(SecretString::new("password".to_string())),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let root = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "root@avocado.com")
+            .await
+            .unwrap();
+        assert!(root.is_none());
+
+        state
+            .user_store
+            .delete(&PLATFORM_ID, &admin.id, &[])
+            .await
+            .unwrap();
+        Bootstrap {
+            email: "root@avocado.com".to_string(),
+            password: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert!(state.setup_token.lock().unwrap().is_some());
+
+        Bootstrap {
+            email: "root@avocado.com".to_string(),
+            password: Some(SecretString::new("password".to_string())),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let root = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "root@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(root.roles[0].name, "admin");
+    }
+}
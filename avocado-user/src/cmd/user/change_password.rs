@@ -0,0 +1,112 @@
+use crate::cmd::user::add::validate_password;
+use crate::cmd::user::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserError, UserId};
+use crate::state::State;
+use anyhow::Result;
+use avocado_base::secret::SecretString;
+use validator::Validate;
+
+#[derive(Debug, Validate)]
+pub(crate) struct ChangePassword {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    pub(crate) old_password: SecretString,
+    #[validate(custom = "validate_password")]
+    pub(crate) new_password: SecretString,
+}
+
+#[tonic::async_trait]
+impl Command for ChangePassword {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(name = "Executing 'user change password' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        let user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        if !Self::verify_password(self.old_password.clone(), user.password_hash.clone()).await? {
+            return Err(UserError::AuthenticationError.into());
+        }
+
+        let user = User {
+            password_hash: Self::hash_password(self.new_password.clone()).await?,
+            ..user
+        };
+        let events = [DomainEvent::password_changed(&user)];
+        let (tenant_id, user_id) = (user.tenant_id, user.id);
+        Ok(state
+            .user_store
+            .update(&tenant_id, &user_id, user, &events)
+            .await?)
+    }
+}
+
+impl ChangePassword {
+    async fn verify_password(plain_password: SecretString, password_hash: String) -> Result<bool> {
+        tokio::task::spawn_blocking(move || User::verify_password(plain_password, password_hash))
+            .await?
+    }
+
+    async fn hash_password(plain_password: SecretString) -> Result<String> {
+        tokio::task::spawn_blocking(move || User::hash_password(plain_password)).await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::change_password::ChangePassword;
+    use crate::cmd::user::login::Login;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_change_password() {
+        let state = State::new(connect().await).await;
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let result = ChangePassword {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            old_password: SecretString::new("wrong-password".to_string()),
+            new_password: SecretString::new("new-password".to_string()),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+
+        ChangePassword {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            old_password: SecretString::new("kIxv4NomLT0WwGKF".to_string()),
+            new_password: SecretString::new("new-password".to_string()),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let result = Login {
+            tenant: "".to_string(),
+            email: "admin@avocado.com".to_string(),
+            password: SecretString::new("new-password".to_string()),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::cmd::user::get::Get;
+use crate::cmd::user::reset_password::check_manage;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserError, UserId, UserStatus};
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct Delete {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    /// The user deleting, who could otherwise remove someone holding more than them. None for
+    /// internal use.
+    pub(crate) actor: Option<User>,
+}
+
+#[tonic::async_trait]
+impl Command for Delete {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(
+        name = "Executing 'user delete' command",
+        skip(self, state),
+        fields(user_id = %self.user_id)
+    )]
+    async fn execute(&self, state: State) -> Self::R {
+        let user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: true,
+        }
+        .execute(state.clone())
+        .await?;
+        if user.status == UserStatus::Deleted {
+            return Err(UserError::NotExist {
+                field: "id".to_string(),
+                value: self.user_id.to_string(),
+            }
+            .into());
+        }
+        if let Some(actor) = &self.actor {
+            check_manage(actor, &user)?;
+        }
+        Ok(state
+            .user_store
+            .delete(
+                &user.tenant_id,
+                &user.id,
+                &[DomainEvent::user_deleted(&user)],
+            )
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::delete::Delete;
+    use crate::cmd::user::update::Update;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::UserStatus;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+    use ulid::Ulid;
+
+    #[tokio::test]
+    async fn test_update_and_delete() {
+        let state = State::new(connect().await).await;
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let user = Update {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            email: "root@avocado.com".to_string(),
+            first_name: "Root".to_string(),
+            last_name: "Admin".to_string(),
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(user.email, "root@avocado.com");
+        assert_eq!(user.roles, admin.roles);
+
+        // Users holding less than the admin can neither take over their email nor delete them
+        let user_id = Add {
+            tenant_id: PLATFORM_ID,
+            email: "manager@avocado.com".to_string(),
+            first_name: "Manager".to_string(),
+            last_name: "Test".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: vec![],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let actor = state
+            .user_store
+            .get(&PLATFORM_ID, &user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = Update {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            email: "taken@avocado.com".to_string(),
+            first_name: "Root".to_string(),
+            last_name: "Admin".to_string(),
+            actor: Some(actor.clone()),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+        let result = Delete {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            actor: Some(actor),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+
+        // Users of other tenants cannot be reached
+        let result = Delete {
+            tenant_id: Ulid::new(),
+            user_id: admin.id,
+            actor: None,
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+
+        Delete {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let deleted = state
+            .user_store
+            .get(&PLATFORM_ID, &admin.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deleted.status, UserStatus::Deleted);
+        // Deleting twice reports the user as gone
+        let result = Delete {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            actor: None,
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -1,10 +1,14 @@
 use crate::cmd::{Command, CommandResult};
+use crate::domain::tenant::TenantId;
 use crate::domain::user::{User, UserError, UserId};
 use crate::state::State;
 
 #[derive(Debug)]
 pub(crate) struct Get {
+    pub(crate) tenant_id: TenantId,
     pub(crate) user_id: UserId,
+    /// Disabled and deleted users are reported as not existing unless included
+    pub(crate) include_inactive: bool,
 }
 
 #[tonic::async_trait]
@@ -13,8 +17,8 @@ impl Command for Get {
 
     #[tracing::instrument(name = "Executing 'user get' command", skip(state))]
     async fn execute(&self, state: State) -> Self::R {
-        match state.user_store.get(&self.user_id).await {
-            Ok(Some(u)) => Ok(u),
+        match state.user_store.get(&self.tenant_id, &self.user_id).await {
+            Ok(Some(u)) if u.is_active() || self.include_inactive => Ok(u),
             _ => Err(UserError::NotExist {
                 field: "id".to_string(),
                 value: self.user_id.to_string(),
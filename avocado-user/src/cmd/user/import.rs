@@ -0,0 +1,237 @@
+use crate::cmd::user::add::{check_grant, resolve_roles, Add};
+use crate::cmd::user::reset_password::check_manage;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::{TenantError, TenantId};
+use crate::domain::user::{normalize_email, User, UserError, UserId, UserStatus};
+use crate::state::State;
+use avocado_base::secret::SecretString;
+use std::collections::HashSet;
+use validator::Validate;
+
+#[derive(Debug)]
+pub(crate) struct ImportRow {
+    pub(crate) email: String,
+    pub(crate) first_name: String,
+    pub(crate) last_name: String,
+    pub(crate) password: SecretString,
+    /// The normal user role for new users and the current roles for updated ones when empty
+    pub(crate) roles: Vec<String>,
+}
+
+/// What happened to a row, or in a dry run what would have happened.
+#[derive(Debug, PartialEq)]
+pub(crate) enum RowOutcome {
+    /// The id is only known when the user was actually created
+    Created(Option<UserId>),
+    Updated(UserId),
+    Failed(String),
+}
+
+/// Adds users in bulk, each row validated by the rules of [`Add`] and imported on its own, so a
+/// failing row does not stop the others.
+#[derive(Debug)]
+pub(crate) struct Import {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) rows: Vec<ImportRow>,
+    /// Only validates the rows and reports what would happen
+    pub(crate) dry_run: bool,
+    /// Updates the users whose email already exists instead of failing the row
+    pub(crate) upsert: bool,
+    /// The user importing, who can only grant the permissions they hold and update the users
+    /// holding no more than them
+    pub(crate) actor: User,
+}
+
+#[tonic::async_trait]
+impl Command for Import {
+    /// The outcome of every row, in the order of the rows
+    type R = CommandResult<Vec<RowOutcome>>;
+
+    #[tracing::instrument(name = "Executing 'user import' command", skip(self, state))]
+    async fn execute(&self, state: State) -> Self::R {
+        if state.tenant_store.get(&self.tenant_id).await?.is_none() {
+            return Err(TenantError::NotExist {
+                field: "id".to_string(),
+                value: self.tenant_id.to_string(),
+            }
+            .into());
+        }
+
+        let mut emails = HashSet::new();
+        let mut outcomes = vec![];
+        for row in &self.rows {
+            let outcome = if emails.insert(normalize_email(&row.email)) {
+                match self.import(row, state.clone()).await {
+                    Ok(outcome) => outcome,
+                    Err(e) => RowOutcome::Failed(e.0.to_string()),
+                }
+            } else {
+                RowOutcome::Failed(format!("email {} appears more than once", row.email))
+            };
+            outcomes.push(outcome);
+        }
+        Ok(outcomes)
+    }
+}
+
+impl Import {
+    async fn import(&self, row: &ImportRow, state: State) -> CommandResult<RowOutcome> {
+        let add = Add {
+            tenant_id: self.tenant_id,
+            email: row.email.clone(),
+            first_name: row.first_name.clone(),
+            last_name: row.last_name.clone(),
+            password: row.password.clone(),
+            roles: row.roles.clone(),
+            actor: Some(self.actor.clone()),
+        };
+        add.validate()?;
+
+        match state
+            .user_store
+            .get_by_email(&self.tenant_id, &normalize_email(&row.email))
+            .await?
+        {
+            // Deleted users keep their email, they cannot be brought back by an import
+            Some(user) if !self.upsert || user.status == UserStatus::Deleted => {
+                Err(UserError::AlreadyExist(row.email.clone()).into())
+            }
+            Some(user) => {
+                // The row sets the password, which would hand the user over to the importer
+                check_manage(&self.actor, &user)?;
+                let roles = match row.roles.is_empty() {
+                    true => user.roles.clone(),
+                    false => {
+                        let roles = resolve_roles(&state, &row.roles).await?;
+                        check_grant(&self.actor, &roles)?;
+                        roles
+                    }
+                };
+                if self.dry_run {
+                    return Ok(RowOutcome::Updated(user.id));
+                }
+                let user = User {
+                    first_name: row.first_name.clone(),
+                    last_name: row.last_name.clone(),
+                    password_hash: Add::hash_password(row.password.clone()).await?,
+                    roles,
+                    ..user
+                };
+                state
+                    .user_store
+                    .update(
+                        &user.tenant_id,
+                        &user.id,
+                        user.clone(),
+                        &[DomainEvent::user_updated(&user)],
+                    )
+                    .await?;
+                Ok(RowOutcome::Updated(user.id))
+            }
+            None if self.dry_run => {
+                check_grant(&self.actor, &resolve_roles(&state, &row.roles).await?)?;
+                Ok(RowOutcome::Created(None))
+            }
+            None => Ok(RowOutcome::Created(Some(add.execute(state).await?))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::import::{Import, ImportRow, RowOutcome};
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::User;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    fn row(email: &str, first_name: &str, roles: &[&str]) -> ImportRow {
+        ImportRow {
+            email: email.to_string(),
+            first_name: first_name.to_string(),
+            last_name: "Import".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    async fn user(state: &State, email: &str) -> User {
+        state
+            .user_store
+            .get_by_email(&PLATFORM_ID, email)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_import() {
+        let state = State::new(connect().await).await;
+        let admin = user(&state, "admin@avocado.com").await;
+        let import = |dry_run: bool, upsert: bool| Import {
+            tenant_id: PLATFORM_ID,
+            rows: vec![
+                row("new@avocado.com", "New", &[]),
+                row("admin@avocado.com", "Root", &[]),
+                row("invalid", "Invalid", &[]),
+                row("role@avocado.com", "Role", &["missing"]),
+                row("new@avocado.com", "Again", &[]),
+            ],
+            dry_run,
+            upsert,
+            actor: admin.clone(),
+        };
+
+        let outcomes = import(true, true).execute(state.clone()).await.unwrap();
+        assert_eq!(outcomes[0], RowOutcome::Created(None));
+        assert!(matches!(outcomes[1], RowOutcome::Updated(_)));
+        assert!(matches!(outcomes[2], RowOutcome::Failed(_)));
+        assert!(matches!(outcomes[3], RowOutcome::Failed(_)));
+        assert!(matches!(outcomes[4], RowOutcome::Failed(_)));
+        // Nothing was written by the dry run
+        assert!(state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "new@avocado.com")
+            .await
+            .unwrap()
+            .is_none());
+
+        let outcomes = import(false, false).execute(state.clone()).await.unwrap();
+        assert!(matches!(outcomes[0], RowOutcome::Created(Some(_))));
+        assert!(matches!(outcomes[1], RowOutcome::Failed(_)));
+
+        let outcomes = import(false, true).execute(state.clone()).await.unwrap();
+        assert!(matches!(outcomes[0], RowOutcome::Updated(_)));
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(admin.first_name, "Root");
+        assert!(admin.is_platform_admin());
+
+        // Other users can neither take over nor grant more than they hold
+        let import = Import {
+            tenant_id: PLATFORM_ID,
+            rows: vec![
+                row("admin@avocado.com", "Taken", &["user"]),
+                row("escalated@avocado.com", "Escalated", &["admin"]),
+                row("new@avocado.com", "Normal", &[]),
+            ],
+            dry_run: false,
+            upsert: true,
+            actor: user(&state, "new@avocado.com").await,
+        };
+        let outcomes = import.execute(state.clone()).await.unwrap();
+        assert!(matches!(outcomes[0], RowOutcome::Failed(_)));
+        assert!(matches!(outcomes[1], RowOutcome::Failed(_)));
+        assert!(matches!(outcomes[2], RowOutcome::Updated(_)));
+        let admin = user(&state, "admin@avocado.com").await;
+        assert_eq!(admin.first_name, "Root");
+        assert!(admin.is_platform_admin());
+    }
+}
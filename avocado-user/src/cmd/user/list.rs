@@ -1,9 +1,14 @@
 use crate::cmd::{Command, CommandResult};
+use crate::domain::tenant::TenantId;
 use crate::domain::user::User;
 use crate::state::State;
 
 #[derive(Debug)]
-pub(crate) struct List;
+pub(crate) struct List {
+    pub(crate) tenant_id: TenantId,
+    /// Also lists the disabled and deleted users
+    pub(crate) include_inactive: bool,
+}
 
 #[tonic::async_trait]
 impl Command for List {
@@ -11,6 +16,9 @@ impl Command for List {
 
     #[tracing::instrument(name = "Executing 'user list' command", skip(state))]
     async fn execute(&self, state: State) -> Self::R {
-        Ok(state.user_store.list().await?)
+        Ok(state
+            .user_store
+            .list(&self.tenant_id, self.include_inactive)
+            .await?)
     }
 }
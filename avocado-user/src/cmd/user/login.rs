@@ -1,14 +1,16 @@
+use crate::cmd::jwt::issue::Issue;
 use crate::cmd::{Command, CommandResult};
-use crate::domain::jwt::Claims;
-use crate::domain::user::{User, UserError};
+use crate::domain::tenant::PLATFORM;
+use crate::domain::user::{normalize_email, User, UserError};
 use crate::state::State;
 use anyhow::Result;
 use avocado_base::secret::SecretString;
-use chrono::Utc;
 use validator::Validate;
 
 #[derive(Debug, Validate)]
 pub(crate) struct Login {
+    /// Name of the tenant, the platform tenant when empty
+    pub(crate) tenant: String,
     #[validate(email(message = "invalid email address"))]
     pub(crate) email: String,
     pub(crate) password: SecretString,
@@ -22,27 +24,29 @@ impl Command for Login {
     async fn execute(&self, state: State) -> Self::R {
         self.validate()?;
 
-        match state.user_store.get_by_email(self.email.as_str()).await? {
+        let tenant_name = match self.tenant.as_str() {
+            "" => PLATFORM,
+            name => name,
+        };
+        // An unknown tenant fails like wrong credentials, so logins can't tell which tenants exist
+        let tenant = match state.tenant_store.get_by_name(tenant_name).await? {
+            Some(t) => t,
+            None => return Err(UserError::AuthenticationError.into()),
+        };
+
+        match state
+            .user_store
+            .get_by_email(&tenant.id, &normalize_email(&self.email))
+            .await?
+        {
             Some(u)
                 if Self::verify_password(self.password.clone(), u.password_hash.clone())
                     .await? =>
             {
-                let now = Utc::now().timestamp();
-                let claims = Claims::new(
-                    u.id.to_string(),
-                    state.config.jwt.access_token_expire_time()?,
-                    now,
-                );
-                let access_token = claims.into_jwt_token(state.config.rsa.private_key())?;
-
-                let claims = Claims::new(
-                    u.id.to_string(),
-                    state.config.jwt.refresh_token_expire_time()?,
-                    now,
-                );
-                let refresh_token = claims.into_jwt_token(state.config.rsa.private_key())?;
-
-                Ok((access_token, refresh_token))
+                if !u.is_active() {
+                    return Err(UserError::Inactive(u.email).into());
+                }
+                Issue { user: u }.execute(state).await
             }
             _ => Err(UserError::AuthenticationError.into()),
         }
@@ -60,15 +64,16 @@ impl Login {
 mod tests {
     use crate::cmd::user::login::Login;
     use crate::cmd::Command;
-    use crate::db::sqlite::user::Store as UserStore;
+    use crate::db::sqlite::connect;
     use crate::state::State;
     use avocado_base::secret::SecretString;
 
     #[tokio::test]
     async fn test_login() {
-        let state = State::new(UserStore::new().await);
+        let state = State::new(connect().await).await;
 
         let login_cmd = Login {
+            tenant: "".to_string(),
             email: "admin@avocado.com".to_string(),
             password: SecretString::new("kIxv4NomLT0WwGKF".to_string()),
         };
@@ -76,17 +81,26 @@ mod tests {
         assert!(result.is_ok());
 
         let login_cmd = Login {
+            tenant: "".to_string(),
             email: "admin@avocado.com".to_string(),
             password: SecretString::new("secureitis".to_string()),
         };
-        let result = login_cmd.execute(state.clone()).await;
-        assert!(result.is_err());
+        let wrong_password = login_cmd.execute(state.clone()).await.unwrap_err();
 
         let login_cmd = Login {
+            tenant: "".to_string(),
             email: "".to_string(),
             password: SecretString::new("pass".to_string()),
         };
         let result = login_cmd.execute(state.clone()).await;
-        assert!(result.is_err())
+        assert!(result.is_err());
+
+        let login_cmd = Login {
+            tenant: "acme".to_string(),
+            email: "admin@avocado.com".to_string(),
+            password: SecretString::new("kIxv4NomLT0WwGKF".to_string()),
+        };
+        let unknown_tenant = login_cmd.execute(state.clone()).await.unwrap_err();
+        assert_eq!(unknown_tenant.0.to_string(), wrong_password.0.to_string());
     }
 }
@@ -1,4 +1,16 @@
 pub(crate) mod add;
+pub(crate) mod assign_role;
+pub(crate) mod bootstrap;
+pub(crate) mod change_password;
+pub(crate) mod delete;
 pub(crate) mod get;
+pub(crate) mod import;
 pub(crate) mod list;
 pub(crate) mod login;
+pub(crate) mod reset_password;
+pub(crate) mod revoke_role;
+pub(crate) mod set_status;
+pub(crate) mod setup;
+pub(crate) mod update;
+pub(crate) mod update_profile;
+pub(crate) mod watch;
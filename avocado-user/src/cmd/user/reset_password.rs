@@ -0,0 +1,133 @@
+use crate::cmd::user::add::{validate_password, Add};
+use crate::cmd::user::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserError, UserId};
+use crate::state::State;
+use avocado_base::secret::SecretString;
+use validator::Validate;
+
+/// Sets a new password for a user on behalf of an admin, without knowing the old one.
+#[derive(Debug, Validate)]
+pub(crate) struct ResetPassword {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    #[validate(custom = "validate_password")]
+    pub(crate) new_password: SecretString,
+    /// The user resetting, who could otherwise sign in as someone holding more than them
+    pub(crate) actor: User,
+}
+
+/// Refuses acting on a user holding a permission the actor does not, the rule of impersonation.
+pub(super) fn check_manage(actor: &User, user: &User) -> CommandResult<()> {
+    match actor.missing_permission(&user.roles) {
+        Some(permission) => Err(UserError::PermissionDenied(format!(
+            "user holds permission {} the actor does not",
+            permission
+        ))
+        .into()),
+        None => Ok(()),
+    }
+}
+
+#[tonic::async_trait]
+impl Command for ResetPassword {
+    type R = CommandResult<()>;
+
+    #[tracing::instrument(
+        name = "Executing 'user reset password' command",
+        skip(self, state),
+        fields(user_id = %self.user_id)
+    )]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        let user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        check_manage(&self.actor, &user)?;
+        let user = User {
+            password_hash: Add::hash_password(self.new_password.clone()).await?,
+            ..user
+        };
+        let events = [DomainEvent::password_changed(&user)];
+        let (tenant_id, user_id) = (user.tenant_id, user.id);
+        Ok(state
+            .user_store
+            .update(&tenant_id, &user_id, user, &events)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::login::Login;
+    use crate::cmd::user::reset_password::ResetPassword;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::User;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    async fn user(state: &State, email: &str) -> User {
+        state
+            .user_store
+            .get_by_email(&PLATFORM_ID, email)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reset_password() {
+        let state = State::new(connect().await).await;
+        let user_id = Add {
+            tenant_id: PLATFORM_ID,
+            email: "reset@avocado.com".to_string(),
+            first_name: "Reset".to_string(),
+            last_name: "Test".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: vec![],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+
+        let admin = user(&state, "admin@avocado.com").await;
+        let reset = |password: &str| ResetPassword {
+            tenant_id: PLATFORM_ID,
+            user_id,
+            new_password: SecretString::new(password.to_string()),
+            actor: admin.clone(),
+        };
+        assert!(reset("short").execute(state.clone()).await.is_err());
+        reset("new-password").execute(state.clone()).await.unwrap();
+        let result = Login {
+            tenant: "".to_string(),
+            email: "reset@avocado.com".to_string(),
+            password: SecretString::new("new-password".to_string()),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_ok());
+
+        // Nobody can take over a user holding more permissions than them
+        let result = ResetPassword {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            new_password: SecretString::new("new-password".to_string()),
+            actor: user(&state, "reset@avocado.com").await,
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+    }
+}
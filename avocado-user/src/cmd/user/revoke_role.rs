@@ -0,0 +1,159 @@
+use crate::cmd::user::add::check_grant;
+use crate::cmd::user::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserId};
+use crate::state::State;
+
+#[derive(Debug)]
+pub(crate) struct RevokeRole {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    pub(crate) role: String,
+    /// The user revoking, who can only take away the permissions they hold. None for internal use.
+    pub(crate) actor: Option<User>,
+}
+
+#[tonic::async_trait]
+impl Command for RevokeRole {
+    type R = CommandResult<User>;
+
+    #[tracing::instrument(
+        name = "Executing 'user revoke role' command",
+        skip(self, state),
+        fields(user_id = %self.user_id, role = %self.role)
+    )]
+    async fn execute(&self, state: State) -> Self::R {
+        let mut user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+
+        if let Some(role) = user.roles.iter().find(|r| r.name == self.role) {
+            if let Some(actor) = &self.actor {
+                check_grant(actor, std::slice::from_ref(role))?;
+            }
+            user.roles.retain(|r| r.name != self.role);
+            let events = [DomainEvent::role_revoked(&user, &self.role)];
+            state
+                .user_store
+                .update(&user.tenant_id, &user.id, user.clone(), &events)
+                .await?;
+        }
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::assign_role::AssignRole;
+    use crate::cmd::user::revoke_role::RevokeRole;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::role::{permission, ADMIN, NORMAL_USER};
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_assign_and_revoke_role() {
+        let state = State::new(connect().await).await;
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let plain_id = Add {
+            tenant_id: PLATFORM_ID,
+            email: "plain@avocado.com".to_string(),
+            first_name: "Plain".to_string(),
+            last_name: "User".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: vec![],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let plain = state
+            .user_store
+            .get(&PLATFORM_ID, &plain_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let user = AssignRole {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            role: NORMAL_USER.to_string(),
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(user.role_names(), vec![ADMIN, NORMAL_USER]);
+
+        let result = AssignRole {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            role: "unknown".to_string(),
+            actor: None,
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+
+        // A user without the permissions of the admin can neither grant nor take them away
+        for result in [
+            AssignRole {
+                tenant_id: PLATFORM_ID,
+                user_id: admin.id,
+                role: ADMIN.to_string(),
+                actor: Some(plain.clone()),
+            }
+            .execute(state.clone())
+            .await,
+            RevokeRole {
+                tenant_id: PLATFORM_ID,
+                user_id: admin.id,
+                role: ADMIN.to_string(),
+                actor: Some(plain.clone()),
+            }
+            .execute(state.clone())
+            .await,
+        ] {
+            let error = result.unwrap_err().0.to_string();
+            assert!(
+                error.starts_with("permission denied: cannot grant"),
+                "{}",
+                error
+            );
+        }
+
+        let user = RevokeRole {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            role: ADMIN.to_string(),
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(user.role_names(), vec![NORMAL_USER]);
+        assert!(!user.has_permission(permission::USER_READ));
+
+        let user = state
+            .user_store
+            .get(&PLATFORM_ID, &admin.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.role_names(), vec![NORMAL_USER]);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::cmd::user::get::Get;
+use crate::cmd::user::reset_password::check_manage;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserError, UserId, UserStatus};
+use crate::state::State;
+
+/// Disables or re-enables a user, deleted users cannot be brought back.
+#[derive(Debug)]
+pub(crate) struct SetStatus {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    pub(crate) enabled: bool,
+    /// The user changing the status, who could otherwise lock out someone holding more than them.
+    /// None for internal use.
+    pub(crate) actor: Option<User>,
+}
+
+#[tonic::async_trait]
+impl Command for SetStatus {
+    type R = CommandResult<User>;
+
+    #[tracing::instrument(
+        name = "Executing 'user set status' command",
+        skip(self, state),
+        fields(user_id = %self.user_id, enabled = self.enabled)
+    )]
+    async fn execute(&self, state: State) -> Self::R {
+        let user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: true,
+        }
+        .execute(state.clone())
+        .await?;
+        if user.status == UserStatus::Deleted {
+            return Err(UserError::NotExist {
+                field: "id".to_string(),
+                value: self.user_id.to_string(),
+            }
+            .into());
+        }
+        if let Some(actor) = &self.actor {
+            check_manage(actor, &user)?;
+        }
+
+        let (status, event): (_, fn(&User) -> DomainEvent) = match self.enabled {
+            true => (UserStatus::Active, DomainEvent::user_enabled),
+            false => (UserStatus::Disabled, DomainEvent::user_disabled),
+        };
+        if user.status == status {
+            return Ok(user);
+        }
+        let user = User { status, ..user };
+        state
+            .user_store
+            .update(&user.tenant_id, &user.id, user.clone(), &[event(&user)])
+            .await?;
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::jwt::who::Who;
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::login::Login;
+    use crate::cmd::user::set_status::SetStatus;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::UserStatus;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_set_status() {
+        let state = State::new(connect().await).await;
+        let user_id = Add {
+            tenant_id: PLATFORM_ID,
+            email: "disabled@avocado.com".to_string(),
+            first_name: "Disabled".to_string(),
+            last_name: "Test".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: vec![],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let login = || Login {
+            tenant: "".to_string(),
+            email: "disabled@avocado.com".to_string(),
+            password: SecretString::new("password".to_string()),
+        };
+        let (access_token, _) = login().execute(state.clone()).await.unwrap();
+
+        let user = SetStatus {
+            tenant_id: PLATFORM_ID,
+            user_id,
+            enabled: false,
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(user.status, UserStatus::Disabled);
+        assert!(login().execute(state.clone()).await.is_err());
+        let who = Who {
+            token: access_token.clone(),
+        };
+        assert!(who.execute(state.clone()).await.is_err());
+
+        SetStatus {
+            tenant_id: PLATFORM_ID,
+            user_id,
+            enabled: true,
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert!(login().execute(state.clone()).await.is_ok());
+        let who = Who {
+            token: access_token,
+        };
+        assert!(who.execute(state.clone()).await.is_ok());
+
+        // Nor can they lock out a user holding more than them
+        let actor = state
+            .user_store
+            .get(&PLATFORM_ID, &user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let result = SetStatus {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            enabled: false,
+            actor: Some(actor),
+        }
+        .execute(state.clone())
+        .await;
+        assert!(result.is_err());
+        let admin = state.user_store.get(&PLATFORM_ID, &admin.id).await.unwrap();
+        assert_eq!(admin.unwrap().status, UserStatus::Active);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::cmd::user::add::Add;
+use crate::cmd::user::bootstrap::admin_exists;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::role::ADMIN;
+use crate::domain::tenant::PLATFORM_ID;
+use crate::domain::user::{UserError, UserId};
+use crate::state::State;
+use avocado_base::secret::SecretString;
+
+/// Exchanges the one-time setup token logged by the bootstrap for the first platform admin.
+#[derive(Debug)]
+pub(crate) struct Setup {
+    pub(crate) setup_token: SecretString,
+    pub(crate) email: String,
+    pub(crate) first_name: String,
+    pub(crate) last_name: String,
+    pub(crate) password: SecretString,
+}
+
+#[tonic::async_trait]
+impl Command for Setup {
+    type R = CommandResult<UserId>;
+
+    #[tracing::instrument(name = "Executing 'user setup' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        // The token is taken out while the admin is added, so concurrent setups cannot both pass
+        let token = {
+            let mut token = state.setup_token.lock().unwrap();
+            match token.as_ref() {
+                Some(t) if t.expose_secret() == self.setup_token.expose_secret() => token.take(),
+                _ => None,
+            }
+        };
+        let Some(token) = token else {
+            return Err(UserError::SetupDenied("invalid setup token".to_string()).into());
+        };
+        if admin_exists(&state).await? {
+            return Err(UserError::SetupDenied("an admin already exists".to_string()).into());
+        }
+
+        let result = Add {
+            tenant_id: PLATFORM_ID,
+            email: self.email.clone(),
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            password: self.password.clone(),
+            roles: vec![ADMIN.to_string()],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await;
+        if result.is_err() {
+            *state.setup_token.lock().unwrap() = Some(token);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::setup::Setup;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_setup() {
+        let state = State::new(connect().await).await;
+        let setup = |token: &str, password: &str| Setup {
+            setup_token: SecretString::new(token.to_string()),
+            email: "root@avocado.com".to_string(),
+            first_name: "Root".to_string(),
+            last_name: "Admin".to_string(),
+            password: SecretString::new(password.to_string()),
+        };
+        *state.setup_token.lock().unwrap() = Some(SecretString::new("token".to_string()));
+
+        // Refused while the bootstrapped admin exists
+        assert!(setup("token", "password")
+            .execute(state.clone())
+            .await
+            .is_err());
+
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+        state
+            .user_store
+            .delete(&PLATFORM_ID, &admin.id, &[])
+            .await
+            .unwrap();
+        *state.setup_token.lock().unwrap() = Some(SecretString::new("token".to_string()));
+        assert!(setup("wrong", "password")
+            .execute(state.clone())
+            .await
+            .is_err());
+        // An invalid admin keeps the token usable
+        assert!(setup("token", "short")
+            .execute(state.clone())
+            .await
+            .is_err());
+        setup("token", "password")
+            .execute(state.clone())
+            .await
+            .unwrap();
+        assert!(setup("token", "password")
+            .execute(state.clone())
+            .await
+            .is_err());
+    }
+}
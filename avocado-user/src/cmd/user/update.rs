@@ -0,0 +1,83 @@
+use crate::cmd::user::get::Get;
+use crate::cmd::user::reset_password::check_manage;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{normalize_email, User, UserError, UserId};
+use crate::state::State;
+use validator::Validate;
+
+#[derive(Debug, Validate)]
+pub(crate) struct Update {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    #[validate(email(message = "invalid email address"))]
+    pub(crate) email: String,
+    #[validate(length(
+        min = 2,
+        max = 32,
+        message = "length of first name must between 2 to 32"
+    ))]
+    pub(crate) first_name: String,
+    #[validate(length(
+        min = 2,
+        max = 32,
+        message = "length of last name must between 2 to 32"
+    ))]
+    pub(crate) last_name: String,
+    /// The user updating, who could otherwise take over the email of someone holding more than
+    /// them. None for internal use.
+    pub(crate) actor: Option<User>,
+}
+
+#[tonic::async_trait]
+impl Command for Update {
+    type R = CommandResult<User>;
+
+    #[tracing::instrument(
+        name = "Executing 'user update' command",
+        skip(self, state),
+        fields(user_id = %self.user_id)
+    )]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+
+        let user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        if let Some(actor) = &self.actor {
+            check_manage(actor, &user)?;
+        }
+        let email = normalize_email(&self.email);
+        if let Some(other) = state
+            .user_store
+            .get_by_email(&self.tenant_id, &email)
+            .await?
+        {
+            if other.id != user.id {
+                return Err(UserError::AlreadyExist(email).into());
+            }
+        }
+
+        let user = User {
+            email,
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            ..user
+        };
+        state
+            .user_store
+            .update(
+                &user.tenant_id,
+                &user.id,
+                user.clone(),
+                &[DomainEvent::user_updated(&user)],
+            )
+            .await?;
+        Ok(user)
+    }
+}
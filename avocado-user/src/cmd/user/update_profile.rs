@@ -0,0 +1,256 @@
+use crate::cfg::{AttributeType, Profile as ProfileSchema};
+use crate::cmd::user::get::Get;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{Profile, User, UserId};
+use crate::state::State;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+#[derive(Debug, Validate)]
+pub(crate) struct UpdateProfile {
+    pub(crate) tenant_id: TenantId,
+    pub(crate) user_id: UserId,
+    #[validate(custom = "validate_phone")]
+    pub(crate) phone: String,
+    #[validate(length(max = 64, message = "length of job title must be at most 64"))]
+    pub(crate) job_title: String,
+    #[validate(custom = "validate_locale")]
+    pub(crate) locale: String,
+    #[validate(custom = "validate_timezone")]
+    pub(crate) timezone: String,
+    pub(crate) attributes: Map<String, Value>,
+}
+
+fn invalid(code: &'static str, message: String) -> ValidationError {
+    ValidationError {
+        code: Cow::from(code),
+        message: Some(Cow::from(message)),
+        params: HashMap::new(),
+    }
+}
+
+/// An E.164 number such as +4915112345678.
+fn validate_phone(phone: &str) -> Result<(), ValidationError> {
+    match phone.strip_prefix('+') {
+        _ if phone.is_empty() => Ok(()),
+        Some(digits)
+            if (7..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            Ok(())
+        }
+        _ => Err(invalid(
+            "phone",
+            "phone needs to be in E.164 format, such as +4915112345678".to_string(),
+        )),
+    }
+}
+
+/// A language tag such as en or en-US.
+fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    let mut parts = locale.split('-');
+    let language = parts.next().unwrap_or_default();
+    let valid = (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && parts
+            .all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()));
+    match locale.is_empty() || valid {
+        true => Ok(()),
+        false => Err(invalid(
+            "locale",
+            "locale needs to be a language tag, such as en-US".to_string(),
+        )),
+    }
+}
+
+/// An IANA time zone name such as Europe/Berlin, or UTC.
+fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    let valid = timezone == "UTC"
+        || (timezone.contains('/')
+            && timezone.split('/').all(|p| {
+                !p.is_empty()
+                    && p.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "_-+".contains(c))
+            }));
+    match timezone.is_empty() || valid {
+        true => Ok(()),
+        false => Err(invalid(
+            "timezone",
+            "timezone needs to be an IANA time zone, such as Europe/Berlin".to_string(),
+        )),
+    }
+}
+
+/// Checks the attributes against the configured schema.
+fn validate_attributes(
+    schema: &ProfileSchema,
+    attributes: &Map<String, Value>,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    for attribute in &schema.attributes {
+        match attributes.get(&attribute.name) {
+            None | Some(Value::Null) if attribute.required => errors.add(
+                "attributes",
+                invalid(
+                    "required",
+                    format!("attribute {} is required", attribute.name),
+                ),
+            ),
+            None | Some(Value::Null) => {}
+            Some(value) => {
+                let matches = match attribute.kind {
+                    AttributeType::String => value.is_string(),
+                    AttributeType::Number => value.is_number(),
+                    AttributeType::Boolean => value.is_boolean(),
+                };
+                if !matches {
+                    errors.add(
+                        "attributes",
+                        invalid(
+                            "type",
+                            format!(
+                                "attribute {} must be a {:?}",
+                                attribute.name, attribute.kind
+                            )
+                            .to_lowercase(),
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    if !schema.allow_unknown_attributes {
+        for name in attributes.keys() {
+            if !schema.attributes.iter().any(|a| &a.name == name) {
+                errors.add(
+                    "attributes",
+                    invalid("unknown", format!("attribute {} is unknown", name)),
+                );
+            }
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+#[tonic::async_trait]
+impl Command for UpdateProfile {
+    type R = CommandResult<User>;
+
+    #[tracing::instrument(name = "Executing 'user update profile' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        self.validate()?;
+        validate_attributes(&state.config().profile, &self.attributes)?;
+
+        let user = Get {
+            tenant_id: self.tenant_id,
+            user_id: self.user_id,
+            include_inactive: false,
+        }
+        .execute(state.clone())
+        .await?;
+        let user = User {
+            profile: Profile {
+                phone: self.phone.clone(),
+                job_title: self.job_title.clone(),
+                locale: self.locale.clone(),
+                timezone: self.timezone.clone(),
+                // Unset attributes are dropped rather than stored as null
+                attributes: self
+                    .attributes
+                    .iter()
+                    .filter(|(_, v)| !v.is_null())
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            },
+            ..user
+        };
+        state
+            .user_store
+            .update(
+                &user.tenant_id,
+                &user.id,
+                user.clone(),
+                &[DomainEvent::user_updated(&user)],
+            )
+            .await?;
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cfg::{Attribute, AttributeType, Profile as ProfileSchema};
+    use crate::cmd::user::update_profile::{validate_attributes, UpdateProfile};
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+    use serde_json::{json, Map, Value};
+
+    fn attributes(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_update_profile() {
+        let state = State::new(connect().await).await;
+        let admin = state
+            .user_store
+            .get_by_email(&PLATFORM_ID, "admin@avocado.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let update = |phone: &str| UpdateProfile {
+            tenant_id: PLATFORM_ID,
+            user_id: admin.id,
+            phone: phone.to_string(),
+            job_title: "Administrator".to_string(),
+            locale: "en-US".to_string(),
+            timezone: "Europe/Berlin".to_string(),
+            attributes: attributes(json!({"employee_id": "E-1", "desk": null})),
+        };
+
+        assert!(update("12345").execute(state.clone()).await.is_err());
+        let user = update("+4915112345678")
+            .execute(state.clone())
+            .await
+            .unwrap();
+        let stored = state
+            .user_store
+            .get(&PLATFORM_ID, &admin.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.profile, user.profile);
+        assert_eq!(
+            stored.profile.attributes,
+            attributes(json!({"employee_id": "E-1"}))
+        );
+    }
+
+    #[test]
+    fn test_validate_attributes() {
+        let schema = ProfileSchema {
+            attributes: vec![Attribute {
+                name: "employee_id".to_string(),
+                kind: AttributeType::String,
+                required: true,
+            }],
+            allow_unknown_attributes: false,
+        };
+        assert!(validate_attributes(&schema, &attributes(json!({"employee_id": "E-1"}))).is_ok());
+        assert!(validate_attributes(&schema, &attributes(json!({}))).is_err());
+        assert!(validate_attributes(&schema, &attributes(json!({"employee_id": 1}))).is_err());
+        assert!(validate_attributes(
+            &schema,
+            &attributes(json!({"employee_id": "E-1", "desk": "A1"}))
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::cmd::{Command, CommandResult};
+use crate::domain::event::OutboxEvent;
+use crate::domain::tenant::TenantId;
+use crate::state::State;
+
+/// Reads the next user events of a tenant, used to stream the changes to watchers.
+#[derive(Debug)]
+pub(crate) struct Watch {
+    pub(crate) tenant_id: TenantId,
+    /// The sequence number of the last event seen by the watcher
+    pub(crate) after: i64,
+    pub(crate) limit: u64,
+}
+
+#[tonic::async_trait]
+impl Command for Watch {
+    /// The events of the tenant and the sequence number to continue after, which also skips the
+    /// events of other tenants and of the roles
+    type R = CommandResult<(Vec<OutboxEvent>, i64)>;
+
+    #[tracing::instrument(name = "Executing 'user watch' command", skip(state))]
+    async fn execute(&self, state: State) -> Self::R {
+        let events = state.outbox_store.list(self.after, self.limit).await?;
+        let next = events.last().map_or(self.after, |e| e.seq);
+        let events = events
+            .into_iter()
+            .filter(|e| e.event.tenant_id() == Some(&self.tenant_id))
+            .collect();
+        Ok((events, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::tenant::add::Add as AddTenant;
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::watch::Watch;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::event::DomainEvent;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+
+    #[tokio::test]
+    async fn test_watch() {
+        let state = State::new(connect().await).await;
+        let tenant_id = AddTenant {
+            name: "watch".to_string(),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        for (tenant_id, email) in [
+            (PLATFORM_ID, "watch1@avocado.com"),
+            (tenant_id, "watch2@avocado.com"),
+            (PLATFORM_ID, "watch3@avocado.com"),
+        ] {
+            Add {
+                tenant_id,
+                email: email.to_string(),
+                first_name: "Watch".to_string(),
+                last_name: "Test".to_string(),
+                password: SecretString::new("password".to_string()),
+                roles: vec![],
+                actor: None,
+            }
+            .execute(state.clone())
+            .await
+            .unwrap();
+        }
+
+        let (events, next) = Watch {
+            tenant_id: PLATFORM_ID,
+            after: 0,
+            limit: 2,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(next, 2);
+
+        let (events, next) = Watch {
+            tenant_id: PLATFORM_ID,
+            after: next,
+            limit: 2,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0].event,
+            DomainEvent::UserCreated { email, .. } if email == "watch3@avocado.com"
+        ));
+        assert_eq!(next, 3);
+    }
+}
@@ -1,15 +1,113 @@
+use crate::domain::audit::{AuditEvent, AuditEventId, AuditFilter};
+use crate::domain::event::{DomainEvent, OutboxEvent};
+use crate::domain::group::{Group, GroupId};
+use crate::domain::role::{Role, RoleId};
+use crate::domain::tenant::{Tenant, TenantId};
 use crate::domain::user::{User, UserId};
 use anyhow::Result;
 use std::fmt::Debug;
 
 #[tonic::async_trait]
 pub(crate) trait UserStore: Send + Sync + Debug {
-    async fn insert(&self, user: User) -> Result<UserId>;
-    async fn get(&self, user_id: &UserId) -> Result<Option<User>>;
-    async fn get_by_email(&self, email: &str) -> Result<Option<User>>;
-    async fn list(&self) -> Result<Vec<User>>;
-    async fn delete(&self, user_id: &UserId) -> Result<()>;
-    async fn update(&self, user_id: &UserId, user: User) -> Result<()>;
+    // The changes and their events are written in the same transaction, so an event is
+    // recorded if and only if its change is.
+    async fn insert(&self, user: User, events: &[DomainEvent]) -> Result<UserId>;
+    async fn get(&self, tenant_id: &TenantId, user_id: &UserId) -> Result<Option<User>>;
+    async fn get_by_email(&self, tenant_id: &TenantId, email: &str) -> Result<Option<User>>;
+    async fn list(&self, tenant_id: &TenantId, include_inactive: bool) -> Result<Vec<User>>;
+    /// Marks the user as deleted and removes their group memberships.
+    async fn delete(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()>;
+    async fn update(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        user: User,
+        events: &[DomainEvent],
+    ) -> Result<()>;
+}
+
+#[tonic::async_trait]
+pub(crate) trait RoleStore: Send + Sync + Debug {
+    async fn insert(&self, role: Role) -> Result<RoleId>;
+    async fn get(&self, role_id: &RoleId) -> Result<Option<Role>>;
+    async fn get_by_name(&self, name: &str) -> Result<Option<Role>>;
+    async fn list(&self) -> Result<Vec<Role>>;
+    // Like those of the users, the changes are written along with their events
+    async fn update(&self, role: Role, events: &[DomainEvent]) -> Result<()>;
+    async fn delete(&self, role_id: &RoleId, events: &[DomainEvent]) -> Result<()>;
+}
+
+#[tonic::async_trait]
+pub(crate) trait GroupStore: Send + Sync + Debug {
+    async fn insert(&self, group: Group) -> Result<GroupId>;
+    async fn get(&self, tenant_id: &TenantId, group_id: &GroupId) -> Result<Option<Group>>;
+    async fn get_by_name(&self, tenant_id: &TenantId, name: &str) -> Result<Option<Group>>;
+    async fn list(&self, tenant_id: &TenantId) -> Result<Vec<Group>>;
+    async fn update(&self, group: Group) -> Result<()>;
+    // The membership changes are written along with their events
+    async fn delete(
+        &self,
+        tenant_id: &TenantId,
+        group_id: &GroupId,
+        events: &[DomainEvent],
+    ) -> Result<()>;
+    async fn add_member(
+        &self,
+        group_id: &GroupId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()>;
+    async fn remove_member(
+        &self,
+        group_id: &GroupId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()>;
+    async fn members(&self, group_ids: &[GroupId]) -> Result<Vec<UserId>>;
+    async fn groups_of(&self, user_id: &UserId) -> Result<Vec<GroupId>>;
+}
+
+#[tonic::async_trait]
+pub(crate) trait TenantStore: Send + Sync + Debug {
+    async fn insert(&self, tenant: Tenant) -> Result<TenantId>;
+    async fn get(&self, tenant_id: &TenantId) -> Result<Option<Tenant>>;
+    async fn get_by_name(&self, name: &str) -> Result<Option<Tenant>>;
+    async fn list(&self) -> Result<Vec<Tenant>>;
+}
+
+/// The audit log is append-only, events can never be changed or removed.
+#[tonic::async_trait]
+pub(crate) trait AuditStore: Send + Sync + Debug {
+    async fn append(&self, event: AuditEvent) -> Result<()>;
+    /// Lists the matching events newest first, starting after the given event.
+    async fn list(
+        &self,
+        filter: &AuditFilter,
+        after: Option<AuditEventId>,
+        limit: u64,
+    ) -> Result<Vec<AuditEvent>>;
+}
+
+#[tonic::async_trait]
+pub(crate) trait OutboxStore: Send + Sync + Debug {
+    /// The events not published yet, oldest first.
+    async fn pending(&self, limit: u64) -> Result<Vec<OutboxEvent>>;
+    async fn mark_published(&self, seq: i64) -> Result<()>;
+    /// The events after the sequence number, whether published or not, oldest first.
+    async fn list(&self, after: i64, limit: u64) -> Result<Vec<OutboxEvent>>;
+    /// The sequence number of the latest event, 0 while there is none.
+    async fn last_seq(&self) -> Result<i64>;
+}
+
+#[tonic::async_trait]
+pub(crate) trait HealthStore: Send + Sync + Debug {
+    /// Fails when the database is unreachable.
+    async fn ping(&self) -> Result<()>;
 }
 
 pub mod sqlite;
@@ -0,0 +1,239 @@
+use crate::db::AuditStore;
+use crate::domain::audit::{AuditEvent, AuditEventId, AuditFilter, Outcome};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sea_query::{Expr, Iden, Order, Query, SqliteQueryBuilder};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Pool, Sqlite};
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(super) enum AuditEventTable {
+    #[iden = "audit_event"]
+    Table,
+    Id,
+    TenantId,
+    ActorId,
+    Action,
+    Target,
+    Success,
+    FailureReason,
+    SourceIp,
+    OccurredAt,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct AuditEventSqlite {
+    id: Uuid,
+    tenant_id: Option<Uuid>,
+    actor_id: Option<Uuid>,
+    action: String,
+    target: String,
+    success: bool,
+    failure_reason: Option<String>,
+    source_ip: Option<String>,
+    occurred_at: DateTime<Utc>,
+}
+
+impl TryFrom<AuditEventSqlite> for AuditEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(value: AuditEventSqlite) -> Result<Self> {
+        Ok(AuditEvent {
+            id: value.id.into(),
+            tenant_id: value.tenant_id.map(|t| t.into()),
+            actor_id: value.actor_id.map(|a| a.into()),
+            action: value.action.parse()?,
+            target: value.target,
+            outcome: if value.success {
+                Outcome::Success
+            } else {
+                Outcome::Failure(value.failure_reason.unwrap_or_default())
+            },
+            source_ip: value.source_ip,
+            occurred_at: value.occurred_at,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Store {
+    pool: Pool<Sqlite>,
+}
+
+impl Store {
+    pub(crate) fn new(pool: Pool<Sqlite>) -> Self {
+        Store { pool }
+    }
+
+    fn all_columns() -> Vec<AuditEventTable> {
+        vec![
+            AuditEventTable::Id,
+            AuditEventTable::TenantId,
+            AuditEventTable::ActorId,
+            AuditEventTable::Action,
+            AuditEventTable::Target,
+            AuditEventTable::Success,
+            AuditEventTable::FailureReason,
+            AuditEventTable::SourceIp,
+            AuditEventTable::OccurredAt,
+        ]
+    }
+}
+
+#[tonic::async_trait]
+impl AuditStore for Store {
+    async fn append(&self, event: AuditEvent) -> Result<()> {
+        let (success, failure_reason) = match event.outcome {
+            Outcome::Success => (true, None),
+            Outcome::Failure(reason) => (false, Some(reason)),
+        };
+        let (sql, values) = Query::insert()
+            .into_table(AuditEventTable::Table)
+            .columns(Self::all_columns())
+            .values([
+                Uuid::from(event.id).into(),
+                event.tenant_id.map(Uuid::from).into(),
+                event.actor_id.map(Uuid::from).into(),
+                event.action.as_str().into(),
+                event.target.into(),
+                success.into(),
+                failure_reason.into(),
+                event.source_ip.into(),
+                event.occurred_at.into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        filter: &AuditFilter,
+        after: Option<AuditEventId>,
+        limit: u64,
+    ) -> Result<Vec<AuditEvent>> {
+        let mut query = Query::select();
+        query
+            .columns(Self::all_columns())
+            .from(AuditEventTable::Table)
+            .order_by(AuditEventTable::Id, Order::Desc)
+            .limit(limit);
+        if let Some(after) = after {
+            query.and_where(Expr::col(AuditEventTable::Id).lt(Uuid::from(after)));
+        }
+        if let Some(tenant_id) = filter.tenant_id {
+            query.and_where(Expr::col(AuditEventTable::TenantId).eq(Uuid::from(tenant_id)));
+        }
+        if filter.without_tenant {
+            query.and_where(Expr::col(AuditEventTable::TenantId).is_null());
+        }
+        if let Some(actor_id) = filter.actor_id {
+            query.and_where(Expr::col(AuditEventTable::ActorId).eq(Uuid::from(actor_id)));
+        }
+        if let Some(action) = filter.action {
+            query.and_where(Expr::col(AuditEventTable::Action).eq(action.as_str()));
+        }
+        if let Some(target) = &filter.target {
+            query.and_where(Expr::col(AuditEventTable::Target).eq(target.as_str()));
+        }
+        if let Some(success) = filter.success {
+            query.and_where(Expr::col(AuditEventTable::Success).eq(success));
+        }
+        if let Some(since) = filter.since {
+            query.and_where(Expr::col(AuditEventTable::OccurredAt).gte(since));
+        }
+        if let Some(until) = filter.until {
+            query.and_where(Expr::col(AuditEventTable::OccurredAt).lt(until));
+        }
+        let (sql, values) = query.build_sqlx(SqliteQueryBuilder);
+        let rows = sqlx::query_as_with::<_, AuditEventSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter().map(|e| e.try_into()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::sqlite::audit::Store;
+    use crate::db::sqlite::connect;
+    use crate::db::AuditStore;
+    use crate::domain::audit::{Action, AuditEvent, AuditFilter, Outcome};
+    use crate::domain::tenant::PLATFORM_ID;
+    use chrono::{Duration, DurationRound, Utc};
+    use ulid::Ulid;
+
+    #[tokio::test]
+    async fn test_audit_store() {
+        let audit_db = Store::new(connect().await);
+        let actor_id = Ulid::new();
+        let now = Utc::now().duration_trunc(Duration::seconds(1)).unwrap();
+        let mut generator = ulid::Generator::new();
+        let mut events = vec![];
+        for (action, outcome, occurred_at) in [
+            (Action::Login, Outcome::Success, now - Duration::hours(1)),
+            (
+                Action::Login,
+                Outcome::Failure("bad password".to_string()),
+                now,
+            ),
+            (Action::UserAdd, Outcome::Success, now),
+        ] {
+            let event = AuditEvent {
+                id: generator.generate().unwrap(),
+                tenant_id: Some(PLATFORM_ID),
+                actor_id: Some(actor_id),
+                action,
+                target: "admin@avocado.com".to_string(),
+                outcome,
+                source_ip: Some("::1".to_string()),
+                occurred_at,
+            };
+            audit_db.append(event.clone()).await.unwrap();
+            events.push(event);
+        }
+
+        // Newest first, one page at a time
+        let filter = AuditFilter::default();
+        let page = audit_db.list(&filter, None, 2).await.unwrap();
+        assert_eq!(page, vec![events[2].clone(), events[1].clone()]);
+        let page = audit_db.list(&filter, Some(page[1].id), 2).await.unwrap();
+        assert_eq!(page, vec![events[0].clone()]);
+
+        let filter = AuditFilter {
+            action: Some(Action::Login),
+            success: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            audit_db.list(&filter, None, 10).await.unwrap(),
+            vec![events[1].clone()]
+        );
+        let filter = AuditFilter {
+            since: Some(now - Duration::minutes(1)),
+            actor_id: Some(actor_id),
+            ..Default::default()
+        };
+        assert_eq!(audit_db.list(&filter, None, 10).await.unwrap().len(), 2);
+        let filter = AuditFilter {
+            tenant_id: Some(Ulid::new()),
+            ..Default::default()
+        };
+        assert!(audit_db.list(&filter, None, 10).await.unwrap().is_empty());
+
+        // The events without a tenant are listed apart
+        let event = AuditEvent {
+            id: generator.generate().unwrap(),
+            tenant_id: None,
+            actor_id: None,
+            ..events[1].clone()
+        };
+        audit_db.append(event.clone()).await.unwrap();
+        let filter = AuditFilter {
+            without_tenant: true,
+            ..Default::default()
+        };
+        assert_eq!(audit_db.list(&filter, None, 10).await.unwrap(), vec![event]);
+    }
+}
@@ -0,0 +1,363 @@
+use crate::db::sqlite::outbox::save_events;
+use crate::db::GroupStore;
+use crate::domain::event::DomainEvent;
+use crate::domain::group::{Group, GroupId};
+use crate::domain::tenant::TenantId;
+use crate::domain::user::UserId;
+use anyhow::Result;
+use sea_query::{Expr, Iden, OnConflict, Order, Query, SqliteQueryBuilder};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Pool, Sqlite};
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(super) enum GroupTable {
+    #[iden = "group"]
+    Table,
+    Id,
+    TenantId,
+    Name,
+    ParentId,
+}
+
+#[derive(Iden)]
+pub(super) enum GroupMemberTable {
+    #[iden = "group_member"]
+    Table,
+    GroupId,
+    UserId,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct GroupSqlite {
+    id: Uuid,
+    tenant_id: Uuid,
+    name: String,
+    parent_id: Option<Uuid>,
+}
+
+impl From<GroupSqlite> for Group {
+    fn from(value: GroupSqlite) -> Self {
+        Group {
+            id: value.id.into(),
+            tenant_id: value.tenant_id.into(),
+            name: value.name,
+            parent_id: value.parent_id.map(|p| p.into()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Store {
+    pool: Pool<Sqlite>,
+}
+
+impl Store {
+    pub(crate) fn new(pool: Pool<Sqlite>) -> Self {
+        Store { pool }
+    }
+
+    fn all_columns() -> Vec<GroupTable> {
+        vec![
+            GroupTable::Id,
+            GroupTable::TenantId,
+            GroupTable::Name,
+            GroupTable::ParentId,
+        ]
+    }
+}
+
+#[tonic::async_trait]
+impl GroupStore for Store {
+    async fn insert(&self, group: Group) -> Result<GroupId> {
+        let (sql, values) = Query::insert()
+            .into_table(GroupTable::Table)
+            .columns(Self::all_columns())
+            .values([
+                Uuid::from(group.id).into(),
+                Uuid::from(group.tenant_id).into(),
+                group.name.into(),
+                group.parent_id.map(Uuid::from).into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        Ok(group.id)
+    }
+
+    async fn get(&self, tenant_id: &TenantId, group_id: &GroupId) -> Result<Option<Group>> {
+        let (sql, values) = Query::select()
+            .columns(Self::all_columns())
+            .from(GroupTable::Table)
+            .and_where(Expr::col(GroupTable::TenantId).eq(Uuid::from(*tenant_id)))
+            .and_where(Expr::col(GroupTable::Id).eq(Uuid::from(*group_id)))
+            .limit(1)
+            .build_sqlx(SqliteQueryBuilder);
+        let mut rows = sqlx::query_as_with::<_, GroupSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.pop().map(|g| g.into()))
+    }
+
+    async fn get_by_name(&self, tenant_id: &TenantId, name: &str) -> Result<Option<Group>> {
+        let (sql, values) = Query::select()
+            .columns(Self::all_columns())
+            .from(GroupTable::Table)
+            .and_where(Expr::col(GroupTable::TenantId).eq(Uuid::from(*tenant_id)))
+            .and_where(Expr::col(GroupTable::Name).eq(name))
+            .limit(1)
+            .build_sqlx(SqliteQueryBuilder);
+        let mut rows = sqlx::query_as_with::<_, GroupSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.pop().map(|g| g.into()))
+    }
+
+    async fn list(&self, tenant_id: &TenantId) -> Result<Vec<Group>> {
+        let (sql, values) = Query::select()
+            .columns(Self::all_columns())
+            .from(GroupTable::Table)
+            .and_where(Expr::col(GroupTable::TenantId).eq(Uuid::from(*tenant_id)))
+            .order_by(GroupTable::Name, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+        let rows = sqlx::query_as_with::<_, GroupSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|g| g.into()).collect())
+    }
+
+    async fn update(&self, group: Group) -> Result<()> {
+        let (sql, values) = Query::update()
+            .table(GroupTable::Table)
+            .values([
+                (GroupTable::Name, group.name.into()),
+                (GroupTable::ParentId, group.parent_id.map(Uuid::from).into()),
+            ])
+            .and_where(Expr::col(GroupTable::TenantId).eq(Uuid::from(group.tenant_id)))
+            .and_where(Expr::col(GroupTable::Id).eq(Uuid::from(group.id)))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        tenant_id: &TenantId,
+        group_id: &GroupId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let group = match self.get(tenant_id, group_id).await? {
+            Some(g) => g,
+            None => return Ok(()),
+        };
+        let group_id = Uuid::from(group.id);
+        let mut tx = self.pool.begin().await?;
+
+        // Subgroups move up to the parent of the deleted group
+        let (sql, values) = Query::update()
+            .table(GroupTable::Table)
+            .values([(GroupTable::ParentId, group.parent_id.map(Uuid::from).into())])
+            .and_where(Expr::col(GroupTable::ParentId).eq(group_id))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        let (sql, values) = Query::delete()
+            .from_table(GroupMemberTable::Table)
+            .and_where(Expr::col(GroupMemberTable::GroupId).eq(group_id))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        let (sql, values) = Query::delete()
+            .from_table(GroupTable::Table)
+            .and_where(Expr::col(GroupTable::Id).eq(group_id))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        save_events(&mut tx, events).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_member(
+        &self,
+        group_id: &GroupId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let (sql, values) = Query::insert()
+            .into_table(GroupMemberTable::Table)
+            .columns([GroupMemberTable::GroupId, GroupMemberTable::UserId])
+            .values([Uuid::from(*group_id).into(), Uuid::from(*user_id).into()])?
+            .on_conflict(
+                OnConflict::columns([GroupMemberTable::GroupId, GroupMemberTable::UserId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        save_events(&mut tx, events).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn remove_member(
+        &self,
+        group_id: &GroupId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let (sql, values) = Query::delete()
+            .from_table(GroupMemberTable::Table)
+            .and_where(Expr::col(GroupMemberTable::GroupId).eq(Uuid::from(*group_id)))
+            .and_where(Expr::col(GroupMemberTable::UserId).eq(Uuid::from(*user_id)))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        save_events(&mut tx, events).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn members(&self, group_ids: &[GroupId]) -> Result<Vec<UserId>> {
+        let (sql, values) = Query::select()
+            .distinct()
+            .column(GroupMemberTable::UserId)
+            .from(GroupMemberTable::Table)
+            .and_where(
+                Expr::col(GroupMemberTable::GroupId)
+                    .is_in(group_ids.iter().map(|id| Uuid::from(*id))),
+            )
+            .build_sqlx(SqliteQueryBuilder);
+        let rows = sqlx::query_scalar_with::<_, Uuid, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|id| id.into()).collect())
+    }
+
+    async fn groups_of(&self, user_id: &UserId) -> Result<Vec<GroupId>> {
+        let (sql, values) = Query::select()
+            .column(GroupMemberTable::GroupId)
+            .from(GroupMemberTable::Table)
+            .and_where(Expr::col(GroupMemberTable::UserId).eq(Uuid::from(*user_id)))
+            .build_sqlx(SqliteQueryBuilder);
+        let rows = sqlx::query_scalar_with::<_, Uuid, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|id| id.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::sqlite::connect;
+    use crate::db::sqlite::group::Store;
+    use crate::db::GroupStore;
+    use crate::domain::group::Group;
+    use crate::domain::tenant::PLATFORM_ID;
+    use ulid::Ulid;
+
+    #[tokio::test]
+    async fn test_group_store() {
+        let group_db = Store::new(connect().await);
+        let sales = Group {
+            id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
+            name: "sales".to_string(),
+            parent_id: None,
+        };
+        let mut emea = Group {
+            id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
+            name: "emea".to_string(),
+            parent_id: Some(sales.id),
+        };
+        let uk = Group {
+            id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
+            name: "uk".to_string(),
+            parent_id: Some(emea.id),
+        };
+        for group in [&sales, &emea, &uk] {
+            group_db.insert(group.clone()).await.unwrap();
+        }
+        assert_eq!(
+            group_db.get(&PLATFORM_ID, &emea.id).await.unwrap().unwrap(),
+            emea
+        );
+        assert_eq!(
+            group_db
+                .get_by_name(&PLATFORM_ID, "uk")
+                .await
+                .unwrap()
+                .unwrap(),
+            uk
+        );
+        assert!(group_db
+            .get(&PLATFORM_ID, &Ulid::new())
+            .await
+            .unwrap()
+            .is_none());
+        // Groups of other tenants are invisible
+        assert!(group_db
+            .get(&Ulid::new(), &emea.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            group_db.list(&PLATFORM_ID).await.unwrap(),
+            vec![emea.clone(), sales.clone(), uk.clone()]
+        );
+
+        emea.name = "europe".to_string();
+        group_db.update(emea.clone()).await.unwrap();
+        assert_eq!(
+            group_db.get(&PLATFORM_ID, &emea.id).await.unwrap().unwrap(),
+            emea
+        );
+
+        let (first_user, second_user) = (Ulid::new(), Ulid::new());
+        group_db
+            .add_member(&emea.id, &first_user, &[])
+            .await
+            .unwrap();
+        group_db
+            .add_member(&emea.id, &first_user, &[])
+            .await
+            .unwrap();
+        group_db.add_member(&uk.id, &first_user, &[]).await.unwrap();
+        group_db
+            .add_member(&uk.id, &second_user, &[])
+            .await
+            .unwrap();
+        assert_eq!(
+            group_db.members(&[emea.id]).await.unwrap(),
+            vec![first_user]
+        );
+        assert_eq!(group_db.members(&[emea.id, uk.id]).await.unwrap().len(), 2);
+        assert_eq!(group_db.groups_of(&first_user).await.unwrap().len(), 2);
+
+        group_db
+            .remove_member(&uk.id, &first_user, &[])
+            .await
+            .unwrap();
+        assert_eq!(
+            group_db.groups_of(&first_user).await.unwrap(),
+            vec![emea.id]
+        );
+
+        // Deleting a group moves its subgroups to its parent
+        group_db.delete(&PLATFORM_ID, &emea.id, &[]).await.unwrap();
+        assert!(group_db
+            .get(&PLATFORM_ID, &emea.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            group_db
+                .get(&PLATFORM_ID, &uk.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .parent_id,
+            Some(sales.id)
+        );
+        assert!(group_db.groups_of(&first_user).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,22 @@
+use crate::db::HealthStore;
+use anyhow::Result;
+use sqlx::{Pool, Sqlite};
+
+#[derive(Debug)]
+pub(crate) struct Store {
+    pool: Pool<Sqlite>,
+}
+
+impl Store {
+    pub(crate) fn new(pool: Pool<Sqlite>) -> Self {
+        Store { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl HealthStore for Store {
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+}
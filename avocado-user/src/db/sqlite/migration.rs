@@ -0,0 +1,567 @@
+use crate::db::sqlite::audit::AuditEventTable;
+use crate::db::sqlite::group::{GroupMemberTable, GroupTable};
+use crate::db::sqlite::outbox::OutboxTable;
+use crate::db::sqlite::role::{RolePermissionTable, RoleTable, UserRoleTable};
+use crate::db::sqlite::tenant::TenantTable;
+use crate::db::sqlite::user::UserTable;
+use crate::domain::role::{permission, ADMIN, NORMAL_USER};
+use crate::domain::tenant::{PLATFORM, PLATFORM_ID};
+use crate::domain::user::UserStatus;
+use anyhow::Result;
+use chrono::Utc;
+use sea_query::{
+    Alias, ColumnDef, Expr, Func, Iden, Index, Query, SqliteQueryBuilder, Table, Values,
+};
+use sea_query_binder::{SqlxBinder, SqlxValues};
+use sqlx::{Pool, Sqlite};
+use ulid::Ulid;
+use uuid::Uuid;
+
+#[derive(Iden)]
+enum MigrationTable {
+    #[iden = "migration"]
+    Table,
+    Version,
+    Description,
+    AppliedAt,
+}
+
+type Statement = (String, SqlxValues);
+
+struct Migration {
+    version: i64,
+    description: &'static str,
+    statements: fn() -> Result<Vec<Statement>>,
+}
+
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "create user table",
+            statements: create_user_table,
+        },
+        Migration {
+            version: 2,
+            description: "replace the fixed user role with dynamic roles",
+            statements: create_role_tables,
+        },
+        Migration {
+            version: 3,
+            description: "create group tables",
+            statements: create_group_tables,
+        },
+        Migration {
+            version: 4,
+            description: "scope users and groups by tenant",
+            statements: scope_by_tenant,
+        },
+        Migration {
+            version: 5,
+            description: "create audit event table",
+            statements: create_audit_event_table,
+        },
+        Migration {
+            version: 6,
+            description: "create outbox table",
+            statements: create_outbox_table,
+        },
+        Migration {
+            version: 7,
+            description: "add user status",
+            statements: add_user_status,
+        },
+        Migration {
+            version: 8,
+            description: "add user profile",
+            statements: add_user_profile,
+        },
+        Migration {
+            version: 9,
+            description: "lowercase user emails",
+            statements: lowercase_user_emails,
+        },
+    ]
+}
+
+/// Applies the migrations the database misses, returning the version it ends up at.
+pub(crate) async fn run(pool: &Pool<Sqlite>) -> Result<i64> {
+    let sql = Table::create()
+        .table(MigrationTable::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(MigrationTable::Version)
+                .big_integer()
+                .not_null()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(MigrationTable::Description).string())
+        .col(ColumnDef::new(MigrationTable::AppliedAt).date_time())
+        .build(SqliteQueryBuilder);
+    sqlx::query(&sql).execute(pool).await?;
+
+    let (sql, values) = Query::select()
+        .expr(Func::max(Expr::col(MigrationTable::Version)))
+        .from(MigrationTable::Table)
+        .build_sqlx(SqliteQueryBuilder);
+    let current_version = sqlx::query_scalar_with::<_, Option<i64>, _>(&sql, values)
+        .fetch_one(pool)
+        .await?
+        .unwrap_or(0);
+
+    let mut version = current_version;
+    for migration in migrations()
+        .into_iter()
+        .filter(|m| m.version > current_version)
+    {
+        let mut tx = pool.begin().await?;
+        for (sql, values) in (migration.statements)()? {
+            sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        }
+        let (sql, values) = Query::insert()
+            .into_table(MigrationTable::Table)
+            .columns([
+                MigrationTable::Version,
+                MigrationTable::Description,
+                MigrationTable::AppliedAt,
+            ])
+            .values([
+                migration.version.into(),
+                migration.description.into(),
+                Utc::now().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        tx.commit().await?;
+        tracing::info!(
+            "applied database migration {}: {}",
+            migration.version,
+            migration.description
+        );
+        version = migration.version;
+    }
+    Ok(version)
+}
+
+fn schema(sql: String) -> Statement {
+    (sql, SqlxValues(Values(vec![])))
+}
+
+fn create_user_table() -> Result<Vec<Statement>> {
+    Ok(vec![schema(
+        Table::create()
+            .table(UserTable::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(UserTable::Id)
+                    .uuid()
+                    .not_null()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(UserTable::FirstName).string())
+            .col(ColumnDef::new(UserTable::LastName).string())
+            .col(ColumnDef::new(UserTable::Email).string().unique_key())
+            .col(ColumnDef::new(UserTable::PasswordHash).string())
+            .col(ColumnDef::new(UserTable::Role).integer())
+            .build(SqliteQueryBuilder),
+    )])
+}
+
+fn create_role_tables() -> Result<Vec<Statement>> {
+    let admin_id = Uuid::from(Ulid::new());
+    let normal_user_id = Uuid::from(Ulid::new());
+
+    Ok(vec![
+        schema(
+            Table::create()
+                .table(RoleTable::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(RoleTable::Id)
+                        .uuid()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(RoleTable::Name).string().unique_key())
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Table::create()
+                .table(RolePermissionTable::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(RolePermissionTable::RoleId)
+                        .uuid()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(RolePermissionTable::Permission)
+                        .string()
+                        .not_null(),
+                )
+                .primary_key(
+                    Index::create()
+                        .col(RolePermissionTable::RoleId)
+                        .col(RolePermissionTable::Permission),
+                )
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Table::create()
+                .table(UserRoleTable::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(UserRoleTable::UserId).uuid().not_null())
+                .col(ColumnDef::new(UserRoleTable::RoleId).uuid().not_null())
+                .primary_key(
+                    Index::create()
+                        .col(UserRoleTable::UserId)
+                        .col(UserRoleTable::RoleId),
+                )
+                .build(SqliteQueryBuilder),
+        ),
+        Query::insert()
+            .into_table(RoleTable::Table)
+            .columns([RoleTable::Id, RoleTable::Name])
+            .values([admin_id.into(), ADMIN.into()])?
+            .values([normal_user_id.into(), NORMAL_USER.into()])?
+            .build_sqlx(SqliteQueryBuilder),
+        Query::insert()
+            .into_table(RolePermissionTable::Table)
+            .columns([RolePermissionTable::RoleId, RolePermissionTable::Permission])
+            .values([admin_id.into(), permission::ALL.into()])?
+            .build_sqlx(SqliteQueryBuilder),
+        // Map the former `Role` integers, where 1 was admin and anything else a normal user
+        Query::insert()
+            .into_table(UserRoleTable::Table)
+            .columns([UserRoleTable::UserId, UserRoleTable::RoleId])
+            .select_from(
+                Query::select()
+                    .column(UserTable::Id)
+                    .expr(Expr::val(admin_id))
+                    .from(UserTable::Table)
+                    .and_where(Expr::col(UserTable::Role).eq(1))
+                    .to_owned(),
+            )?
+            .build_sqlx(SqliteQueryBuilder),
+        Query::insert()
+            .into_table(UserRoleTable::Table)
+            .columns([UserRoleTable::UserId, UserRoleTable::RoleId])
+            .select_from(
+                Query::select()
+                    .column(UserTable::Id)
+                    .expr(Expr::val(normal_user_id))
+                    .from(UserTable::Table)
+                    .and_where(
+                        Expr::col(UserTable::Role)
+                            .ne(1)
+                            .or(Expr::col(UserTable::Role).is_null()),
+                    )
+                    .to_owned(),
+            )?
+            .build_sqlx(SqliteQueryBuilder),
+        schema(
+            Table::alter()
+                .table(UserTable::Table)
+                .drop_column(UserTable::Role)
+                .build(SqliteQueryBuilder),
+        ),
+    ])
+}
+
+fn create_group_tables() -> Result<Vec<Statement>> {
+    Ok(vec![
+        schema(
+            Table::create()
+                .table(GroupTable::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(GroupTable::Id)
+                        .uuid()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(GroupTable::Name).string().unique_key())
+                .col(ColumnDef::new(GroupTable::ParentId).uuid())
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Table::create()
+                .table(GroupMemberTable::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(GroupMemberTable::GroupId).uuid().not_null())
+                .col(ColumnDef::new(GroupMemberTable::UserId).uuid().not_null())
+                .primary_key(
+                    Index::create()
+                        .col(GroupMemberTable::GroupId)
+                        .col(GroupMemberTable::UserId),
+                )
+                .build(SqliteQueryBuilder),
+        ),
+    ])
+}
+
+/// SQLite cannot change the constraints of a table in place, so the user and group tables are
+/// rebuilt with their unique columns scoped by tenant, and every existing row moves to the
+/// platform tenant.
+fn scope_by_tenant() -> Result<Vec<Statement>> {
+    let platform_id = Uuid::from(PLATFORM_ID);
+    let former_user = Alias::new("user_v3");
+    let former_group = Alias::new("group_v3");
+
+    Ok(vec![
+        schema(
+            Table::create()
+                .table(TenantTable::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(TenantTable::Id)
+                        .uuid()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(TenantTable::Name).string().unique_key())
+                .build(SqliteQueryBuilder),
+        ),
+        Query::insert()
+            .into_table(TenantTable::Table)
+            .columns([TenantTable::Id, TenantTable::Name])
+            .values([platform_id.into(), PLATFORM.into()])?
+            .build_sqlx(SqliteQueryBuilder),
+        // Users
+        schema(
+            Table::rename()
+                .table(UserTable::Table, former_user.clone())
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Table::create()
+                .table(UserTable::Table)
+                .col(
+                    ColumnDef::new(UserTable::Id)
+                        .uuid()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(UserTable::TenantId).uuid().not_null())
+                .col(ColumnDef::new(UserTable::FirstName).string())
+                .col(ColumnDef::new(UserTable::LastName).string())
+                .col(ColumnDef::new(UserTable::Email).string())
+                .col(ColumnDef::new(UserTable::PasswordHash).string())
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Index::create()
+                .name("idx_user_tenant_id_email")
+                .table(UserTable::Table)
+                .col(UserTable::TenantId)
+                .col(UserTable::Email)
+                .unique()
+                .build(SqliteQueryBuilder),
+        ),
+        Query::insert()
+            .into_table(UserTable::Table)
+            .columns([
+                UserTable::Id,
+                UserTable::TenantId,
+                UserTable::FirstName,
+                UserTable::LastName,
+                UserTable::Email,
+                UserTable::PasswordHash,
+            ])
+            .select_from(
+                Query::select()
+                    .column(UserTable::Id)
+                    .expr(Expr::val(platform_id))
+                    .columns([
+                        UserTable::FirstName,
+                        UserTable::LastName,
+                        UserTable::Email,
+                        UserTable::PasswordHash,
+                    ])
+                    .from(former_user.clone())
+                    .to_owned(),
+            )?
+            .build_sqlx(SqliteQueryBuilder),
+        schema(Table::drop().table(former_user).build(SqliteQueryBuilder)),
+        // Groups
+        schema(
+            Table::rename()
+                .table(GroupTable::Table, former_group.clone())
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Table::create()
+                .table(GroupTable::Table)
+                .col(
+                    ColumnDef::new(GroupTable::Id)
+                        .uuid()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(GroupTable::TenantId).uuid().not_null())
+                .col(ColumnDef::new(GroupTable::Name).string())
+                .col(ColumnDef::new(GroupTable::ParentId).uuid())
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Index::create()
+                .name("idx_group_tenant_id_name")
+                .table(GroupTable::Table)
+                .col(GroupTable::TenantId)
+                .col(GroupTable::Name)
+                .unique()
+                .build(SqliteQueryBuilder),
+        ),
+        Query::insert()
+            .into_table(GroupTable::Table)
+            .columns([
+                GroupTable::Id,
+                GroupTable::TenantId,
+                GroupTable::Name,
+                GroupTable::ParentId,
+            ])
+            .select_from(
+                Query::select()
+                    .column(GroupTable::Id)
+                    .expr(Expr::val(platform_id))
+                    .columns([GroupTable::Name, GroupTable::ParentId])
+                    .from(former_group.clone())
+                    .to_owned(),
+            )?
+            .build_sqlx(SqliteQueryBuilder),
+        schema(Table::drop().table(former_group).build(SqliteQueryBuilder)),
+    ])
+}
+
+fn create_audit_event_table() -> Result<Vec<Statement>> {
+    Ok(vec![
+        schema(
+            Table::create()
+                .table(AuditEventTable::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(AuditEventTable::Id)
+                        .uuid()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(AuditEventTable::TenantId).uuid())
+                .col(ColumnDef::new(AuditEventTable::ActorId).uuid())
+                .col(ColumnDef::new(AuditEventTable::Action).string().not_null())
+                .col(ColumnDef::new(AuditEventTable::Target).string().not_null())
+                .col(
+                    ColumnDef::new(AuditEventTable::Success)
+                        .boolean()
+                        .not_null(),
+                )
+                .col(ColumnDef::new(AuditEventTable::FailureReason).string())
+                .col(ColumnDef::new(AuditEventTable::SourceIp).string())
+                .col(
+                    ColumnDef::new(AuditEventTable::OccurredAt)
+                        .date_time()
+                        .not_null(),
+                )
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Index::create()
+                .name("idx_audit_event_tenant_id")
+                .table(AuditEventTable::Table)
+                .col(AuditEventTable::TenantId)
+                .build(SqliteQueryBuilder),
+        ),
+    ])
+}
+
+fn create_outbox_table() -> Result<Vec<Statement>> {
+    Ok(vec![
+        schema(
+            Table::create()
+                .table(OutboxTable::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(OutboxTable::Seq)
+                        .integer()
+                        .not_null()
+                        .primary_key()
+                        .auto_increment(),
+                )
+                .col(ColumnDef::new(OutboxTable::Id).uuid().not_null())
+                .col(ColumnDef::new(OutboxTable::EventType).string().not_null())
+                .col(ColumnDef::new(OutboxTable::Payload).text().not_null())
+                .col(
+                    ColumnDef::new(OutboxTable::OccurredAt)
+                        .date_time()
+                        .not_null(),
+                )
+                .col(ColumnDef::new(OutboxTable::PublishedAt).date_time())
+                .build(SqliteQueryBuilder),
+        ),
+        schema(
+            Index::create()
+                .name("idx_outbox_published_at")
+                .table(OutboxTable::Table)
+                .col(OutboxTable::PublishedAt)
+                .build(SqliteQueryBuilder),
+        ),
+    ])
+}
+
+fn add_user_status() -> Result<Vec<Statement>> {
+    Ok(vec![schema(
+        Table::alter()
+            .table(UserTable::Table)
+            .add_column(
+                ColumnDef::new(UserTable::Status)
+                    .string()
+                    .not_null()
+                    .default(UserStatus::Active.as_str()),
+            )
+            .build(SqliteQueryBuilder),
+    )])
+}
+
+fn add_user_profile() -> Result<Vec<Statement>> {
+    // SQLite only supports adding one column per statement
+    let text_column = |column: UserTable, default: &str| {
+        schema(
+            Table::alter()
+                .table(UserTable::Table)
+                .add_column(ColumnDef::new(column).string().not_null().default(default))
+                .build(SqliteQueryBuilder),
+        )
+    };
+    Ok(vec![
+        text_column(UserTable::Phone, ""),
+        text_column(UserTable::JobTitle, ""),
+        text_column(UserTable::Locale, ""),
+        text_column(UserTable::Timezone, ""),
+        text_column(UserTable::Attributes, "{}"),
+    ])
+}
+
+fn lowercase_user_emails() -> Result<Vec<Statement>> {
+    // A user whose lowercase email another user of the tenant has already keeps theirs, for an
+    // admin to sort out
+    let other = Alias::new("other");
+    let taken = Query::select()
+        .expr(Expr::val(1))
+        .from_as(UserTable::Table, other.clone())
+        .and_where(
+            Expr::col((other.clone(), UserTable::TenantId))
+                .equals((UserTable::Table, UserTable::TenantId)),
+        )
+        .and_where(
+            Expr::col((other.clone(), UserTable::Email))
+                .eq(Func::lower(Expr::col((UserTable::Table, UserTable::Email)))),
+        )
+        .and_where(
+            Expr::col((other, UserTable::Id)).ne(Expr::col((UserTable::Table, UserTable::Id))),
+        )
+        .to_owned();
+    Ok(vec![Query::update()
+        .table(UserTable::Table)
+        .value(UserTable::Email, Func::lower(Expr::col(UserTable::Email)))
+        .and_where(Expr::exists(taken).not())
+        .build_sqlx(SqliteQueryBuilder)])
+}
@@ -1 +1,47 @@
+use anyhow::Result;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Pool, Sqlite};
+use std::str::FromStr;
+
+pub(crate) mod audit;
+pub(crate) mod group;
+pub(crate) mod health;
+pub(crate) mod migration;
+pub(crate) mod outbox;
+pub(crate) mod role;
+pub(crate) mod tenant;
 pub(crate) mod user;
+
+pub(crate) async fn connect() -> Pool<Sqlite> {
+    // An in-memory database only lives as long as its connection, so the pool
+    // must never open a second one.
+    let pool = sqlx::pool::PoolOptions::new()
+        .max_connections(1)
+        .max_lifetime(None)
+        .idle_timeout(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    migration::run(&pool)
+        .await
+        .expect("unable to migrate the database");
+    pool
+}
+
+/// Opens and migrates the database at the url, or else an in-memory one.
+pub(crate) async fn connect_to(url: Option<&str>) -> Result<Pool<Sqlite>> {
+    match url {
+        Some(url) => {
+            let pool = open(url).await?;
+            migration::run(&pool).await?;
+            Ok(pool)
+        }
+        None => Ok(connect().await),
+    }
+}
+
+/// Opens the SQLite database at the url, creating the file when missing, without migrating it.
+pub(crate) async fn open(url: &str) -> Result<Pool<Sqlite>> {
+    let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+    Ok(sqlx::pool::PoolOptions::new().connect_with(options).await?)
+}
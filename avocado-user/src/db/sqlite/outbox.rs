@@ -0,0 +1,139 @@
+use crate::db::OutboxStore;
+use crate::domain::event::{DomainEvent, OutboxEvent};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sea_query::{Expr, Iden, Order, Query, SqliteQueryBuilder};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use ulid::Ulid;
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(super) enum OutboxTable {
+    #[iden = "outbox"]
+    Table,
+    Seq,
+    Id,
+    EventType,
+    Payload,
+    OccurredAt,
+    PublishedAt,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct OutboxSqlite {
+    seq: i64,
+    id: Uuid,
+    payload: String,
+    occurred_at: DateTime<Utc>,
+}
+
+impl TryFrom<OutboxSqlite> for OutboxEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(value: OutboxSqlite) -> Result<Self> {
+        Ok(OutboxEvent {
+            seq: value.seq,
+            id: value.id.into(),
+            event: serde_json::from_str(&value.payload)?,
+            occurred_at: value.occurred_at,
+        })
+    }
+}
+
+/// Appends the events to the outbox, on the connection of the transaction changing the data
+/// the events are about.
+pub(super) async fn save_events(conn: &mut SqliteConnection, events: &[DomainEvent]) -> Result<()> {
+    for event in events {
+        let (sql, values) = Query::insert()
+            .into_table(OutboxTable::Table)
+            .columns([
+                OutboxTable::Id,
+                OutboxTable::EventType,
+                OutboxTable::Payload,
+                OutboxTable::OccurredAt,
+            ])
+            .values([
+                Uuid::from(Ulid::new()).into(),
+                event.name().into(),
+                serde_json::to_string(event)?.into(),
+                Utc::now().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *conn).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) struct Store {
+    pool: Pool<Sqlite>,
+}
+
+impl Store {
+    pub(crate) fn new(pool: Pool<Sqlite>) -> Self {
+        Store { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl OutboxStore for Store {
+    async fn pending(&self, limit: u64) -> Result<Vec<OutboxEvent>> {
+        let (sql, values) = Query::select()
+            .columns([
+                OutboxTable::Seq,
+                OutboxTable::Id,
+                OutboxTable::Payload,
+                OutboxTable::OccurredAt,
+            ])
+            .from(OutboxTable::Table)
+            .and_where(Expr::col(OutboxTable::PublishedAt).is_null())
+            .order_by(OutboxTable::Seq, Order::Asc)
+            .limit(limit)
+            .build_sqlx(SqliteQueryBuilder);
+        let rows = sqlx::query_as_with::<_, OutboxSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter().map(|e| e.try_into()).collect()
+    }
+
+    async fn mark_published(&self, seq: i64) -> Result<()> {
+        let (sql, values) = Query::update()
+            .table(OutboxTable::Table)
+            .values([(OutboxTable::PublishedAt, Utc::now().into())])
+            .and_where(Expr::col(OutboxTable::Seq).eq(seq))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn list(&self, after: i64, limit: u64) -> Result<Vec<OutboxEvent>> {
+        let (sql, values) = Query::select()
+            .columns([
+                OutboxTable::Seq,
+                OutboxTable::Id,
+                OutboxTable::Payload,
+                OutboxTable::OccurredAt,
+            ])
+            .from(OutboxTable::Table)
+            .and_where(Expr::col(OutboxTable::Seq).gt(after))
+            .order_by(OutboxTable::Seq, Order::Asc)
+            .limit(limit)
+            .build_sqlx(SqliteQueryBuilder);
+        let rows = sqlx::query_as_with::<_, OutboxSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter().map(|e| e.try_into()).collect()
+    }
+
+    async fn last_seq(&self) -> Result<i64> {
+        let (sql, values) = Query::select()
+            .expr(Expr::col(OutboxTable::Seq).max())
+            .from(OutboxTable::Table)
+            .build_sqlx(SqliteQueryBuilder);
+        let (seq,): (Option<i64>,) = sqlx::query_as_with(&sql, values)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(seq.unwrap_or(0))
+    }
+}
@@ -0,0 +1,296 @@
+use crate::db::sqlite::outbox::save_events;
+use crate::db::RoleStore;
+use crate::domain::event::DomainEvent;
+use crate::domain::role::{Role, RoleId};
+use anyhow::Result;
+use sea_query::{Cond, Expr, Iden, IntoCondition, Order, Query, SqliteQueryBuilder};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(super) enum RoleTable {
+    #[iden = "role"]
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(Iden)]
+pub(super) enum RolePermissionTable {
+    #[iden = "role_permission"]
+    Table,
+    RoleId,
+    Permission,
+}
+
+#[derive(Iden)]
+pub(super) enum UserRoleTable {
+    #[iden = "user_role"]
+    Table,
+    UserId,
+    RoleId,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct RoleSqlite {
+    id: Uuid,
+    name: String,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct RolePermissionSqlite {
+    role_id: Uuid,
+    permission: String,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct UserRoleSqlite {
+    user_id: Uuid,
+    role_id: Uuid,
+}
+
+/// Loads the roles matching the condition together with their permissions, ordered by name.
+pub(super) async fn load_roles(
+    pool: &Pool<Sqlite>,
+    condition: impl IntoCondition,
+) -> Result<Vec<Role>> {
+    let (sql, values) = Query::select()
+        .columns([RoleTable::Id, RoleTable::Name])
+        .from(RoleTable::Table)
+        .cond_where(condition)
+        .order_by(RoleTable::Name, Order::Asc)
+        .build_sqlx(SqliteQueryBuilder);
+    let rows = sqlx::query_as_with::<_, RoleSqlite, _>(&sql, values)
+        .fetch_all(pool)
+        .await?;
+
+    let (sql, values) = Query::select()
+        .columns([RolePermissionTable::RoleId, RolePermissionTable::Permission])
+        .from(RolePermissionTable::Table)
+        .and_where(Expr::col(RolePermissionTable::RoleId).is_in(rows.iter().map(|r| r.id)))
+        .build_sqlx(SqliteQueryBuilder);
+    let mut permissions = HashMap::<Uuid, Vec<String>>::new();
+    for row in sqlx::query_as_with::<_, RolePermissionSqlite, _>(&sql, values)
+        .fetch_all(pool)
+        .await?
+    {
+        permissions
+            .entry(row.role_id)
+            .or_default()
+            .push(row.permission);
+    }
+
+    Ok(rows
+        .into_iter()
+        .map(|r| Role {
+            id: r.id.into(),
+            name: r.name,
+            permissions: permissions
+                .remove(&r.id)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        })
+        .collect())
+}
+
+/// Loads the roles assigned to each of the given users.
+pub(super) async fn load_user_roles(
+    pool: &Pool<Sqlite>,
+    user_ids: Vec<Uuid>,
+) -> Result<HashMap<Uuid, Vec<Role>>> {
+    let (sql, values) = Query::select()
+        .columns([UserRoleTable::UserId, UserRoleTable::RoleId])
+        .from(UserRoleTable::Table)
+        .and_where(Expr::col(UserRoleTable::UserId).is_in(user_ids))
+        .build_sqlx(SqliteQueryBuilder);
+    let assignments = sqlx::query_as_with::<_, UserRoleSqlite, _>(&sql, values)
+        .fetch_all(pool)
+        .await?;
+
+    let roles = load_roles(
+        pool,
+        Expr::col(RoleTable::Id).is_in(assignments.iter().map(|a| a.role_id)),
+    )
+    .await?;
+
+    let mut user_roles = HashMap::<Uuid, Vec<Role>>::new();
+    for role in roles {
+        for assignment in assignments
+            .iter()
+            .filter(|a| a.role_id == Uuid::from(role.id))
+        {
+            user_roles
+                .entry(assignment.user_id)
+                .or_default()
+                .push(role.clone());
+        }
+    }
+    Ok(user_roles)
+}
+
+/// Replaces the roles assigned to a user.
+pub(super) async fn save_user_roles(
+    conn: &mut SqliteConnection,
+    user_id: Uuid,
+    roles: &[Role],
+) -> Result<()> {
+    let (sql, values) = Query::delete()
+        .from_table(UserRoleTable::Table)
+        .and_where(Expr::col(UserRoleTable::UserId).eq(user_id))
+        .build_sqlx(SqliteQueryBuilder);
+    sqlx::query_with(&sql, values).execute(&mut *conn).await?;
+
+    for role in roles {
+        let (sql, values) = Query::insert()
+            .into_table(UserRoleTable::Table)
+            .columns([UserRoleTable::UserId, UserRoleTable::RoleId])
+            .values([user_id.into(), Uuid::from(role.id).into()])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *conn).await?;
+    }
+    Ok(())
+}
+
+async fn save_permissions(conn: &mut SqliteConnection, role: &Role) -> Result<()> {
+    let (sql, values) = Query::delete()
+        .from_table(RolePermissionTable::Table)
+        .and_where(Expr::col(RolePermissionTable::RoleId).eq(Uuid::from(role.id)))
+        .build_sqlx(SqliteQueryBuilder);
+    sqlx::query_with(&sql, values).execute(&mut *conn).await?;
+
+    for permission in &role.permissions {
+        let (sql, values) = Query::insert()
+            .into_table(RolePermissionTable::Table)
+            .columns([RolePermissionTable::RoleId, RolePermissionTable::Permission])
+            .values([Uuid::from(role.id).into(), permission.into()])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *conn).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) struct Store {
+    pool: Pool<Sqlite>,
+}
+
+impl Store {
+    pub(crate) fn new(pool: Pool<Sqlite>) -> Self {
+        Store { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl RoleStore for Store {
+    async fn insert(&self, role: Role) -> Result<RoleId> {
+        let mut tx = self.pool.begin().await?;
+        let (sql, values) = Query::insert()
+            .into_table(RoleTable::Table)
+            .columns([RoleTable::Id, RoleTable::Name])
+            .values([Uuid::from(role.id).into(), role.name.clone().into()])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        save_permissions(&mut tx, &role).await?;
+        tx.commit().await?;
+        Ok(role.id)
+    }
+
+    async fn get(&self, role_id: &RoleId) -> Result<Option<Role>> {
+        let mut roles = load_roles(
+            &self.pool,
+            Expr::col(RoleTable::Id).eq(Uuid::from(*role_id)),
+        )
+        .await?;
+        Ok(roles.pop())
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Role>> {
+        let mut roles = load_roles(&self.pool, Expr::col(RoleTable::Name).eq(name)).await?;
+        Ok(roles.pop())
+    }
+
+    async fn list(&self) -> Result<Vec<Role>> {
+        load_roles(&self.pool, Cond::all()).await
+    }
+
+    async fn update(&self, role: Role, events: &[DomainEvent]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let (sql, values) = Query::update()
+            .table(RoleTable::Table)
+            .values([(RoleTable::Name, role.name.clone().into())])
+            .and_where(Expr::col(RoleTable::Id).eq(Uuid::from(role.id)))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        save_permissions(&mut tx, &role).await?;
+        save_events(&mut tx, events).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, role_id: &RoleId, events: &[DomainEvent]) -> Result<()> {
+        let role_id = Uuid::from(*role_id);
+        let mut tx = self.pool.begin().await?;
+        let (sql, values) = Query::delete()
+            .from_table(UserRoleTable::Table)
+            .and_where(Expr::col(UserRoleTable::RoleId).eq(role_id))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        let (sql, values) = Query::delete()
+            .from_table(RolePermissionTable::Table)
+            .and_where(Expr::col(RolePermissionTable::RoleId).eq(role_id))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        let (sql, values) = Query::delete()
+            .from_table(RoleTable::Table)
+            .and_where(Expr::col(RoleTable::Id).eq(role_id))
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        save_events(&mut tx, events).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::sqlite::connect;
+    use crate::db::sqlite::role::Store;
+    use crate::db::RoleStore;
+    use crate::domain::role::{permission, Role, ADMIN, NORMAL_USER};
+    use ulid::Ulid;
+
+    #[tokio::test]
+    async fn test_role_store() {
+        let role_db = Store::new(connect().await);
+
+        // The migration creates the built-in roles
+        let roles = role_db.list().await.unwrap();
+        assert_eq!(roles.len(), 2);
+        assert_eq!(roles.first().unwrap().name, ADMIN);
+        assert!(roles.first().unwrap().grants(permission::ROLE_WRITE));
+        assert_eq!(roles.get(1).unwrap().name, NORMAL_USER);
+
+        let mut role = Role {
+            id: Ulid::new(),
+            name: "sales".to_string(),
+            permissions: [permission::USER_READ.to_string()].into(),
+        };
+        let role_id = role_db.insert(role.clone()).await.unwrap();
+        assert_eq!(role_db.get(&role_id).await.unwrap().unwrap(), role);
+        assert_eq!(role_db.get_by_name("sales").await.unwrap().unwrap(), role);
+        assert!(role_db.get(&Ulid::new()).await.unwrap().is_none());
+
+        role.name = "sales-manager".to_string();
+        role.permissions.insert(permission::USER_WRITE.to_string());
+        role_db.update(role.clone(), &[]).await.unwrap();
+        assert_eq!(role_db.get(&role_id).await.unwrap().unwrap(), role);
+        assert!(role_db.get_by_name("sales").await.unwrap().is_none());
+
+        role_db.delete(&role_id, &[]).await.unwrap();
+        assert!(role_db.get(&role_id).await.unwrap().is_none());
+        assert_eq!(role_db.list().await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::db::TenantStore;
+use crate::domain::tenant::{Tenant, TenantId};
+use anyhow::Result;
+use sea_query::{Expr, Iden, Order, Query, SqliteQueryBuilder};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Pool, Sqlite};
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(super) enum TenantTable {
+    #[iden = "tenant"]
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct TenantSqlite {
+    id: Uuid,
+    name: String,
+}
+
+impl From<TenantSqlite> for Tenant {
+    fn from(value: TenantSqlite) -> Self {
+        Tenant {
+            id: value.id.into(),
+            name: value.name,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Store {
+    pool: Pool<Sqlite>,
+}
+
+impl Store {
+    pub(crate) fn new(pool: Pool<Sqlite>) -> Self {
+        Store { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl TenantStore for Store {
+    async fn insert(&self, tenant: Tenant) -> Result<TenantId> {
+        let (sql, values) = Query::insert()
+            .into_table(TenantTable::Table)
+            .columns([TenantTable::Id, TenantTable::Name])
+            .values([Uuid::from(tenant.id).into(), tenant.name.into()])?
+            .build_sqlx(SqliteQueryBuilder);
+        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        Ok(tenant.id)
+    }
+
+    async fn get(&self, tenant_id: &TenantId) -> Result<Option<Tenant>> {
+        let (sql, values) = Query::select()
+            .columns([TenantTable::Id, TenantTable::Name])
+            .from(TenantTable::Table)
+            .and_where(Expr::col(TenantTable::Id).eq(Uuid::from(*tenant_id)))
+            .limit(1)
+            .build_sqlx(SqliteQueryBuilder);
+        let mut rows = sqlx::query_as_with::<_, TenantSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.pop().map(|t| t.into()))
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Tenant>> {
+        let (sql, values) = Query::select()
+            .columns([TenantTable::Id, TenantTable::Name])
+            .from(TenantTable::Table)
+            .and_where(Expr::col(TenantTable::Name).eq(name))
+            .limit(1)
+            .build_sqlx(SqliteQueryBuilder);
+        let mut rows = sqlx::query_as_with::<_, TenantSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.pop().map(|t| t.into()))
+    }
+
+    async fn list(&self) -> Result<Vec<Tenant>> {
+        let (sql, values) = Query::select()
+            .columns([TenantTable::Id, TenantTable::Name])
+            .from(TenantTable::Table)
+            .order_by(TenantTable::Name, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+        let rows = sqlx::query_as_with::<_, TenantSqlite, _>(&sql, values)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|t| t.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::sqlite::connect;
+    use crate::db::sqlite::tenant::Store;
+    use crate::db::TenantStore;
+    use crate::domain::tenant::{Tenant, PLATFORM, PLATFORM_ID};
+    use ulid::Ulid;
+
+    #[tokio::test]
+    async fn test_tenant_store() {
+        let tenant_db = Store::new(connect().await);
+
+        // The migration creates the platform tenant
+        let platform = tenant_db.get(&PLATFORM_ID).await.unwrap().unwrap();
+        assert_eq!(platform.name, PLATFORM);
+
+        let acme = Tenant {
+            id: Ulid::new(),
+            name: "acme".to_string(),
+        };
+        tenant_db.insert(acme.clone()).await.unwrap();
+        assert_eq!(tenant_db.get_by_name("acme").await.unwrap().unwrap(), acme);
+        assert!(tenant_db.get_by_name("globex").await.unwrap().is_none());
+        assert_eq!(tenant_db.list().await.unwrap(), vec![acme, platform]);
+    }
+}
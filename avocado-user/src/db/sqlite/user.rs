@@ -1,52 +1,73 @@
+use crate::db::sqlite::group::GroupMemberTable;
+use crate::db::sqlite::outbox::save_events;
+use crate::db::sqlite::role::{load_user_roles, save_user_roles};
 use crate::db::UserStore;
-use crate::domain::user::{Role, User, UserId};
+use crate::domain::event::DomainEvent;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{Profile, User, UserId, UserStatus};
 use anyhow::Result;
-use avocado_base::secret::SecretString;
-use fake::faker::internet::en::FreeEmail;
-use fake::faker::name::en::{FirstName, LastName};
-use fake::{Dummy, Fake, Faker};
-use sea_query::{ColumnDef, Expr, Iden, Order, Query, SqliteQueryBuilder, Table};
+use sea_query::{Expr, Iden, Order, Query, SqliteQueryBuilder};
 use sea_query_binder::SqlxBinder;
 use sqlx::{Pool, Sqlite};
-use ulid::Ulid;
 use uuid::Uuid;
 
 #[derive(Iden)]
-enum UserTable {
+pub(super) enum UserTable {
     #[iden = "user"]
     Table,
     Id,
+    TenantId,
     Email,
     FirstName,
     LastName,
     PasswordHash,
+    Status,
+    Phone,
+    JobTitle,
+    Locale,
+    Timezone,
+    Attributes,
+    // Replaced by the user_role table, only referenced by the migrations
     Role,
 }
 
-#[derive(sqlx::FromRow, Debug, Dummy)]
+#[derive(sqlx::FromRow, Debug)]
 struct UserSqlite {
     id: Uuid,
-    #[dummy(faker = "FreeEmail()")]
+    tenant_id: Uuid,
     email: String,
-    #[dummy(faker = "FirstName()")]
     first_name: String,
-    #[dummy(faker = "LastName()")]
     last_name: String,
     password_hash: String,
-    #[dummy(faker = "0..=1")]
-    role: i32,
+    status: String,
+    phone: String,
+    job_title: String,
+    locale: String,
+    timezone: String,
+    attributes: String,
 }
 
-impl From<UserSqlite> for User {
-    fn from(value: UserSqlite) -> Self {
-        User {
+impl TryFrom<UserSqlite> for User {
+    type Error = anyhow::Error;
+
+    fn try_from(value: UserSqlite) -> Result<Self> {
+        Ok(User {
             id: value.id.into(),
+            tenant_id: value.tenant_id.into(),
             first_name: value.first_name,
             last_name: value.last_name,
             email: value.email,
             password_hash: value.password_hash,
-            role: value.role.try_into().unwrap_or(Role::NormalUser),
-        }
+            roles: vec![],
+            status: value.status.parse()?,
+            profile: Profile {
+                phone: value.phone,
+                job_title: value.job_title,
+                locale: value.locale,
+                timezone: value.timezone,
+                attributes: serde_json::from_str(&value.attributes)?,
+            },
+        })
     }
 }
 
@@ -56,140 +77,155 @@ pub(crate) struct Store {
 }
 
 impl Store {
-    pub(crate) async fn new() -> Self {
-        let pool = sqlx::pool::PoolOptions::new()
-            .max_lifetime(None)
-            .idle_timeout(None)
-            .connect("sqlite::memory:")
-            .await
-            .unwrap();
-
-        // Create the user table
-        let sql = Table::create()
-            .table(UserTable::Table)
-            .if_not_exists()
-            .col(
-                ColumnDef::new(UserTable::Id)
-                    .uuid()
-                    .not_null()
-                    .primary_key(),
-            )
-            .col(ColumnDef::new(UserTable::FirstName).string())
-            .col(ColumnDef::new(UserTable::LastName).string())
-            .col(ColumnDef::new(UserTable::Email).string().unique_key())
-            .col(ColumnDef::new(UserTable::PasswordHash).string())
-            .col(ColumnDef::new(UserTable::Role).integer())
-            .build(SqliteQueryBuilder);
-        sqlx::query(&sql).execute(&pool).await.unwrap();
-        let store = Store { pool };
-
-        // Insert an admin user
-        let admin = User {
-            id: Ulid::new(),
-            first_name: "System".to_string(),
-            last_name: "Admin".to_string(),
-            email: "admin@avocado.com".to_string(),
-            password_hash: User::hash_password(SecretString::new("kIxv4NomLT0WwGKF".to_string()))
-                .expect("unable to generate admin password hash"),
-            role: Role::Admin,
-        };
-        store
-            .insert(admin)
-            .await
-            .expect("unable to create admin user");
-
-        if std::env::var("FAKE_DATA").is_ok() {
-            for _ in 0..50 {
-                let fake_user: UserSqlite = Faker.fake();
-                store
-                    .insert(fake_user.into())
-                    .await
-                    .expect("unable to create fake user");
-            }
-        }
-
-        store
+    pub(crate) fn new(pool: Pool<Sqlite>) -> Self {
+        Store { pool }
     }
 
     fn all_columns() -> Vec<UserTable> {
         vec![
             UserTable::Id,
+            UserTable::TenantId,
             UserTable::FirstName,
             UserTable::LastName,
             UserTable::Email,
             UserTable::PasswordHash,
-            UserTable::Role,
+            UserTable::Status,
+            UserTable::Phone,
+            UserTable::JobTitle,
+            UserTable::Locale,
+            UserTable::Timezone,
+            UserTable::Attributes,
         ]
     }
+
+    async fn with_roles(&self, rows: Vec<UserSqlite>) -> Result<Vec<User>> {
+        let mut roles = load_user_roles(&self.pool, rows.iter().map(|u| u.id).collect()).await?;
+        rows.into_iter()
+            .map(|u| {
+                let user_roles = roles.remove(&u.id).unwrap_or_default();
+                Ok(User {
+                    roles: user_roles,
+                    ..u.try_into()?
+                })
+            })
+            .collect()
+    }
 }
 
 #[tonic::async_trait]
 impl UserStore for Store {
-    async fn insert(&self, user: User) -> Result<UserId> {
+    async fn insert(&self, user: User, events: &[DomainEvent]) -> Result<UserId> {
+        let mut tx = self.pool.begin().await?;
         let (sql, values) = Query::insert()
             .into_table(UserTable::Table)
             .columns(Self::all_columns())
             .values([
                 Uuid::from(user.id).into(),
+                Uuid::from(user.tenant_id).into(),
                 user.first_name.into(),
                 user.last_name.into(),
                 user.email.into(),
                 user.password_hash.into(),
-                (user.role as i32).into(),
+                user.status.as_str().into(),
+                user.profile.phone.into(),
+                user.profile.job_title.into(),
+                user.profile.locale.into(),
+                user.profile.timezone.into(),
+                serde_json::to_string(&user.profile.attributes)?.into(),
             ])?
             .build_sqlx(SqliteQueryBuilder);
-        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+        save_user_roles(&mut tx, Uuid::from(user.id), &user.roles).await?;
+        save_events(&mut tx, events).await?;
+        tx.commit().await?;
         Ok(user.id)
     }
 
-    async fn get(&self, user_id: &UserId) -> Result<Option<User>> {
+    async fn get(&self, tenant_id: &TenantId, user_id: &UserId) -> Result<Option<User>> {
         let (sql, values) = Query::select()
             .columns(Self::all_columns())
             .from(UserTable::Table)
+            .and_where(Expr::col(UserTable::TenantId).eq(Uuid::from(*tenant_id)))
             .and_where(Expr::col(UserTable::Id).eq(Uuid::from(*user_id)))
             .limit(1)
             .build_sqlx(SqliteQueryBuilder);
-        let mut rows = sqlx::query_as_with::<_, UserSqlite, _>(&sql, values)
+        let rows = sqlx::query_as_with::<_, UserSqlite, _>(&sql, values)
             .fetch_all(&self.pool)
             .await?;
-        Ok(rows.pop().map(|u| u.into()))
+        Ok(self.with_roles(rows).await?.pop())
     }
 
-    async fn get_by_email(&self, email: &str) -> Result<Option<User>> {
+    async fn get_by_email(&self, tenant_id: &TenantId, email: &str) -> Result<Option<User>> {
         let (sql, values) = Query::select()
             .columns(Self::all_columns())
             .from(UserTable::Table)
+            .and_where(Expr::col(UserTable::TenantId).eq(Uuid::from(*tenant_id)))
             .and_where(Expr::col(UserTable::Email).eq(email))
             .limit(1)
             .build_sqlx(SqliteQueryBuilder);
-        let mut rows = sqlx::query_as_with::<_, UserSqlite, _>(&sql, values)
+        let rows = sqlx::query_as_with::<_, UserSqlite, _>(&sql, values)
             .fetch_all(&self.pool)
             .await?;
-        Ok(rows.pop().map(|u| u.into()))
+        Ok(self.with_roles(rows).await?.pop())
     }
 
-    async fn list(&self) -> Result<Vec<User>> {
+    async fn list(&self, tenant_id: &TenantId, include_inactive: bool) -> Result<Vec<User>> {
         let (sql, values) = Query::select()
             .columns(Self::all_columns())
             .from(UserTable::Table)
+            .and_where(Expr::col(UserTable::TenantId).eq(Uuid::from(*tenant_id)))
+            .and_where_option(
+                (!include_inactive)
+                    .then(|| Expr::col(UserTable::Status).eq(UserStatus::Active.as_str())),
+            )
             .order_by(UserTable::Email, Order::Asc)
             .build_sqlx(SqliteQueryBuilder);
         let rows = sqlx::query_as_with::<_, UserSqlite, _>(&sql, values.clone())
             .fetch_all(&self.pool)
             .await?;
-        Ok(rows.into_iter().map(|d| d.into()).collect())
+        self.with_roles(rows).await
     }
 
-    async fn delete(&self, user_id: &UserId) -> Result<()> {
-        let (sql, values) = Query::delete()
-            .from_table(UserTable::Table)
+    async fn delete(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        // The row is kept so the history of the user stays intact
+        let (sql, values) = Query::update()
+            .table(UserTable::Table)
+            .values([(UserTable::Status, UserStatus::Deleted.as_str().into())])
+            .and_where(Expr::col(UserTable::TenantId).eq(Uuid::from(*tenant_id)))
             .and_where(Expr::col(UserTable::Id).eq(Uuid::from(*user_id)))
             .build_sqlx(SqliteQueryBuilder);
-        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        // Leave the memberships alone when the user belongs to another tenant
+        if sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0
+        {
+            let (sql, values) = Query::delete()
+                .from_table(GroupMemberTable::Table)
+                .and_where(Expr::col(GroupMemberTable::UserId).eq(Uuid::from(*user_id)))
+                .build_sqlx(SqliteQueryBuilder);
+            sqlx::query_with(&sql, values).execute(&mut *tx).await?;
+            save_events(&mut tx, events).await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 
-    async fn update(&self, user_id: &UserId, user: User) -> Result<()> {
+    async fn update(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        user: User,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
         let (sql, values) = Query::update()
             .table(UserTable::Table)
             .values([
@@ -197,70 +233,164 @@ impl UserStore for Store {
                 (UserTable::LastName, user.last_name.into()),
                 (UserTable::Email, user.email.into()),
                 (UserTable::PasswordHash, user.password_hash.into()),
+                (UserTable::Status, user.status.as_str().into()),
+                (UserTable::Phone, user.profile.phone.into()),
+                (UserTable::JobTitle, user.profile.job_title.into()),
+                (UserTable::Locale, user.profile.locale.into()),
+                (UserTable::Timezone, user.profile.timezone.into()),
+                (
+                    UserTable::Attributes,
+                    serde_json::to_string(&user.profile.attributes)?.into(),
+                ),
             ])
+            .and_where(Expr::col(UserTable::TenantId).eq(Uuid::from(*tenant_id)))
             .and_where(Expr::col(UserTable::Id).eq(Uuid::from(*user_id)))
             .build_sqlx(SqliteQueryBuilder);
-        sqlx::query_with(&sql, values).execute(&self.pool).await?;
+        if sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0
+        {
+            save_user_roles(&mut tx, Uuid::from(*user_id), &user.roles).await?;
+            save_events(&mut tx, events).await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::db::sqlite::connect;
+    use crate::db::sqlite::role::Store as RoleStore;
     use crate::db::sqlite::user::Store;
-    use crate::db::UserStore;
-    use crate::domain::user::{Role, User};
+    use crate::db::{RoleStore as _, UserStore};
+    use crate::domain::role::{ADMIN, NORMAL_USER};
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::{Profile, User, UserStatus};
     use ulid::Ulid;
 
     #[tokio::test]
     async fn test_user_store() {
-        let user_db = Store::new().await;
+        let pool = connect().await;
+        let user_db = Store::new(pool.clone());
+        let role_db = RoleStore::new(pool);
+        let admin = role_db.get_by_name(ADMIN).await.unwrap().unwrap();
+        let normal_user = role_db.get_by_name(NORMAL_USER).await.unwrap().unwrap();
         let first_user = User {
             id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
             first_name: "Wei".to_string(),
             last_name: "Zheng".to_string(),
             email: "william@test.com".to_string(),
             password_hash: "hash".to_string(),
-            role: Role::Admin,
+            roles: vec![admin.clone()],
+            status: UserStatus::Active,
+            profile: Profile::default(),
         };
-        let first_user_id = user_db.insert(first_user.clone()).await.unwrap();
+        let first_user_id = user_db.insert(first_user.clone(), &[]).await.unwrap();
 
-        let existing_user = user_db.get(&first_user_id).await.unwrap().unwrap();
-        let non_existing_user = user_db.get(&Ulid::new()).await.unwrap();
+        let existing_user = user_db
+            .get(&PLATFORM_ID, &first_user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let non_existing_user = user_db.get(&PLATFORM_ID, &Ulid::new()).await.unwrap();
         assert_eq!(existing_user, first_user);
         assert!(non_existing_user.is_none());
 
         let existing_user = user_db
-            .get_by_email("william@test.com")
+            .get_by_email(&PLATFORM_ID, "william@test.com")
             .await
             .unwrap()
             .unwrap();
-        let non_existing_user = user_db.get_by_email("tester@test.com").await.unwrap();
+        let non_existing_user = user_db
+            .get_by_email(&PLATFORM_ID, "tester@test.com")
+            .await
+            .unwrap();
         assert_eq!(existing_user, first_user);
         assert!(non_existing_user.is_none());
 
         let mut second_user = User {
             id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
             first_name: "Robert".to_string(),
             last_name: "Li".to_string(),
             email: "robert@test.com".to_string(),
             password_hash: "hash".to_string(),
-            role: Role::Admin,
+            roles: vec![admin.clone(), normal_user.clone()],
+            status: UserStatus::Active,
+            profile: Profile::default(),
         };
-        let second_user_id = user_db.insert(second_user.clone()).await.unwrap();
-        let existing_users = user_db.list().await.unwrap();
-        assert_eq!(existing_users.len(), 3);
-        assert_eq!(*existing_users.get(1).unwrap(), second_user);
-        assert_eq!(*existing_users.get(2).unwrap(), first_user);
-
-        user_db.delete(&first_user.id).await.unwrap();
-        let existing_users = user_db.list().await.unwrap();
+        let second_user_id = user_db.insert(second_user.clone(), &[]).await.unwrap();
+        let existing_users = user_db.list(&PLATFORM_ID, false).await.unwrap();
         assert_eq!(existing_users.len(), 2);
-        assert_eq!(*existing_users.get(1).unwrap(), second_user);
+        assert_eq!(*existing_users.first().unwrap(), second_user);
+        assert_eq!(*existing_users.get(1).unwrap(), first_user);
+
+        // The same email can be used in another tenant, which is invisible to this one
+        let tenant_id = Ulid::new();
+        let other_tenant_user = User {
+            id: Ulid::new(),
+            tenant_id,
+            roles: vec![normal_user.clone()],
+            ..first_user.clone()
+        };
+        user_db
+            .insert(other_tenant_user.clone(), &[])
+            .await
+            .unwrap();
+        assert!(user_db
+            .insert(other_tenant_user.clone(), &[])
+            .await
+            .is_err());
+        assert_eq!(
+            user_db.list(&tenant_id, false).await.unwrap(),
+            vec![other_tenant_user.clone()]
+        );
+        assert!(user_db
+            .get(&tenant_id, &first_user_id)
+            .await
+            .unwrap()
+            .is_none());
+        user_db
+            .delete(&tenant_id, &first_user_id, &[])
+            .await
+            .unwrap();
+        assert!(user_db
+            .get(&PLATFORM_ID, &first_user_id)
+            .await
+            .unwrap()
+            .is_some());
+
+        user_db
+            .delete(&PLATFORM_ID, &first_user.id, &[])
+            .await
+            .unwrap();
+        let existing_users = user_db.list(&PLATFORM_ID, false).await.unwrap();
+        assert_eq!(existing_users, vec![second_user.clone()]);
+        // Deleted users are kept, only marked as deleted
+        assert_eq!(user_db.list(&PLATFORM_ID, true).await.unwrap().len(), 2);
+        let deleted_user = user_db
+            .get(&PLATFORM_ID, &first_user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deleted_user.status, UserStatus::Deleted);
 
         second_user.email = "robert.li@gmail.com".to_string();
-        user_db.update(&second_user_id, second_user).await.unwrap();
-        let existing_user = user_db.get(&second_user_id).await.unwrap().unwrap();
-        assert_eq!(existing_user.email, "robert.li@gmail.com")
+        second_user.roles = vec![normal_user.clone()];
+        user_db
+            .update(&PLATFORM_ID, &second_user_id, second_user, &[])
+            .await
+            .unwrap();
+        let existing_user = user_db
+            .get(&PLATFORM_ID, &second_user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(existing_user.email, "robert.li@gmail.com");
+        assert_eq!(existing_user.roles, vec![normal_user])
     }
 }
@@ -0,0 +1,156 @@
+use crate::domain::tenant::TenantId;
+use crate::domain::user::UserId;
+use chrono::{DateTime, Utc};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+use ulid::Ulid;
+
+pub(crate) type AuditEventId = Ulid;
+
+#[derive(Error, Debug)]
+pub enum AuditError {
+    #[error("unknown audit action {0}")]
+    UnknownAction(String),
+}
+
+/// The security relevant operations recorded in the audit log.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Action {
+    Login,
+    Refresh,
+    KeyRotate,
+    UserAdd,
+    UserUpdate,
+    UserDelete,
+    PasswordChange,
+    PasswordReset,
+    Impersonate,
+    UserDisable,
+    UserEnable,
+    ProfileUpdate,
+    UserImport,
+    Setup,
+    RoleAssign,
+    RoleRevoke,
+    RoleAdd,
+    RoleUpdate,
+    RoleDelete,
+    /// A call refused by the auth middleware, for its token or its policy
+    AccessDenied,
+}
+
+impl Action {
+    const ALL: [Action; 20] = [
+        Action::Login,
+        Action::Refresh,
+        Action::KeyRotate,
+        Action::UserAdd,
+        Action::UserUpdate,
+        Action::UserDelete,
+        Action::PasswordChange,
+        Action::PasswordReset,
+        Action::Impersonate,
+        Action::UserDisable,
+        Action::UserEnable,
+        Action::ProfileUpdate,
+        Action::UserImport,
+        Action::Setup,
+        Action::RoleAssign,
+        Action::RoleRevoke,
+        Action::RoleAdd,
+        Action::RoleUpdate,
+        Action::RoleDelete,
+        Action::AccessDenied,
+    ];
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Action::Login => "user.login",
+            Action::Refresh => "jwt.refresh",
+            Action::KeyRotate => "jwt.rotate_key",
+            Action::UserAdd => "user.add",
+            Action::UserUpdate => "user.update",
+            Action::UserDelete => "user.delete",
+            Action::PasswordChange => "user.change_password",
+            Action::PasswordReset => "user.reset_password",
+            Action::Impersonate => "user.impersonate",
+            Action::UserDisable => "user.disable",
+            Action::UserEnable => "user.enable",
+            Action::ProfileUpdate => "user.update_profile",
+            Action::UserImport => "user.import",
+            Action::Setup => "user.setup",
+            Action::RoleAssign => "user.assign_role",
+            Action::RoleRevoke => "user.revoke_role",
+            Action::RoleAdd => "role.add",
+            Action::RoleUpdate => "role.update",
+            Action::RoleDelete => "role.delete",
+            Action::AccessDenied => "auth.deny",
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Action {
+    type Err = AuditError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|a| a.as_str() == s)
+            .ok_or_else(|| AuditError::UnknownAction(s.to_string()))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Outcome {
+    Success,
+    Failure(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct AuditEvent {
+    pub(crate) id: AuditEventId,
+    /// Unknown when a login names a tenant which does not exist
+    pub(crate) tenant_id: Option<TenantId>,
+    /// Unknown for failed logins
+    pub(crate) actor_id: Option<UserId>,
+    pub(crate) action: Action,
+    /// What the action was applied to, such as a user id, an email or a role name
+    pub(crate) target: String,
+    pub(crate) outcome: Outcome,
+    pub(crate) source_ip: Option<String>,
+    pub(crate) occurred_at: DateTime<Utc>,
+}
+
+/// Narrows down the audit events to list, where unset criteria match everything.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct AuditFilter {
+    pub(crate) tenant_id: Option<TenantId>,
+    /// Only the events recorded without a tenant, with `tenant_id` unset
+    pub(crate) without_tenant: bool,
+    pub(crate) actor_id: Option<UserId>,
+    pub(crate) action: Option<Action>,
+    pub(crate) target: Option<String>,
+    pub(crate) success: Option<bool>,
+    pub(crate) since: Option<DateTime<Utc>>,
+    pub(crate) until: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::audit::Action;
+
+    #[test]
+    fn test_action_round_trip() {
+        for action in Action::ALL {
+            assert_eq!(action.as_str().parse::<Action>().unwrap(), action);
+        }
+        assert!("user.unknown".parse::<Action>().is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use crate::domain::group::GroupId;
+use crate::domain::role::RoleId;
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+pub(crate) type EventId = Ulid;
+
+/// Changes to users and roles which other services, and the replicas of this one, may need to
+/// follow.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum DomainEvent {
+    UserCreated {
+        user_id: UserId,
+        tenant_id: TenantId,
+        email: String,
+        first_name: String,
+        last_name: String,
+    },
+    UserUpdated {
+        user_id: UserId,
+        tenant_id: TenantId,
+        email: String,
+        first_name: String,
+        last_name: String,
+    },
+    UserDeleted {
+        user_id: UserId,
+        tenant_id: TenantId,
+    },
+    PasswordChanged {
+        user_id: UserId,
+        tenant_id: TenantId,
+    },
+    UserDisabled {
+        user_id: UserId,
+        tenant_id: TenantId,
+    },
+    UserEnabled {
+        user_id: UserId,
+        tenant_id: TenantId,
+    },
+    RoleAssigned {
+        user_id: UserId,
+        tenant_id: TenantId,
+        role: String,
+    },
+    RoleRevoked {
+        user_id: UserId,
+        tenant_id: TenantId,
+        role: String,
+    },
+    MemberAdded {
+        user_id: UserId,
+        tenant_id: TenantId,
+        group_id: GroupId,
+    },
+    MemberRemoved {
+        user_id: UserId,
+        tenant_id: TenantId,
+        group_id: GroupId,
+    },
+    /// Roles are shared by the tenants, so their events belong to none
+    RoleUpdated {
+        role_id: RoleId,
+    },
+    RoleDeleted {
+        role_id: RoleId,
+    },
+}
+
+impl DomainEvent {
+    pub(crate) fn user_created(user: &User) -> Self {
+        DomainEvent::UserCreated {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+        }
+    }
+
+    pub(crate) fn user_updated(user: &User) -> Self {
+        DomainEvent::UserUpdated {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+        }
+    }
+
+    pub(crate) fn user_deleted(user: &User) -> Self {
+        DomainEvent::UserDeleted {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+        }
+    }
+
+    pub(crate) fn password_changed(user: &User) -> Self {
+        DomainEvent::PasswordChanged {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+        }
+    }
+
+    pub(crate) fn user_disabled(user: &User) -> Self {
+        DomainEvent::UserDisabled {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+        }
+    }
+
+    pub(crate) fn user_enabled(user: &User) -> Self {
+        DomainEvent::UserEnabled {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+        }
+    }
+
+    pub(crate) fn role_assigned(user: &User, role: &str) -> Self {
+        DomainEvent::RoleAssigned {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+            role: role.to_string(),
+        }
+    }
+
+    pub(crate) fn role_revoked(user: &User, role: &str) -> Self {
+        DomainEvent::RoleRevoked {
+            user_id: user.id,
+            tenant_id: user.tenant_id,
+            role: role.to_string(),
+        }
+    }
+
+    pub(crate) fn member_added(user_id: &UserId, tenant_id: &TenantId, group_id: &GroupId) -> Self {
+        DomainEvent::MemberAdded {
+            user_id: *user_id,
+            tenant_id: *tenant_id,
+            group_id: *group_id,
+        }
+    }
+
+    pub(crate) fn member_removed(
+        user_id: &UserId,
+        tenant_id: &TenantId,
+        group_id: &GroupId,
+    ) -> Self {
+        DomainEvent::MemberRemoved {
+            user_id: *user_id,
+            tenant_id: *tenant_id,
+            group_id: *group_id,
+        }
+    }
+
+    pub(crate) fn role_updated(role_id: &RoleId) -> Self {
+        DomainEvent::RoleUpdated { role_id: *role_id }
+    }
+
+    pub(crate) fn role_deleted(role_id: &RoleId) -> Self {
+        DomainEvent::RoleDeleted { role_id: *role_id }
+    }
+
+    /// The user the event is about, none for the events about roles.
+    pub(crate) fn user_id(&self) -> Option<&UserId> {
+        match self {
+            DomainEvent::UserCreated { user_id, .. }
+            | DomainEvent::UserUpdated { user_id, .. }
+            | DomainEvent::UserDeleted { user_id, .. }
+            | DomainEvent::PasswordChanged { user_id, .. }
+            | DomainEvent::UserDisabled { user_id, .. }
+            | DomainEvent::UserEnabled { user_id, .. }
+            | DomainEvent::RoleAssigned { user_id, .. }
+            | DomainEvent::RoleRevoked { user_id, .. }
+            | DomainEvent::MemberAdded { user_id, .. }
+            | DomainEvent::MemberRemoved { user_id, .. } => Some(user_id),
+            DomainEvent::RoleUpdated { .. } | DomainEvent::RoleDeleted { .. } => None,
+        }
+    }
+
+    pub(crate) fn tenant_id(&self) -> Option<&TenantId> {
+        match self {
+            DomainEvent::UserCreated { tenant_id, .. }
+            | DomainEvent::UserUpdated { tenant_id, .. }
+            | DomainEvent::UserDeleted { tenant_id, .. }
+            | DomainEvent::PasswordChanged { tenant_id, .. }
+            | DomainEvent::UserDisabled { tenant_id, .. }
+            | DomainEvent::UserEnabled { tenant_id, .. }
+            | DomainEvent::RoleAssigned { tenant_id, .. }
+            | DomainEvent::RoleRevoked { tenant_id, .. }
+            | DomainEvent::MemberAdded { tenant_id, .. }
+            | DomainEvent::MemberRemoved { tenant_id, .. } => Some(tenant_id),
+            DomainEvent::RoleUpdated { .. } | DomainEvent::RoleDeleted { .. } => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::UserCreated { .. } => "UserCreated",
+            DomainEvent::UserUpdated { .. } => "UserUpdated",
+            DomainEvent::UserDeleted { .. } => "UserDeleted",
+            DomainEvent::PasswordChanged { .. } => "PasswordChanged",
+            DomainEvent::UserDisabled { .. } => "UserDisabled",
+            DomainEvent::UserEnabled { .. } => "UserEnabled",
+            DomainEvent::RoleAssigned { .. } => "RoleAssigned",
+            DomainEvent::RoleRevoked { .. } => "RoleRevoked",
+            DomainEvent::MemberAdded { .. } => "MemberAdded",
+            DomainEvent::MemberRemoved { .. } => "MemberRemoved",
+            DomainEvent::RoleUpdated { .. } => "RoleUpdated",
+            DomainEvent::RoleDeleted { .. } => "RoleDeleted",
+        }
+    }
+}
+
+/// A domain event as recorded in the outbox, where `seq` gives the order the events happened in.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct OutboxEvent {
+    pub(crate) seq: i64,
+    /// Stays the same when the event is delivered more than once, so consumers can deduplicate
+    pub(crate) id: EventId,
+    pub(crate) event: DomainEvent,
+    pub(crate) occurred_at: DateTime<Utc>,
+}
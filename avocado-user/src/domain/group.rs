@@ -0,0 +1,124 @@
+use crate::domain::tenant::TenantId;
+use std::collections::HashMap;
+use thiserror::Error;
+use ulid::Ulid;
+
+pub(crate) type GroupId = Ulid;
+
+#[derive(Error, Debug)]
+pub enum GroupError {
+    #[error("group with {field} {value} not exist")]
+    NotExist { field: String, value: String },
+    #[error("group with name {0} already exists")]
+    AlreadyExist(String),
+    #[error("group {0} cannot be nested under itself or one of its subgroups")]
+    Cycle(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Group {
+    pub(crate) id: GroupId,
+    pub(crate) tenant_id: TenantId,
+    pub(crate) name: String,
+    pub(crate) parent_id: Option<GroupId>,
+}
+
+/// The nesting of groups, where a member of a group is also a member of all its ancestors.
+#[derive(Debug)]
+pub(crate) struct Hierarchy {
+    groups: HashMap<GroupId, Group>,
+}
+
+impl Hierarchy {
+    pub(crate) fn new(groups: Vec<Group>) -> Self {
+        Self {
+            groups: groups.into_iter().map(|g| (g.id, g)).collect(),
+        }
+    }
+
+    /// The parent, grandparent and so on of the group, nearest first.
+    pub(crate) fn ancestors(&self, group_id: &GroupId) -> Vec<&Group> {
+        let mut ancestors: Vec<&Group> = vec![];
+        let mut current = self.groups.get(group_id).and_then(|g| g.parent_id);
+        while let Some(parent) = current.and_then(|id| self.groups.get(&id)) {
+            // Stop at a corrupted hierarchy rather than looping forever
+            if parent.id == *group_id || ancestors.iter().any(|a| a.id == parent.id) {
+                break;
+            }
+            ancestors.push(parent);
+            current = parent.parent_id;
+        }
+        ancestors
+    }
+
+    /// All the subgroups nested under the group, at any depth.
+    pub(crate) fn descendants(&self, group_id: &GroupId) -> Vec<&Group> {
+        self.groups
+            .values()
+            .filter(|g| self.ancestors(&g.id).iter().any(|a| a.id == *group_id))
+            .collect()
+    }
+
+    /// Whether nesting the group under the parent would make the group its own ancestor.
+    pub(crate) fn creates_cycle(&self, group_id: &GroupId, parent_id: &GroupId) -> bool {
+        group_id == parent_id || self.ancestors(parent_id).iter().any(|a| a.id == *group_id)
+    }
+
+    /// The groups together with all their ancestors, without duplicates.
+    pub(crate) fn expand(&self, group_ids: &[GroupId]) -> Vec<&Group> {
+        let mut expanded: Vec<&Group> = vec![];
+        for group in group_ids
+            .iter()
+            .filter_map(|id| self.groups.get(id))
+            .flat_map(|g| std::iter::once(g).chain(self.ancestors(&g.id)))
+        {
+            if !expanded.iter().any(|e| e.id == group.id) {
+                expanded.push(group);
+            }
+        }
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::group::{Group, Hierarchy};
+    use crate::domain::tenant::PLATFORM_ID;
+    use ulid::Ulid;
+
+    #[test]
+    fn test_hierarchy() {
+        let group = |name: &str, parent: Option<&Group>| Group {
+            id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
+            name: name.to_string(),
+            parent_id: parent.map(|p| p.id),
+        };
+        let sales = group("sales", None);
+        let emea = group("emea", Some(&sales));
+        let uk = group("uk", Some(&emea));
+        let support = group("support", None);
+        let hierarchy = Hierarchy::new(vec![
+            sales.clone(),
+            emea.clone(),
+            uk.clone(),
+            support.clone(),
+        ]);
+
+        assert_eq!(hierarchy.ancestors(&uk.id), vec![&emea, &sales]);
+        assert!(hierarchy.ancestors(&sales.id).is_empty());
+
+        let mut descendants = hierarchy.descendants(&sales.id);
+        descendants.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(descendants, vec![&emea, &uk]);
+
+        assert!(hierarchy.creates_cycle(&sales.id, &uk.id));
+        assert!(hierarchy.creates_cycle(&sales.id, &sales.id));
+        assert!(!hierarchy.creates_cycle(&support.id, &uk.id));
+
+        assert_eq!(
+            hierarchy.expand(&[uk.id, emea.id, support.id]),
+            vec![&uk, &emea, &sales, &support]
+        );
+    }
+}
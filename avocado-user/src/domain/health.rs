@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HealthError {
+    #[error("unknown service {0}")]
+    UnknownService(String),
+}
+
+/// Whether the services are serving by their full names, such as `user.User`, where the empty
+/// name stands for the server as a whole.
+#[derive(Debug, Default)]
+pub(crate) struct Health(RwLock<BTreeMap<String, bool>>);
+
+impl Health {
+    pub(crate) fn set_serving(&self, service: &str, serving: bool) {
+        self.0.write().unwrap().insert(service.to_string(), serving);
+    }
+
+    /// Reports every service as not serving, when shutting down.
+    pub(crate) fn stop_serving(&self) {
+        for serving in self.0.write().unwrap().values_mut() {
+            *serving = false;
+        }
+    }
+
+    pub(crate) fn serving(&self, service: &str) -> Result<bool, HealthError> {
+        self.0
+            .read()
+            .unwrap()
+            .get(service)
+            .copied()
+            .ok_or_else(|| HealthError::UnknownService(service.to_string()))
+    }
+}
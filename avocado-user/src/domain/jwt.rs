@@ -1,14 +1,138 @@
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{User, UserId};
 use anyhow::Result;
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::{Debug, Formatter};
+use thiserror::Error;
 use ulid::Ulid;
 
+#[derive(Error, Debug)]
+pub enum JwtError {
+    #[error("invalid signing key: {0}")]
+    InvalidKey(String),
+    #[error("no signing key with id {0}")]
+    UnknownKey(String),
+}
+
+/// An RSA key pair signing tokens, named in the `kid` header of the tokens it signs.
+#[derive(Clone)]
+pub(crate) struct SigningKey {
+    /// The start of the SHA-256 digest of the public key, so the same key always has the same id
+    pub(crate) kid: String,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+}
+
+impl Debug for SigningKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("kid", &self.kid)
+            .finish()
+    }
+}
+
+impl SigningKey {
+    /// Parses the PEM encoded keys, which need to be the two halves of the same pair.
+    pub(crate) fn from_pem(private_key: &[u8], public_key: &[u8]) -> Result<Self, JwtError> {
+        let encoding_key = EncodingKey::from_rsa_pem(private_key)
+            .map_err(|e| JwtError::InvalidKey(format!("private key: {}", e)))?;
+        let decoding_key = DecodingKey::from_rsa_pem(public_key)
+            .map_err(|e| JwtError::InvalidKey(format!("public key: {}", e)))?;
+        let kid = format!("{:x}", Sha256::digest(public_key))[..16].to_string();
+        let key = Self {
+            kid,
+            encoding_key,
+            decoding_key,
+        };
+
+        let probe = Claims::new(String::new(), i64::MAX, 0);
+        let token = probe
+            .into_jwt_token(&key)
+            .map_err(|e| JwtError::InvalidKey(e.to_string()))?;
+        if decode::<Claims>(
+            &token,
+            &key.decoding_key,
+            &Validation::new(Algorithm::RS256),
+        )
+        .is_err()
+        {
+            return Err(JwtError::InvalidKey(
+                "the public key does not match the private key".to_string(),
+            ));
+        }
+        Ok(key)
+    }
+}
+
+/// The key signing new tokens, and the retired keys which keep verifying the tokens they signed
+/// until those expire.
+#[derive(Debug)]
+pub(crate) struct KeyRing {
+    current: SigningKey,
+    /// The retired keys with the time they were retired at
+    retired: Vec<(SigningKey, i64)>,
+}
+
+impl KeyRing {
+    pub(crate) fn new(current: SigningKey) -> Self {
+        Self {
+            current,
+            retired: vec![],
+        }
+    }
+
+    pub(crate) fn current(&self) -> &SigningKey {
+        &self.current
+    }
+
+    /// Signs new tokens with the key from now on. Keys retired for longer than `keep_for`
+    /// seconds can only have signed expired tokens, so they are dropped.
+    pub(crate) fn rotate(&mut self, key: SigningKey, now: i64, keep_for: i64) {
+        if key.kid == self.current.kid {
+            return;
+        }
+        let retired = std::mem::replace(&mut self.current, key);
+        self.retired
+            .retain(|(k, at)| k.kid != self.current.kid && now - at <= keep_for);
+        self.retired.push((retired, now));
+    }
+
+    fn find(&self, kid: &str) -> Option<&SigningKey> {
+        std::iter::once(&self.current)
+            .chain(self.retired.iter().map(|(k, _)| k))
+            .find(|k| k.kid == kid)
+    }
+}
+
+/// The user acting on behalf of the subject, the `act` claim of RFC 8693.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub(crate) struct Actor {
+    pub(crate) sub: String,
+    pub(crate) tid: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub(crate) struct Claims {
     pub(crate) sub: String,
     pub(crate) exp: i64,
     pub(crate) iat: i64,
     pub(crate) nbf: i64,
+    /// The tenant of the subject
+    #[serde(default)]
+    pub(crate) tid: String,
+    #[serde(default)]
+    pub(crate) roles: Vec<String>,
+    #[serde(default)]
+    pub(crate) permissions: Vec<String>,
+    #[serde(default)]
+    pub(crate) groups: Vec<String>,
+    /// Only present in tokens issued to impersonate the subject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) act: Option<Actor>,
 }
 
 impl Claims {
@@ -18,29 +142,75 @@ impl Claims {
             exp: expire_time,
             iat: issue_at,
             nbf: issue_at,
+            tid: String::new(),
+            roles: vec![],
+            permissions: vec![],
+            groups: vec![],
+            act: None,
+        }
+    }
+
+    /// Claims for a token issued to the user, carrying the user's roles and effective permissions.
+    pub(crate) fn for_user(user: &User, expire_time: i64, issue_at: i64) -> Self {
+        Self {
+            tid: user.tenant_id.to_string(),
+            roles: user.role_names(),
+            permissions: user.permissions().into_iter().collect(),
+            ..Self::new(user.id.to_string(), expire_time, issue_at)
         }
     }
 
-    pub(crate) fn from_jwt_token(token: String, public_key: Vec<u8>) -> Result<Self> {
+    /// Embeds the ids of the groups the user belongs to, including inherited ones.
+    pub(crate) fn with_groups(self, groups: Vec<String>) -> Self {
+        Self { groups, ..self }
+    }
+
+    /// Marks the token as issued to the actor impersonating the subject.
+    pub(crate) fn with_actor(self, actor: &User) -> Self {
+        Self {
+            act: Some(Actor {
+                sub: actor.id.to_string(),
+                tid: actor.tenant_id.to_string(),
+            }),
+            ..self
+        }
+    }
+
+    /// Verifies the token with the key named in its header, or the current key without one.
+    pub(crate) fn from_jwt_token(token: String, keys: &KeyRing) -> Result<Self> {
+        let key = match decode_header(token.as_str())?.kid {
+            Some(kid) => keys.find(&kid).ok_or(JwtError::UnknownKey(kid))?,
+            None => keys.current(),
+        };
         let validation = Validation::new(Algorithm::RS256);
-        let token = decode::<Claims>(
-            token.as_str(),
-            &DecodingKey::from_rsa_pem(public_key.as_slice())?,
-            &validation,
-        )?;
+        let token = decode::<Claims>(token.as_str(), &key.decoding_key, &validation)?;
         Ok(token.claims)
     }
 
-    pub(crate) fn into_jwt_token(self, private_key: Vec<u8>) -> Result<String> {
-        let header = Header::new(Algorithm::RS256);
-        Ok(encode::<Claims>(
-            &header,
-            &self,
-            &EncodingKey::from_rsa_pem(private_key.as_slice())?,
-        )?)
+    pub(crate) fn into_jwt_token(self, key: &SigningKey) -> Result<String> {
+        let header = Header {
+            kid: Some(key.kid.clone()),
+            ..Header::new(Algorithm::RS256)
+        };
+        Ok(encode::<Claims>(&header, &self, &key.encoding_key)?)
     }
 
     pub(crate) fn get_user_id(&self) -> Result<Ulid> {
         Ok(Ulid::from_string(self.sub.as_str())?)
     }
+
+    pub(crate) fn get_tenant_id(&self) -> Result<TenantId> {
+        Ok(Ulid::from_string(self.tid.as_str())?)
+    }
+
+    /// The tenant and id of the impersonating user, if the token is an impersonated one.
+    pub(crate) fn get_actor(&self) -> Result<Option<(TenantId, UserId)>> {
+        match &self.act {
+            Some(actor) => Ok(Some((
+                Ulid::from_string(actor.tid.as_str())?,
+                Ulid::from_string(actor.sub.as_str())?,
+            ))),
+            None => Ok(None),
+        }
+    }
 }
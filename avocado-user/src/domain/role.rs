@@ -0,0 +1,80 @@
+use std::collections::BTreeSet;
+use thiserror::Error;
+use ulid::Ulid;
+
+pub(crate) type RoleId = Ulid;
+
+pub(crate) const ADMIN: &str = "admin";
+pub(crate) const NORMAL_USER: &str = "user";
+
+pub(crate) mod permission {
+    pub(crate) const ALL: &str = "*";
+    pub(crate) const AUDIT_READ: &str = "audit:read";
+    pub(crate) const GROUP_READ: &str = "group:read";
+    pub(crate) const GROUP_WRITE: &str = "group:write";
+    pub(crate) const USER_IMPERSONATE: &str = "user:impersonate";
+    pub(crate) const USER_READ: &str = "user:read";
+    pub(crate) const USER_WRITE: &str = "user:write";
+    pub(crate) const ROLE_READ: &str = "role:read";
+    pub(crate) const ROLE_WRITE: &str = "role:write";
+}
+
+#[derive(Error, Debug)]
+pub enum RoleError {
+    #[error("role with {field} {value} not exist")]
+    NotExist { field: String, value: String },
+    #[error("role with name {0} already exists")]
+    AlreadyExist(String),
+    #[error("role {0} is built in and cannot be changed")]
+    BuiltIn(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Role {
+    pub(crate) id: RoleId,
+    pub(crate) name: String,
+    pub(crate) permissions: BTreeSet<String>,
+}
+
+impl Role {
+    pub(crate) fn is_built_in(&self) -> bool {
+        self.name == ADMIN || self.name == NORMAL_USER
+    }
+
+    /// A granted permission matches either exactly, through the `*` wildcard,
+    /// or through a resource wildcard such as `user:*`.
+    pub(crate) fn grants(&self, permission: &str) -> bool {
+        self.permissions
+            .iter()
+            .any(|granted| permission_matches(granted, permission))
+    }
+}
+
+pub(crate) fn permission_matches(granted: &str, permission: &str) -> bool {
+    match granted.strip_suffix('*') {
+        Some(prefix) => permission.starts_with(prefix),
+        None => granted == permission,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::role::{permission, Role};
+    use ulid::Ulid;
+
+    #[test]
+    fn test_role_grants() {
+        let role = |permissions: &[&str]| Role {
+            id: Ulid::new(),
+            name: "test".to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        };
+
+        assert!(role(&[permission::ALL]).grants(permission::ROLE_WRITE));
+        assert!(role(&["user:*"]).grants(permission::USER_WRITE));
+        assert!(!role(&["user:*"]).grants(permission::ROLE_READ));
+        assert!(role(&[permission::USER_READ]).grants(permission::USER_READ));
+        assert!(!role(&[permission::USER_READ]).grants(permission::USER_WRITE));
+        assert!(!role(&[]).grants(permission::USER_READ));
+    }
+}
@@ -0,0 +1,24 @@
+use thiserror::Error;
+use ulid::Ulid;
+
+pub(crate) type TenantId = Ulid;
+
+/// The tenant operating the deployment, whose admins manage all the other tenants.
+pub(crate) const PLATFORM: &str = "platform";
+pub(crate) const PLATFORM_ID: TenantId = Ulid::nil();
+
+#[derive(Error, Debug)]
+pub enum TenantError {
+    #[error("tenant with {field} {value} not exist")]
+    NotExist { field: String, value: String },
+    #[error("tenant with name {0} already exists")]
+    AlreadyExist(String),
+    #[error("only platform admins can access tenant {0}")]
+    AccessDenied(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Tenant {
+    pub(crate) id: TenantId,
+    pub(crate) name: String,
+}
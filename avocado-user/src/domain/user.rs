@@ -1,61 +1,139 @@
-use anyhow::Result;
+use crate::domain::role::{permission, Role};
+use crate::domain::tenant::{TenantId, PLATFORM_ID};
+use anyhow::{anyhow, Result};
 use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use avocado_base::secret::SecretString;
-use std::fmt;
-use std::fmt::{Display, Formatter};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::str::FromStr;
 use thiserror::Error;
 use ulid::Ulid;
 
 pub(crate) type UserId = Ulid;
 
-#[derive(Debug, PartialEq, Clone)]
-pub(crate) enum Role {
-    NormalUser = 0,
-    Admin = 1,
+#[derive(Error, Debug)]
+pub enum UserError {
+    #[error("fail to authenticate the user")]
+    AuthenticationError,
+    #[error("user with {field} {value} not exist")]
+    NotExist { field: String, value: String },
+    #[error("user with email {0} already exists")]
+    AlreadyExist(String),
+    #[error("impersonation denied: {0}")]
+    ImpersonationDenied(String),
+    #[error("user {0} is not active")]
+    Inactive(String),
+    #[error("setup denied: {0}")]
+    SetupDenied(String),
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
+}
+
+/// Disabled users are kept but blocked from signing in, deleted ones are kept for the history only.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum UserStatus {
+    Active,
+    Disabled,
+    Deleted,
 }
 
-impl Display for Role {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+/// Optional details of a user, where empty strings are unset fields.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub(crate) struct Profile {
+    pub(crate) phone: String,
+    pub(crate) job_title: String,
+    pub(crate) locale: String,
+    pub(crate) timezone: String,
+    /// Attributes defined by the tenant, checked against the configured schema
+    pub(crate) attributes: Map<String, Value>,
+}
+
+impl UserStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
         match self {
-            Role::NormalUser => write!(f, "user"),
-            Role::Admin => write!(f, "admin"),
+            UserStatus::Active => "active",
+            UserStatus::Disabled => "disabled",
+            UserStatus::Deleted => "deleted",
         }
     }
 }
 
-impl TryFrom<i32> for Role {
-    type Error = &'static str;
+impl FromStr for UserStatus {
+    type Err = anyhow::Error;
 
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Role::NormalUser),
-            1 => Ok(Role::Admin),
-            _ => Err("invalid user role"),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(UserStatus::Active),
+            "disabled" => Ok(UserStatus::Disabled),
+            "deleted" => Ok(UserStatus::Deleted),
+            _ => Err(anyhow!("unknown user status {}", s)),
         }
     }
 }
 
-#[derive(Error, Debug)]
-pub enum UserError {
-    #[error("fail to authenticate the user")]
-    AuthenticationError,
-    #[error("user with {field} {value} not exist")]
-    NotExist { field: String, value: String },
-}
-
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct User {
     pub(crate) id: UserId,
+    pub(crate) tenant_id: TenantId,
     pub(crate) email: String,
     pub(crate) first_name: String,
     pub(crate) last_name: String,
     pub(crate) password_hash: String,
-    pub(crate) role: Role,
+    pub(crate) roles: Vec<Role>,
+    pub(crate) status: UserStatus,
+    pub(crate) profile: Profile,
+}
+
+/// Emails are the same whatever their case, so they're stored and looked up in lowercase.
+pub(crate) fn normalize_email(email: &str) -> String {
+    email.to_lowercase()
 }
 
+/// The real user behind a request made with an impersonated token.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Impersonator(pub(crate) User);
+
 impl User {
+    pub(crate) fn role_names(&self) -> Vec<String> {
+        self.roles.iter().map(|r| r.name.clone()).collect()
+    }
+
+    /// The effective permissions are the union of the permissions of all the user's roles.
+    pub(crate) fn permissions(&self) -> BTreeSet<String> {
+        self.roles
+            .iter()
+            .flat_map(|r| r.permissions.iter().cloned())
+            .collect()
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.status == UserStatus::Active
+    }
+
+    pub(crate) fn has_permission(&self, permission: &str) -> bool {
+        self.roles.iter().any(|r| r.grants(permission))
+    }
+
+    /// The first permission of the roles the user does not hold. Users can't grant, or act as
+    /// someone holding, more than they hold themselves.
+    pub(crate) fn missing_permission<'a>(
+        &self,
+        roles: impl IntoIterator<Item = &'a Role>,
+    ) -> Option<&'a str> {
+        roles
+            .into_iter()
+            .flat_map(|r| r.permissions.iter())
+            .map(String::as_str)
+            .find(|p| !self.has_permission(p))
+    }
+
+    /// Platform admins are the only users allowed to act on tenants other than their own.
+    pub(crate) fn is_platform_admin(&self) -> bool {
+        self.tenant_id == PLATFORM_ID && self.has_permission(permission::ALL)
+    }
+
     pub(crate) fn verify_password(
         plain_password: SecretString,
         password_hash: String,
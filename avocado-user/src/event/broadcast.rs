@@ -0,0 +1,31 @@
+use crate::domain::event::OutboxEvent;
+use crate::event::EventBus;
+use anyhow::Result;
+use tokio::sync::broadcast;
+
+/// Fans the events out to the subscribers within this process.
+#[derive(Debug)]
+pub(crate) struct BroadcastBus {
+    sender: broadcast::Sender<OutboxEvent>,
+}
+
+impl BroadcastBus {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        BroadcastBus { sender }
+    }
+}
+
+#[tonic::async_trait]
+impl EventBus for BroadcastBus {
+    async fn publish(&self, event: &OutboxEvent) -> Result<()> {
+        // Having no subscriber at the moment is not a failure to deliver, the event is just not
+        // of interest to anyone
+        let _ = self.sender.send(event.clone());
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Option<broadcast::Receiver<OutboxEvent>> {
+        Some(self.sender.subscribe())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::db::OutboxStore;
+use crate::domain::event::OutboxEvent;
+use crate::event::wake;
+use crate::state::principals::Principals;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::Receiver;
+
+/// Follows the outbox and forgets the cached principals its events are about.
+///
+/// The stores of the state forget the principals of their own changes right away, the outbox
+/// also brings the changes of the other replicas and of the admin cli, which share the database
+/// but not the cache.
+#[derive(Debug)]
+pub(crate) struct Eviction {
+    pub(crate) outbox_store: Arc<dyn OutboxStore>,
+    pub(crate) principals: Arc<Principals>,
+    /// The events published within this process, to forget without waiting for the interval
+    pub(crate) published: Option<Receiver<OutboxEvent>>,
+    pub(crate) interval: Duration,
+    pub(crate) batch_size: u64,
+}
+
+impl Eviction {
+    pub(crate) async fn run(mut self) {
+        // Nothing is cached yet, so the events before the start don't matter
+        let mut after = loop {
+            match self.outbox_store.last_seq().await {
+                Ok(seq) => break seq,
+                Err(e) => {
+                    tracing::error!("failed to read the outbox position: {:?}", e);
+                    tokio::time::sleep(self.interval).await;
+                }
+            }
+        };
+        loop {
+            match self.evict(after).await {
+                Ok(next) if next != after => {
+                    after = next;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("failed to follow outbox events: {:?}", e),
+            }
+            // The other processes don't publish on the bus of this one, so poll as well
+            tokio::select! {
+                _ = wake(&mut self.published, self.interval) => {}
+                _ = tokio::time::sleep(self.interval) => {}
+            }
+        }
+    }
+
+    /// Forgets the principals of one batch of events after the sequence number, and returns the
+    /// sequence number to continue after.
+    pub(crate) async fn evict(&self, after: i64) -> Result<i64> {
+        let events = self.outbox_store.list(after, self.batch_size).await?;
+        for event in &events {
+            self.principals.forget_event(&event.event);
+        }
+        Ok(events.last().map_or(after, |e| e.seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::jwt::who::Who;
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::login::Login;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::db::sqlite::user::Store as SqliteUserStore;
+    use crate::db::UserStore;
+    use crate::domain::event::DomainEvent;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::UserStatus;
+    use crate::event::eviction::Eviction;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_eviction() {
+        let pool = connect().await;
+        let state = State::new(pool.clone()).await;
+        let user_id = Add {
+            tenant_id: PLATFORM_ID,
+            email: "evicted@avocado.com".to_string(),
+            first_name: "Evicted".to_string(),
+            last_name: "Test".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: vec![],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let (access_token, _) = Login {
+            tenant: "".to_string(),
+            email: "evicted@avocado.com".to_string(),
+            password: SecretString::new("password".to_string()),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let eviction = Eviction {
+            outbox_store: state.outbox_store.clone(),
+            principals: state.principals.clone(),
+            published: None,
+            interval: Duration::from_secs(1),
+            batch_size: 10,
+        };
+        let after = state.outbox_store.last_seq().await.unwrap();
+        Who {
+            token: access_token.clone(),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert!(state.principals.get(&access_token).is_some());
+
+        // Disabled by another process, which the stores of this one don't see
+        let elsewhere = SqliteUserStore::new(pool);
+        let mut user = elsewhere
+            .get(&PLATFORM_ID, &user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        user.status = UserStatus::Disabled;
+        let events = [DomainEvent::user_disabled(&user)];
+        elsewhere
+            .update(&PLATFORM_ID, &user_id, user, &events)
+            .await
+            .unwrap();
+        assert!(state.principals.get(&access_token).is_some());
+
+        let next = eviction.evict(after).await.unwrap();
+        assert!(next > after);
+        assert!(state.principals.get(&access_token).is_none());
+        assert_eq!(eviction.evict(next).await.unwrap(), next);
+    }
+}
@@ -0,0 +1,22 @@
+use crate::domain::event::OutboxEvent;
+use crate::event::EventBus;
+use anyhow::Result;
+
+/// Writes the events to the log, for running without a message broker.
+#[derive(Debug)]
+pub(crate) struct LogBus;
+
+#[tonic::async_trait]
+impl EventBus for LogBus {
+    async fn publish(&self, event: &OutboxEvent) -> Result<()> {
+        tracing::info!(
+            seq = event.seq,
+            id = %event.id,
+            occurred_at = %event.occurred_at,
+            payload = %serde_json::to_string(&event.event)?,
+            "{}",
+            event.event.name()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use crate::cfg::{Bus, Event};
+use crate::domain::event::OutboxEvent;
+use anyhow::Result;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+pub(crate) mod broadcast;
+pub(crate) mod eviction;
+pub(crate) mod log;
+pub(crate) mod relay;
+
+/// Where the relay delivers the events of the outbox to.
+#[tonic::async_trait]
+pub(crate) trait EventBus: Send + Sync + Debug {
+    /// Returns only once the bus accepted the event, it may be called again for the same event.
+    async fn publish(&self, event: &OutboxEvent) -> Result<()>;
+
+    /// The events published from now on, for the buses delivering within this process.
+    fn subscribe(&self) -> Option<Receiver<OutboxEvent>> {
+        None
+    }
+}
+
+pub(crate) fn bus(config: &Event) -> Arc<dyn EventBus> {
+    match config.bus {
+        Bus::Log => Arc::new(log::LogBus),
+        Bus::Broadcast => Arc::new(broadcast::BroadcastBus::new(config.broadcast_capacity)),
+    }
+}
+
+/// Waits for an event published on the bus, or for the poll interval when the bus doesn't deliver
+/// within the process.
+pub(crate) async fn wake(published: &mut Option<Receiver<OutboxEvent>>, interval: Duration) {
+    match published {
+        Some(receiver) => match receiver.recv().await {
+            // Lagging behind only means more events to read from the outbox
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => *published = None,
+        },
+        None => tokio::time::sleep(interval).await,
+    }
+}
@@ -0,0 +1,138 @@
+use crate::db::OutboxStore;
+use crate::event::EventBus;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Publishes the events of the outbox to the event bus in the order they happened.
+///
+/// An event is only marked as published after the bus accepted it, so an event is delivered
+/// again when the relay stops in between, i.e. delivery is at least once.
+#[derive(Debug, Clone)]
+pub(crate) struct Relay {
+    pub(crate) outbox_store: Arc<dyn OutboxStore>,
+    pub(crate) event_bus: Arc<dyn EventBus>,
+    pub(crate) interval: Duration,
+    pub(crate) batch_size: u64,
+}
+
+impl Relay {
+    pub(crate) async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.relay().await {
+                tracing::error!("failed to relay outbox events: {:?}", e);
+            }
+        }
+    }
+
+    /// Publishes the pending events until none are left or the bus fails, when shutting down.
+    pub(crate) async fn flush(&self) {
+        loop {
+            match self.relay().await {
+                Ok(published) if published as u64 == self.batch_size => {}
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!("failed to relay outbox events: {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Publishes one batch of pending events and returns how many were published.
+    pub(crate) async fn relay(&self) -> Result<usize> {
+        let events = self.outbox_store.pending(self.batch_size).await?;
+        for (published, event) in events.iter().enumerate() {
+            // Stop at the first failure, publishing later events first would break their order
+            if let Err(e) = self.event_bus.publish(event).await {
+                tracing::warn!("failed to publish event {}: {:?}", event.seq, e);
+                return Ok(published);
+            }
+            self.outbox_store.mark_published(event.seq).await?;
+        }
+        Ok(events.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::user::add::Add;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::event::{DomainEvent, OutboxEvent};
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::event::relay::Relay;
+    use crate::event::EventBus;
+    use crate::state::State;
+    use anyhow::{anyhow, Result};
+    use avocado_base::secret::SecretString;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct TestBus {
+        fail: bool,
+        published: Mutex<Vec<OutboxEvent>>,
+    }
+
+    #[tonic::async_trait]
+    impl EventBus for TestBus {
+        async fn publish(&self, event: &OutboxEvent) -> Result<()> {
+            if self.fail {
+                return Err(anyhow!("bus unavailable"));
+            }
+            self.published.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relay() {
+        let state = State::new(connect().await).await;
+        for email in ["relay1@avocado.com", "relay2@avocado.com"] {
+            Add {
+                tenant_id: PLATFORM_ID,
+                email: email.to_string(),
+                first_name: "Relay".to_string(),
+                last_name: "Test".to_string(),
+                password: SecretString::new("password".to_string()),
+                roles: vec![],
+                actor: None,
+            }
+            .execute(state.clone())
+            .await
+            .unwrap();
+        }
+
+        let failing = Relay {
+            outbox_store: state.outbox_store.clone(),
+            event_bus: Arc::new(TestBus {
+                fail: true,
+                ..TestBus::default()
+            }),
+            interval: Duration::from_secs(1),
+            batch_size: 10,
+        };
+        assert_eq!(failing.relay().await.unwrap(), 0);
+        assert_eq!(state.outbox_store.pending(10).await.unwrap().len(), 2);
+
+        let bus = Arc::new(TestBus::default());
+        let relay = Relay {
+            outbox_store: state.outbox_store.clone(),
+            event_bus: bus.clone(),
+            interval: Duration::from_secs(1),
+            batch_size: 10,
+        };
+        assert_eq!(relay.relay().await.unwrap(), 2);
+        let published = bus.published.lock().unwrap().clone();
+        assert!(published[0].seq < published[1].seq);
+        assert!(matches!(
+            &published[0].event,
+            DomainEvent::UserCreated { email, .. } if email == "relay1@avocado.com"
+        ));
+        assert!(state.outbox_store.pending(10).await.unwrap().is_empty());
+        assert_eq!(relay.relay().await.unwrap(), 0);
+    }
+}
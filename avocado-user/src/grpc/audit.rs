@@ -0,0 +1,113 @@
+use crate::cmd::audit::record::Record;
+use crate::cmd::{Command, CommandResult};
+use crate::domain::audit::{Action, AuditEvent, Outcome};
+use crate::domain::jwt::Claims;
+use crate::domain::tenant::{TenantId, PLATFORM};
+use crate::domain::user::{Impersonator, User, UserId};
+use crate::middleware::client_ip;
+use crate::state::State;
+use chrono::Utc;
+use tonic::transport::Body;
+use tonic::Request;
+use ulid::Ulid;
+
+/// Who made a request and from where, recorded along with the outcome of the audited action.
+#[derive(Debug, Clone)]
+pub(crate) struct Audit {
+    pub(crate) tenant_id: Option<TenantId>,
+    pub(crate) actor_id: Option<UserId>,
+    pub(crate) source_ip: Option<String>,
+}
+
+impl Audit {
+    /// The context of a request, with the user authenticated by the auth middleware as the actor.
+    /// With an impersonated token the real user is the actor, who stays accountable.
+    pub(crate) fn of<T>(request: &Request<T>) -> Self {
+        let user = match request.extensions().get::<Impersonator>() {
+            Some(impersonator) => Some(&impersonator.0),
+            None => request.extensions().get::<User>(),
+        };
+        Self {
+            tenant_id: user.map(|u| u.tenant_id),
+            actor_id: user.map(|u| u.id),
+            source_ip: request.remote_addr().map(|a| a.ip().to_string()),
+        }
+    }
+
+    /// The context of a call refused by the auth middleware, with the user it authenticated, if
+    /// any, as the actor.
+    pub(crate) fn of_call(req: &hyper::Request<Body>, user: Option<&User>) -> Self {
+        Self {
+            tenant_id: user.map(|u| u.tenant_id),
+            actor_id: user.map(|u| u.id),
+            source_ip: client_ip(req).map(|ip| ip.to_string()),
+        }
+    }
+
+    /// Identifies the actor of a login from the access token issued to them.
+    pub(crate) fn with_subject(self, state: &State, access_token: &str) -> Self {
+        match Claims::from_jwt_token(access_token.to_string(), &state.keys.read().unwrap()) {
+            Ok(claims) => Self {
+                tenant_id: claims.get_tenant_id().ok(),
+                actor_id: claims.get_user_id().ok(),
+                ..self
+            },
+            Err(_) => self,
+        }
+    }
+
+    /// Attributes a failed login to the tenant it was attempted on, when that tenant exists.
+    pub(crate) async fn with_tenant(self, state: &State, tenant: &str) -> Self {
+        let name = match tenant {
+            "" => PLATFORM,
+            name => name,
+        };
+        match state.tenant_store.get_by_name(name).await {
+            Ok(Some(t)) => Self {
+                tenant_id: Some(t.id),
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Appends the event to the audit log. A failure to record is only logged, so the audit log
+    /// never changes the outcome of the request itself.
+    pub(crate) async fn record<R>(
+        self,
+        state: &State,
+        action: Action,
+        target: String,
+        result: &CommandResult<R>,
+    ) {
+        let outcome = match result {
+            Ok(_) => Outcome::Success,
+            Err(e) => Outcome::Failure(e.0.to_string()),
+        };
+        self.append(state, action, target, outcome).await
+    }
+
+    /// Appends a call refused by the auth middleware, before any handler could audit it, with the
+    /// method as the target.
+    pub(crate) async fn deny(self, state: &State, method: &str, reason: &str) {
+        let outcome = Outcome::Failure(reason.to_string());
+        self.append(state, Action::AccessDenied, method.to_string(), outcome)
+            .await
+    }
+
+    async fn append(self, state: &State, action: Action, target: String, outcome: Outcome) {
+        let event = AuditEvent {
+            id: Ulid::new(),
+            tenant_id: self.tenant_id,
+            actor_id: self.actor_id,
+            action,
+            target,
+            outcome,
+            source_ip: self.source_ip,
+            occurred_at: Utc::now(),
+        };
+        if let Err(e) = (Record { event }).execute(state.clone()).await {
+            tracing::error!("unable to record audit event: {:?}", e.0);
+        }
+    }
+}
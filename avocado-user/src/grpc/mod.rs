@@ -1,8 +1,22 @@
+// `Status` is what every tonic service method returns, so helpers return it too
+#![allow(clippy::result_large_err)]
+
 use crate::cmd::CommandError;
-use crate::domain::user::{User, UserError};
+use crate::domain::group::{Group, GroupError};
+use crate::domain::health::HealthError;
+use crate::domain::jwt::JwtError;
+use crate::domain::role::{Role, RoleError};
+use crate::domain::tenant::{Tenant, TenantError, TenantId};
+use crate::domain::user::{Impersonator, Profile as DomainProfile, User, UserError};
+use crate::middleware::policy::Policy;
 use avocado_base::error::ValidationMessages;
-use avocado_proto::grpc::user::UserReply;
-use tonic::Status;
+use avocado_proto::grpc::group::GroupReply;
+use avocado_proto::grpc::role::RoleReply;
+use avocado_proto::grpc::tenant::TenantReply;
+use avocado_proto::grpc::user::{Actor, Profile, UserReply};
+use serde_json::Value;
+use tonic::{Request, Status};
+use ulid::Ulid;
 use validator::ValidationErrors;
 
 impl From<CommandError> for Status {
@@ -13,12 +27,46 @@ impl From<CommandError> for Status {
             Status::invalid_argument(
                 serde_json::to_string(&ValidationMessages::from(e.clone()).messages).unwrap(),
             )
+        } else if let Some(e) = error.0.downcast_ref::<RoleError>() {
+            match e {
+                RoleError::NotExist { .. } => Status::not_found(e.to_string()),
+                RoleError::AlreadyExist(_) => Status::already_exists(e.to_string()),
+                RoleError::BuiltIn(_) => Status::failed_precondition(e.to_string()),
+            }
+        } else if let Some(e) = error.0.downcast_ref::<GroupError>() {
+            match e {
+                GroupError::NotExist { .. } => Status::not_found(e.to_string()),
+                GroupError::AlreadyExist(_) => Status::already_exists(e.to_string()),
+                GroupError::Cycle(_) => Status::failed_precondition(e.to_string()),
+            }
+        } else if let Some(e) = error.0.downcast_ref::<TenantError>() {
+            match e {
+                TenantError::NotExist { .. } => Status::not_found(e.to_string()),
+                TenantError::AlreadyExist(_) => Status::already_exists(e.to_string()),
+                TenantError::AccessDenied(_) => Status::permission_denied(e.to_string()),
+            }
+        } else if let Some(e) = error.0.downcast_ref::<HealthError>() {
+            match e {
+                HealthError::UnknownService(_) => Status::not_found(e.to_string()),
+            }
+        } else if let Some(e) = error.0.downcast_ref::<JwtError>() {
+            match e {
+                JwtError::InvalidKey(_) => Status::invalid_argument(e.to_string()),
+                JwtError::UnknownKey(_) => Status::unauthenticated(e.to_string()),
+            }
         } else {
             match error.0.downcast_ref::<UserError>() {
                 Some(UserError::AuthenticationError) => {
                     Status::unauthenticated(error.0.to_string())
                 }
                 Some(UserError::NotExist { .. }) => Status::not_found(error.0.to_string()),
+                Some(UserError::AlreadyExist(_)) => Status::already_exists(error.0.to_string()),
+                Some(UserError::ImpersonationDenied(_))
+                | Some(UserError::Inactive(_))
+                | Some(UserError::SetupDenied(_))
+                | Some(UserError::PermissionDenied(_)) => {
+                    Status::permission_denied(error.0.to_string())
+                }
                 None => Status::internal(error.0.to_string()),
             }
         }
@@ -32,9 +80,110 @@ impl From<User> for UserReply {
             email: user.email.clone(),
             first_name: user.first_name.clone(),
             last_name: user.last_name.clone(),
-            role: user.role.to_string(),
+            roles: user.role_names(),
+            permissions: user.permissions().into_iter().collect(),
+            tenant_id: user.tenant_id.to_string(),
+            actor: None,
+            status: user.status.as_str().to_string(),
+            profile: Some(user.profile.into()),
+        }
+    }
+}
+
+impl From<DomainProfile> for Profile {
+    fn from(profile: DomainProfile) -> Self {
+        Self {
+            phone: profile.phone,
+            job_title: profile.job_title,
+            locale: profile.locale,
+            timezone: profile.timezone,
+            attributes: Value::Object(profile.attributes).to_string(),
         }
     }
 }
 
+impl From<Impersonator> for Actor {
+    fn from(impersonator: Impersonator) -> Self {
+        Self {
+            id: impersonator.0.id.to_string(),
+            email: impersonator.0.email,
+            tenant_id: impersonator.0.tenant_id.to_string(),
+        }
+    }
+}
+
+impl From<Role> for RoleReply {
+    fn from(role: Role) -> Self {
+        Self {
+            id: role.id.to_string(),
+            name: role.name,
+            permissions: role.permissions.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Group> for GroupReply {
+    fn from(group: Group) -> Self {
+        Self {
+            id: group.id.to_string(),
+            name: group.name,
+            parent_id: group.parent_id.map(|p| p.to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<Tenant> for TenantReply {
+    fn from(tenant: Tenant) -> Self {
+        Self {
+            id: tenant.id.to_string(),
+            name: tenant.name,
+        }
+    }
+}
+
+/// The user authenticated by the auth middleware.
+pub(crate) fn caller<T>(request: &Request<T>) -> Result<&User, Status> {
+    request
+        .extensions()
+        .get::<User>()
+        .ok_or_else(|| Status::unauthenticated("user not found"))
+}
+
+/// Ensures the user authenticated by the auth middleware holds the permission, for checks which
+/// depend on the request rather than only on the method and are left out of the method policies.
+pub(crate) fn authorize<T>(request: &Request<T>, permission: &'static str) -> Result<(), Status> {
+    Policy::Permission(permission).check(caller(request)?)
+}
+
+/// Refuses requests made with an impersonated token, for operations only the real user may do.
+pub(crate) fn refuse_impersonated<T>(request: &Request<T>) -> Result<(), Status> {
+    match request.extensions().get::<Impersonator>() {
+        Some(_) => Err(Status::permission_denied(
+            "not allowed with an impersonated token",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Resolves the tenant the request acts on, the caller's own tenant unless a platform admin
+/// names another one.
+pub(crate) fn tenant_scope<T>(request: &Request<T>, tenant_id: &str) -> Result<TenantId, Status> {
+    let user = caller(request)?;
+    if tenant_id.is_empty() {
+        return Ok(user.tenant_id);
+    }
+    let tenant_id = parse_id(tenant_id, "tenant id")?;
+    if tenant_id != user.tenant_id && !user.is_platform_admin() {
+        return Err(Status::permission_denied(
+            TenantError::AccessDenied(tenant_id.to_string()).to_string(),
+        ));
+    }
+    Ok(tenant_id)
+}
+
+pub(crate) fn parse_id(id: &str, field: &str) -> Result<Ulid, Status> {
+    Ulid::from_string(id).map_err(|_| Status::invalid_argument(format!("invalid {}", field)))
+}
+
+pub(crate) mod audit;
 pub(crate) mod service;
@@ -0,0 +1,106 @@
+use crate::cmd::audit::list::List;
+use crate::cmd::Command;
+use crate::domain::audit::{Action, AuditEvent, AuditFilter, Outcome};
+use crate::grpc::{caller, parse_id, tenant_scope};
+use crate::state::State;
+use avocado_proto::grpc::audit::audit_server::Audit;
+use avocado_proto::grpc::audit::{
+    AuditEventReply, ListAuditEventsReply, ListAuditEventsRequest, Outcome as OutcomeFilter,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use tonic::{Request, Response, Status};
+
+#[derive(Debug)]
+pub(crate) struct Service {
+    pub(crate) state: State,
+}
+
+fn parse_timestamp(timestamp: i64, field: &str) -> Result<Option<DateTime<Utc>>, Status> {
+    match timestamp {
+        0 => Ok(None),
+        seconds => Utc
+            .timestamp_opt(seconds, 0)
+            .single()
+            .map(Some)
+            .ok_or_else(|| Status::invalid_argument(format!("invalid {}", field))),
+    }
+}
+
+impl From<AuditEvent> for AuditEventReply {
+    fn from(event: AuditEvent) -> Self {
+        let (success, failure_reason) = match event.outcome {
+            Outcome::Success => (true, String::new()),
+            Outcome::Failure(reason) => (false, reason),
+        };
+        Self {
+            id: event.id.to_string(),
+            tenant_id: event.tenant_id.map(|t| t.to_string()).unwrap_or_default(),
+            actor_id: event.actor_id.map(|a| a.to_string()).unwrap_or_default(),
+            action: event.action.to_string(),
+            target: event.target,
+            success,
+            failure_reason,
+            source_ip: event.source_ip.unwrap_or_default(),
+            occurred_at: event.occurred_at.timestamp(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Audit for Service {
+    async fn list_audit_events(
+        &self,
+        request: Request<ListAuditEventsRequest>,
+    ) -> Result<Response<ListAuditEventsReply>, Status> {
+        let req = request.get_ref();
+        let tenant_id = if req.without_tenant {
+            if !caller(&request)?.is_platform_admin() {
+                return Err(Status::permission_denied(
+                    "only platform admins can list the events without a tenant",
+                ));
+            }
+            None
+        } else {
+            Some(tenant_scope(&request, &req.tenant_id)?)
+        };
+        let filter = AuditFilter {
+            tenant_id,
+            without_tenant: req.without_tenant,
+            actor_id: match req.actor_id.as_str() {
+                "" => None,
+                id => Some(parse_id(id, "actor id")?),
+            },
+            action: match req.action.as_str() {
+                "" => None,
+                action => Some(
+                    action
+                        .parse::<Action>()
+                        .map_err(|e| Status::invalid_argument(e.to_string()))?,
+                ),
+            },
+            target: Some(req.target.clone()).filter(|t| !t.is_empty()),
+            success: match req.outcome() {
+                OutcomeFilter::Any => None,
+                OutcomeFilter::Success => Some(true),
+                OutcomeFilter::Failure => Some(false),
+            },
+            since: parse_timestamp(req.since, "since")?,
+            until: parse_timestamp(req.until, "until")?,
+        };
+        let cmd = List {
+            filter,
+            page_size: req.page_size.into(),
+            page_token: match req.page_token.as_str() {
+                "" => None,
+                token => Some(parse_id(token, "page token")?),
+            },
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok((events, next_page_token)) => Ok(Response::new(ListAuditEventsReply {
+                events: events.into_iter().map(|e| e.into()).collect(),
+                next_page_token: next_page_token.map(|t| t.to_string()).unwrap_or_default(),
+            })),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
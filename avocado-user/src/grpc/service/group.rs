@@ -0,0 +1,192 @@
+use crate::cmd::group::add::Add;
+use crate::cmd::group::add_member::AddMember;
+use crate::cmd::group::delete::Delete;
+use crate::cmd::group::list::List;
+use crate::cmd::group::list_members::ListMembers;
+use crate::cmd::group::list_user_groups::ListUserGroups;
+use crate::cmd::group::remove_member::RemoveMember;
+use crate::cmd::group::update::Update;
+use crate::cmd::Command;
+use crate::domain::group::GroupId;
+use crate::domain::role::permission;
+use crate::grpc::{authorize, caller, parse_id};
+use crate::state::State;
+use avocado_proto::grpc::group::group_server::Group;
+use avocado_proto::grpc::group::{
+    AddMemberReply, AddMemberRequest, AddReply, AddRequest, DeleteReply, DeleteRequest, GroupReply,
+    ListMembersRequest, ListRequest, ListUserGroupsRequest, MemberReply, RemoveMemberReply,
+    RemoveMemberRequest, UpdateRequest,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+#[derive(Debug)]
+pub(crate) struct Service {
+    pub(crate) state: State,
+}
+
+fn parse_parent_id(parent_id: &str) -> Result<Option<GroupId>, Status> {
+    match parent_id {
+        "" => Ok(None),
+        id => parse_id(id, "parent id").map(Some),
+    }
+}
+
+/// Streams the replies to the client from a separate task.
+fn stream<T: Send + 'static>(replies: Vec<T>) -> ReceiverStream<Result<T, Status>> {
+    let (tx, rx) = mpsc::channel(8);
+
+    tokio::spawn(async move {
+        for reply in replies {
+            match tx.send(Ok(reply)).await {
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::error!("group channel sending error: {:?}", e)
+                }
+            };
+        }
+    });
+    ReceiverStream::new(rx)
+}
+
+#[tonic::async_trait]
+impl Group for Service {
+    async fn add(&self, request: Request<AddRequest>) -> Result<Response<AddReply>, Status> {
+        let cmd = Add {
+            tenant_id: caller(&request)?.tenant_id,
+            name: request.get_ref().name.clone(),
+            parent_id: parse_parent_id(&request.get_ref().parent_id)?,
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok(group_id) => Ok(Response::new(AddReply {
+                group_id: group_id.to_string(),
+            })),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    type ListStream = ReceiverStream<Result<GroupReply, Status>>;
+
+    async fn list(
+        &self,
+        request: Request<ListRequest>,
+    ) -> Result<Response<Self::ListStream>, Status> {
+        let groups = List {
+            tenant_id: caller(&request)?.tenant_id,
+        }
+        .execute(self.state.clone())
+        .await?;
+        Ok(Response::new(stream(
+            groups.into_iter().map(|g| g.into()).collect(),
+        )))
+    }
+
+    async fn update(
+        &self,
+        request: Request<UpdateRequest>,
+    ) -> Result<Response<GroupReply>, Status> {
+        let cmd = Update {
+            tenant_id: caller(&request)?.tenant_id,
+            group_id: parse_id(&request.get_ref().group_id, "group id")?,
+            name: request.get_ref().name.clone(),
+            parent_id: parse_parent_id(&request.get_ref().parent_id)?,
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok(group) => Ok(Response::new(group.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteRequest>,
+    ) -> Result<Response<DeleteReply>, Status> {
+        let cmd = Delete {
+            tenant_id: caller(&request)?.tenant_id,
+            group_id: parse_id(&request.get_ref().group_id, "group id")?,
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok(_) => Ok(Response::new(DeleteReply {})),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn add_member(
+        &self,
+        request: Request<AddMemberRequest>,
+    ) -> Result<Response<AddMemberReply>, Status> {
+        let cmd = AddMember {
+            tenant_id: caller(&request)?.tenant_id,
+            group_id: parse_id(&request.get_ref().group_id, "group id")?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok(_) => Ok(Response::new(AddMemberReply {})),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn remove_member(
+        &self,
+        request: Request<RemoveMemberRequest>,
+    ) -> Result<Response<RemoveMemberReply>, Status> {
+        let cmd = RemoveMember {
+            tenant_id: caller(&request)?.tenant_id,
+            group_id: parse_id(&request.get_ref().group_id, "group id")?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok(_) => Ok(Response::new(RemoveMemberReply {})),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    type ListMembersStream = ReceiverStream<Result<MemberReply, Status>>;
+
+    async fn list_members(
+        &self,
+        request: Request<ListMembersRequest>,
+    ) -> Result<Response<Self::ListMembersStream>, Status> {
+        let cmd = ListMembers {
+            tenant_id: caller(&request)?.tenant_id,
+            group_id: parse_id(&request.get_ref().group_id, "group id")?,
+            recursive: request.get_ref().recursive,
+        };
+        let members = cmd.execute(self.state.clone()).await?;
+        Ok(Response::new(stream(
+            members
+                .into_iter()
+                .map(|u| MemberReply {
+                    user_id: u.id.to_string(),
+                    email: u.email,
+                    first_name: u.first_name,
+                    last_name: u.last_name,
+                })
+                .collect(),
+        )))
+    }
+
+    type ListUserGroupsStream = ReceiverStream<Result<GroupReply, Status>>;
+
+    async fn list_user_groups(
+        &self,
+        request: Request<ListUserGroupsRequest>,
+    ) -> Result<Response<Self::ListUserGroupsStream>, Status> {
+        let user_id = parse_id(&request.get_ref().user_id, "user id")?;
+        let user = caller(&request)?;
+        // Everyone may list their own groups
+        if user.id != user_id {
+            authorize(&request, permission::GROUP_READ)?;
+        }
+        let groups = ListUserGroups {
+            tenant_id: user.tenant_id,
+            user_id,
+        }
+        .execute(self.state.clone())
+        .await?;
+        Ok(Response::new(stream(
+            groups.into_iter().map(|g| g.into()).collect(),
+        )))
+    }
+}
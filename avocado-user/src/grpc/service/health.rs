@@ -0,0 +1,84 @@
+use crate::cmd::health::check::Check;
+use crate::cmd::Command;
+use crate::state::State;
+use avocado_proto::grpc::health::health_check_response::ServingStatus;
+use avocado_proto::grpc::health::health_server::Health;
+use avocado_proto::grpc::health::{HealthCheckRequest, HealthCheckResponse};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+/// How often a watched service is checked for a change of its status.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub(crate) struct Service {
+    pub(crate) state: State,
+}
+
+fn reply(status: ServingStatus) -> HealthCheckResponse {
+    HealthCheckResponse {
+        status: status as i32,
+    }
+}
+
+fn serving_status(serving: bool) -> ServingStatus {
+    match serving {
+        true => ServingStatus::Serving,
+        false => ServingStatus::NotServing,
+    }
+}
+
+#[tonic::async_trait]
+impl Health for Service {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let cmd = Check {
+            service: request.into_inner().service,
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok(serving) => Ok(Response::new(reply(serving_status(serving)))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    type WatchStream = ReceiverStream<Result<HealthCheckResponse, Status>>;
+
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service = request.into_inner().service;
+        let state = self.state.clone();
+        let (tx, rx) = mpsc::channel(1);
+
+        // Sends the status, then each change of it until the watcher goes away
+        tokio::spawn(async move {
+            let mut last = None;
+            loop {
+                let cmd = Check {
+                    service: service.clone(),
+                };
+                let status = match cmd.execute(state.clone()).await {
+                    Ok(serving) => serving_status(serving),
+                    Err(_) => ServingStatus::ServiceUnknown,
+                };
+                if last != Some(status) {
+                    if tx.send(Ok(reply(status))).await.is_err() {
+                        break;
+                    }
+                    last = Some(status);
+                }
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    _ = sleep(WATCH_INTERVAL) => {}
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
@@ -1,9 +1,16 @@
 use crate::cmd::jwt::refresh::Refresh;
+use crate::cmd::jwt::rotate_key::RotateKey;
 use crate::cmd::jwt::verify::Verify;
 use crate::cmd::Command;
+use crate::domain::audit::Action;
+use crate::grpc::audit::Audit;
+use crate::grpc::refuse_impersonated;
 use crate::state::State;
+use avocado_base::secret::SecretString;
 use avocado_proto::grpc::jwt::jwt_server::Jwt;
-use avocado_proto::grpc::jwt::{RefreshReply, RefreshRequest, VerifyReply, VerifyRequest};
+use avocado_proto::grpc::jwt::{
+    RefreshReply, RefreshRequest, RotateKeyReply, RotateKeyRequest, VerifyReply, VerifyRequest,
+};
 use tonic::{Request, Response, Status};
 
 #[derive(Debug)]
@@ -26,6 +33,11 @@ impl Jwt for Service {
                 exp: c.exp,
                 iat: c.iat,
                 nbf: c.nbf,
+                roles: c.roles,
+                permissions: c.permissions,
+                groups: c.groups,
+                tid: c.tid,
+                act: c.act.map(|a| a.sub).unwrap_or_default(),
             })),
             Err(e) => Err(e.into()),
         }
@@ -38,7 +50,14 @@ impl Jwt for Service {
         let cmd = Refresh {
             refresh_token: request.get_ref().refresh_token.clone(),
         };
-        match cmd.execute(self.state.clone()).await {
+        let result = cmd.execute(self.state.clone()).await;
+        self.state.metrics.token_refresh(result.is_ok());
+        let audit = Audit::of(&request);
+        let target = audit.actor_id.map(|id| id.to_string()).unwrap_or_default();
+        audit
+            .record(&self.state, Action::Refresh, target, &result)
+            .await;
+        match result {
             Ok((access_token, refresh_token)) => Ok(Response::new(RefreshReply {
                 access_token,
                 refresh_token,
@@ -46,4 +65,26 @@ impl Jwt for Service {
             Err(e) => Err(e.into()),
         }
     }
+    async fn rotate_key(
+        &self,
+        request: Request<RotateKeyRequest>,
+    ) -> Result<Response<RotateKeyReply>, Status> {
+        refuse_impersonated(&request)?;
+        let cmd = RotateKey {
+            private_key: SecretString::new(request.get_ref().private_key.clone()),
+            public_key: request.get_ref().public_key.clone(),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        let target = match &result {
+            Ok(kid) => kid.clone(),
+            Err(_) => String::new(),
+        };
+        Audit::of(&request)
+            .record(&self.state, Action::KeyRotate, target, &result)
+            .await;
+        match result {
+            Ok(kid) => Ok(Response::new(RotateKeyReply { kid })),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
@@ -1,2 +1,8 @@
+pub(crate) mod audit;
+pub(crate) mod group;
+pub(crate) mod health;
 pub(crate) mod jwt;
+pub(crate) mod reflection;
+pub(crate) mod role;
+pub(crate) mod tenant;
 pub(crate) mod user;
@@ -0,0 +1,210 @@
+use avocado_proto::grpc::reflection::server_reflection_request::MessageRequest;
+use avocado_proto::grpc::reflection::server_reflection_response::MessageResponse;
+use avocado_proto::grpc::reflection::server_reflection_server::ServerReflection;
+use avocado_proto::grpc::reflection::{
+    ErrorResponse, ExtensionNumberResponse, FileDescriptorResponse, ListServiceResponse,
+    ServerReflectionRequest, ServerReflectionResponse, ServiceResponse,
+};
+use prost::Message;
+use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Request, Response, Status, Streaming};
+
+/// The files of a descriptor set, with the symbols they define.
+#[derive(Debug, Default)]
+struct Descriptors {
+    files: HashMap<String, FileDescriptorProto>,
+    /// The files by the full names of the services, methods, messages and enums in them
+    symbols: HashMap<String, String>,
+    services: Vec<String>,
+}
+
+impl Descriptors {
+    fn decode(descriptor_set: &[u8]) -> Result<Self, prost::DecodeError> {
+        fn add_message(
+            descriptors: &mut Descriptors,
+            file: &str,
+            prefix: &str,
+            message: &DescriptorProto,
+        ) {
+            let name = format!("{}{}", prefix, message.name());
+            for nested in &message.nested_type {
+                add_message(descriptors, file, &format!("{}.", name), nested);
+            }
+            for nested in &message.enum_type {
+                descriptors
+                    .symbols
+                    .insert(format!("{}.{}", name, nested.name()), file.to_string());
+            }
+            descriptors.symbols.insert(name, file.to_string());
+        }
+
+        let mut descriptors = Descriptors::default();
+        for file in FileDescriptorSet::decode(descriptor_set)?.file {
+            let prefix = match file.package() {
+                "" => String::new(),
+                package => format!("{}.", package),
+            };
+            for service in &file.service {
+                let name = format!("{}{}", prefix, service.name());
+                for method in &service.method {
+                    descriptors.symbols.insert(
+                        format!("{}.{}", name, method.name()),
+                        file.name().to_string(),
+                    );
+                }
+                descriptors
+                    .symbols
+                    .insert(name.clone(), file.name().to_string());
+                descriptors.services.push(name);
+            }
+            for message in &file.message_type {
+                add_message(&mut descriptors, file.name(), &prefix, message);
+            }
+            for enumeration in &file.enum_type {
+                descriptors.symbols.insert(
+                    format!("{}{}", prefix, enumeration.name()),
+                    file.name().to_string(),
+                );
+            }
+            descriptors.files.insert(file.name().to_string(), file);
+        }
+        descriptors.services.sort();
+        Ok(descriptors)
+    }
+
+    /// The encoded file along with the files it depends on, which clients need to make sense
+    /// of it.
+    fn file_with_dependencies(&self, name: &str) -> Option<Vec<Vec<u8>>> {
+        fn collect<'a>(
+            descriptors: &'a Descriptors,
+            name: &str,
+            found: &mut BTreeMap<&'a str, &'a FileDescriptorProto>,
+        ) {
+            if let Some((name, file)) = descriptors.files.get_key_value(name) {
+                if found.insert(name, file).is_none() {
+                    for dependency in &file.dependency {
+                        collect(descriptors, dependency, found);
+                    }
+                }
+            }
+        }
+
+        let file = self.files.get(name)?;
+        let mut found = BTreeMap::new();
+        collect(self, name, &mut found);
+        found.remove(name);
+        Some(
+            std::iter::once(file)
+                .chain(found.into_values())
+                .map(|f| f.encode_to_vec())
+                .collect(),
+        )
+    }
+
+    fn respond(&self, request: ServerReflectionRequest) -> ServerReflectionResponse {
+        let error = |code: Code, message: String| {
+            MessageResponse::ErrorResponse(ErrorResponse {
+                error_code: code as i32,
+                error_message: message,
+            })
+        };
+        let file = |name: Option<&String>, missing: String| match name
+            .and_then(|n| self.file_with_dependencies(n))
+        {
+            Some(files) => MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+                file_descriptor_proto: files,
+            }),
+            None => error(Code::NotFound, missing),
+        };
+
+        let response = match &request.message_request {
+            Some(MessageRequest::ListServices(_)) => {
+                MessageResponse::ListServicesResponse(ListServiceResponse {
+                    service: self
+                        .services
+                        .iter()
+                        .map(|name| ServiceResponse { name: name.clone() })
+                        .collect(),
+                })
+            }
+            Some(MessageRequest::FileByFilename(name)) => {
+                file(Some(name), format!("unknown file {}", name))
+            }
+            Some(MessageRequest::FileContainingSymbol(symbol)) => file(
+                self.symbols.get(symbol),
+                format!("unknown symbol {}", symbol),
+            ),
+            // None of the files declare extensions
+            Some(MessageRequest::FileContainingExtension(extension)) => error(
+                Code::NotFound,
+                format!(
+                    "unknown extension {} of {}",
+                    extension.extension_number, extension.containing_type
+                ),
+            ),
+            Some(MessageRequest::AllExtensionNumbersOfType(name)) => {
+                match self.symbols.contains_key(name) {
+                    true => MessageResponse::AllExtensionNumbersResponse(ExtensionNumberResponse {
+                        base_type_name: name.clone(),
+                        extension_number: vec![],
+                    }),
+                    false => error(Code::NotFound, format!("unknown type {}", name)),
+                }
+            }
+            None => error(Code::InvalidArgument, "empty request".to_string()),
+        };
+        ServerReflectionResponse {
+            valid_host: request.host.clone(),
+            original_request: Some(request),
+            message_response: Some(response),
+        }
+    }
+}
+
+/// Describes the services to clients like `grpcurl`.
+#[derive(Debug, Clone)]
+pub(crate) struct Service {
+    descriptors: Arc<Descriptors>,
+}
+
+impl Service {
+    pub(crate) fn new(descriptor_set: &[u8]) -> Result<Self, prost::DecodeError> {
+        Ok(Service {
+            descriptors: Arc::new(Descriptors::decode(descriptor_set)?),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl ServerReflection for Service {
+    type ServerReflectionInfoStream = ReceiverStream<Result<ServerReflectionResponse, Status>>;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let mut requests = request.into_inner();
+        let descriptors = self.descriptors.clone();
+        let (tx, rx) = mpsc::channel(8);
+
+        // Answers each request in turn until the client is done
+        tokio::spawn(async move {
+            loop {
+                let response = match requests.message().await {
+                    Ok(Some(request)) => Ok(descriptors.respond(request)),
+                    Ok(None) => break,
+                    Err(status) => Err(status),
+                };
+                let failed = response.is_err();
+                if tx.send(response).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
@@ -0,0 +1,106 @@
+use crate::cmd::role::add::Add;
+use crate::cmd::role::delete::Delete;
+use crate::cmd::role::list::List;
+use crate::cmd::role::update::Update;
+use crate::cmd::Command;
+use crate::domain::audit::Action;
+use crate::grpc::audit::Audit;
+use crate::grpc::parse_id;
+use crate::state::State;
+use avocado_proto::grpc::role::role_server::Role;
+use avocado_proto::grpc::role::{
+    AddReply, AddRequest, DeleteReply, DeleteRequest, ListRequest, RoleReply, UpdateRequest,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+#[derive(Debug)]
+pub(crate) struct Service {
+    pub(crate) state: State,
+}
+
+#[tonic::async_trait]
+impl Role for Service {
+    async fn add(&self, request: Request<AddRequest>) -> Result<Response<AddReply>, Status> {
+        let cmd = Add {
+            name: request.get_ref().name.clone(),
+            permissions: request.get_ref().permissions.clone(),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(&self.state, Action::RoleAdd, cmd.name.clone(), &result)
+            .await;
+        match result {
+            Ok(role_id) => Ok(Response::new(AddReply {
+                role_id: role_id.to_string(),
+            })),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    type ListStream = ReceiverStream<Result<RoleReply, Status>>;
+
+    async fn list(
+        &self,
+        _request: Request<ListRequest>,
+    ) -> Result<Response<Self::ListStream>, Status> {
+        let roles = List.execute(self.state.clone()).await?;
+        let (tx, rx) = mpsc::channel(8);
+
+        tokio::spawn(async move {
+            for role in roles {
+                match tx.send(Ok(role.into())).await {
+                    Ok(_) => continue,
+                    Err(e) => {
+                        tracing::error!("role list channel sending error: {:?}", e)
+                    }
+                };
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn update(&self, request: Request<UpdateRequest>) -> Result<Response<RoleReply>, Status> {
+        let cmd = Update {
+            role_id: parse_id(&request.get_ref().role_id, "role id")?,
+            name: request.get_ref().name.clone(),
+            permissions: request.get_ref().permissions.clone(),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::RoleUpdate,
+                cmd.role_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(role) => Ok(Response::new(role.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteRequest>,
+    ) -> Result<Response<DeleteReply>, Status> {
+        let cmd = Delete {
+            role_id: parse_id(&request.get_ref().role_id, "role id")?,
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::RoleDelete,
+                cmd.role_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(Response::new(DeleteReply {})),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::cmd::tenant::add::Add;
+use crate::cmd::tenant::list::List;
+use crate::cmd::Command;
+use crate::state::State;
+use avocado_proto::grpc::tenant::tenant_server::Tenant;
+use avocado_proto::grpc::tenant::{AddReply, AddRequest, ListRequest, TenantReply};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+#[derive(Debug)]
+pub(crate) struct Service {
+    pub(crate) state: State,
+}
+
+#[tonic::async_trait]
+impl Tenant for Service {
+    async fn add(&self, request: Request<AddRequest>) -> Result<Response<AddReply>, Status> {
+        let cmd = Add {
+            name: request.get_ref().name.clone(),
+        };
+        match cmd.execute(self.state.clone()).await {
+            Ok(tenant_id) => Ok(Response::new(AddReply {
+                tenant_id: tenant_id.to_string(),
+            })),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    type ListStream = ReceiverStream<Result<TenantReply, Status>>;
+
+    async fn list(
+        &self,
+        _request: Request<ListRequest>,
+    ) -> Result<Response<Self::ListStream>, Status> {
+        let tenants = List.execute(self.state.clone()).await?;
+        let (tx, rx) = mpsc::channel(8);
+
+        tokio::spawn(async move {
+            for tenant in tenants {
+                match tx.send(Ok(tenant.into())).await {
+                    Ok(_) => continue,
+                    Err(e) => {
+                        tracing::error!("tenant list channel sending error: {:?}", e)
+                    }
+                };
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
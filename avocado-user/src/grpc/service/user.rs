@@ -1,32 +1,195 @@
+use crate::cmd::jwt::impersonate::Impersonate;
 use crate::cmd::user::add::Add;
+use crate::cmd::user::assign_role::AssignRole;
+use crate::cmd::user::change_password::ChangePassword;
+use crate::cmd::user::delete::Delete;
+use crate::cmd::user::import::{Import, ImportRow as CmdImportRow, RowOutcome};
 use crate::cmd::user::list::List;
 use crate::cmd::user::login::Login;
+use crate::cmd::user::reset_password::ResetPassword;
+use crate::cmd::user::revoke_role::RevokeRole;
+use crate::cmd::user::set_status::SetStatus;
+use crate::cmd::user::setup::Setup;
+use crate::cmd::user::update::Update;
+use crate::cmd::user::update_profile::UpdateProfile;
+use crate::cmd::user::watch::Watch;
 use crate::cmd::Command;
-use crate::domain::user::Role;
-use crate::domain::user::User as DomainUser;
+use crate::domain::audit::Action;
+use crate::domain::event::{DomainEvent, OutboxEvent};
+use crate::domain::user::{Impersonator, User as DomainUser};
+use crate::event::wake;
+use crate::grpc::audit::Audit;
+use crate::grpc::{caller, parse_id, refuse_impersonated, tenant_scope};
+use crate::middleware::rate_limit::LOGIN;
 use crate::state::State;
 use avocado_base::secret::SecretString;
+use avocado_proto::grpc::user::import_users_request::Item;
 use avocado_proto::grpc::user::user_server::User;
 use avocado_proto::grpc::user::{
-    AddReply, AddRequest, ListRequest, LoginReply, LoginRequest, UserReply, WhoAmIRequest,
+    AddReply, AddRequest, AssignRoleRequest, ChangePasswordReply, ChangePasswordRequest,
+    DeleteReply, DeleteRequest, DisableRequest, EnableRequest, ExportUsersRequest,
+    ImpersonateReply, ImpersonateRequest, ImportOutcome, ImportRow, ImportRowResult,
+    ImportUsersReply, ImportUsersRequest, ListRequest, LoginReply, LoginRequest,
+    ResetPasswordReply, ResetPasswordRequest, RevokeRoleRequest, SetupRequest,
+    UpdateProfileRequest, UpdateRequest, UserEvent, UserEventType, UserReply, WatchUsersRequest,
+    WhoAmIRequest,
 };
+use serde_json::Map;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
-use tonic::{Request, Response, Status};
+use tonic::{Request, Response, Status, Streaming};
+
+/// The number of events read from the outbox at a time when watching users.
+const WATCH_BATCH_SIZE: u64 = 100;
 
 #[derive(Debug)]
 pub(crate) struct Service {
     pub(crate) state: State,
 }
 
+impl From<OutboxEvent> for UserEvent {
+    fn from(event: OutboxEvent) -> Self {
+        let mut reply = Self {
+            seq: event.seq,
+            id: event.id.to_string(),
+            occurred_at: event.occurred_at.timestamp(),
+            ..Self::default()
+        };
+        let (event_type, user_id, tenant_id) = match event.event {
+            DomainEvent::UserCreated {
+                user_id,
+                tenant_id,
+                email,
+                first_name,
+                last_name,
+            } => {
+                (reply.email, reply.first_name, reply.last_name) = (email, first_name, last_name);
+                (UserEventType::Created, user_id, tenant_id)
+            }
+            DomainEvent::UserUpdated {
+                user_id,
+                tenant_id,
+                email,
+                first_name,
+                last_name,
+            } => {
+                (reply.email, reply.first_name, reply.last_name) = (email, first_name, last_name);
+                (UserEventType::Updated, user_id, tenant_id)
+            }
+            DomainEvent::UserDeleted { user_id, tenant_id } => {
+                (UserEventType::Deleted, user_id, tenant_id)
+            }
+            DomainEvent::PasswordChanged { user_id, tenant_id } => {
+                (UserEventType::PasswordChanged, user_id, tenant_id)
+            }
+            DomainEvent::UserDisabled { user_id, tenant_id } => {
+                (UserEventType::Disabled, user_id, tenant_id)
+            }
+            DomainEvent::UserEnabled { user_id, tenant_id } => {
+                (UserEventType::Enabled, user_id, tenant_id)
+            }
+            DomainEvent::RoleAssigned {
+                user_id,
+                tenant_id,
+                role,
+            } => {
+                reply.role = role;
+                (UserEventType::RoleAssigned, user_id, tenant_id)
+            }
+            DomainEvent::RoleRevoked {
+                user_id,
+                tenant_id,
+                role,
+            } => {
+                reply.role = role;
+                (UserEventType::RoleRevoked, user_id, tenant_id)
+            }
+            DomainEvent::MemberAdded {
+                user_id,
+                tenant_id,
+                group_id,
+            } => {
+                reply.group_id = group_id.to_string();
+                (UserEventType::MemberAdded, user_id, tenant_id)
+            }
+            DomainEvent::MemberRemoved {
+                user_id,
+                tenant_id,
+                group_id,
+            } => {
+                reply.group_id = group_id.to_string();
+                (UserEventType::MemberRemoved, user_id, tenant_id)
+            }
+            // Not about a user of a tenant, so never watched
+            DomainEvent::RoleUpdated { .. } | DomainEvent::RoleDeleted { .. } => return reply,
+        };
+        reply.set_type(event_type);
+        reply.user_id = user_id.to_string();
+        reply.tenant_id = tenant_id.to_string();
+        reply
+    }
+}
+
+fn stream_users(users: Vec<DomainUser>) -> ReceiverStream<Result<UserReply, Status>> {
+    let (tx, rx) = mpsc::channel(8);
+
+    tokio::spawn(async move {
+        for user in users {
+            match tx.send(Ok(user.into())).await {
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::error!("user list channel sending error: {:?}", e)
+                }
+            };
+        }
+    });
+    ReceiverStream::new(rx)
+}
+
+impl From<ImportRow> for CmdImportRow {
+    fn from(row: ImportRow) -> Self {
+        Self {
+            email: row.email,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            password: SecretString::new(row.password),
+            roles: row.roles,
+        }
+    }
+}
+
 #[tonic::async_trait]
 impl User for Service {
     async fn login(&self, request: Request<LoginRequest>) -> Result<Response<LoginReply>, Status> {
+        self.state.config().rate_limit.login.email(
+            self.state.rate_limiter.as_ref(),
+            LOGIN,
+            &request.get_ref().email,
+        )?;
         let cmd = Login {
+            tenant: request.get_ref().tenant.clone(),
             email: request.get_ref().email.clone(),
             password: SecretString::new(request.get_ref().password.clone()),
         };
-        match cmd.execute(self.state.clone()).await {
+        let result = cmd.execute(self.state.clone()).await;
+        self.state.metrics.login(result.is_ok());
+        let audit = match &result {
+            Ok((access_token, _)) => Audit::of(&request).with_subject(&self.state, access_token),
+            Err(_) => {
+                Audit::of(&request)
+                    .with_tenant(&self.state, &request.get_ref().tenant)
+                    .await
+            }
+        };
+        audit
+            .record(
+                &self.state,
+                Action::Login,
+                request.get_ref().email.clone(),
+                &result,
+            )
+            .await;
+        match result {
             Ok((access_token, refresh_token)) => Ok(Response::new(LoginReply {
                 access_token,
                 refresh_token,
@@ -36,55 +199,485 @@ impl User for Service {
     }
 
     async fn add(&self, request: Request<AddRequest>) -> Result<Response<AddReply>, Status> {
-        return if let Ok(role) = TryInto::<Role>::try_into(request.get_ref().role) {
-            let cmd = Add {
-                email: request.get_ref().email.clone(),
-                first_name: request.get_ref().first_name.clone(),
-                last_name: request.get_ref().last_name.clone(),
-                password: SecretString::new(request.get_ref().password.clone()),
-                role,
-            };
-            match cmd.execute(self.state.clone()).await {
-                Ok(user_id) => Ok(Response::new(AddReply {
-                    user_id: user_id.to_string(),
-                })),
-                Err(e) => Err(e.into()),
-            }
-        } else {
-            Err(Status::invalid_argument("invalid user rol"))
+        let cmd = Add {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            email: request.get_ref().email.clone(),
+            first_name: request.get_ref().first_name.clone(),
+            last_name: request.get_ref().last_name.clone(),
+            password: SecretString::new(request.get_ref().password.clone()),
+            roles: request.get_ref().roles.clone(),
+            actor: Some(caller(&request)?.clone()),
         };
+        let result = cmd.execute(self.state.clone()).await;
+        let target = match &result {
+            Ok(user_id) => user_id.to_string(),
+            Err(_) => request.get_ref().email.clone(),
+        };
+        Audit::of(&request)
+            .record(&self.state, Action::UserAdd, target, &result)
+            .await;
+        match result {
+            Ok(user_id) => Ok(Response::new(AddReply {
+                user_id: user_id.to_string(),
+            })),
+            Err(e) => Err(e.into()),
+        }
     }
 
     type ListStream = ReceiverStream<Result<UserReply, Status>>;
 
     async fn list(
         &self,
-        _request: Request<ListRequest>,
+        request: Request<ListRequest>,
     ) -> Result<Response<Self::ListStream>, Status> {
-        let users = List.execute(self.state.clone()).await?;
+        let users = List {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            include_inactive: request.get_ref().include_inactive,
+        }
+        .execute(self.state.clone())
+        .await?;
+        Ok(Response::new(stream_users(users)))
+    }
+
+    async fn who_am_i(
+        &self,
+        request: Request<WhoAmIRequest>,
+    ) -> Result<Response<UserReply>, Status> {
+        if let Some(user) = request.extensions().get::<DomainUser>() {
+            let mut reply: UserReply = user.clone().into();
+            reply.actor = request
+                .extensions()
+                .get::<Impersonator>()
+                .map(|i| i.clone().into());
+            Ok(Response::new(reply))
+        } else {
+            Err(Status::unauthenticated("user not found"))
+        }
+    }
+
+    async fn assign_role(
+        &self,
+        request: Request<AssignRoleRequest>,
+    ) -> Result<Response<UserReply>, Status> {
+        refuse_impersonated(&request)?;
+        let cmd = AssignRole {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+            role: request.get_ref().role.clone(),
+            actor: Some(caller(&request)?.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::RoleAssign,
+                format!("{} {}", cmd.user_id, cmd.role),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(user) => Ok(Response::new(user.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn revoke_role(
+        &self,
+        request: Request<RevokeRoleRequest>,
+    ) -> Result<Response<UserReply>, Status> {
+        refuse_impersonated(&request)?;
+        let cmd = RevokeRole {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+            role: request.get_ref().role.clone(),
+            actor: Some(caller(&request)?.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::RoleRevoke,
+                format!("{} {}", cmd.user_id, cmd.role),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(user) => Ok(Response::new(user.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn update(&self, request: Request<UpdateRequest>) -> Result<Response<UserReply>, Status> {
+        let cmd = Update {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+            email: request.get_ref().email.clone(),
+            first_name: request.get_ref().first_name.clone(),
+            last_name: request.get_ref().last_name.clone(),
+            actor: Some(caller(&request)?.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::UserUpdate,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(user) => Ok(Response::new(user.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteRequest>,
+    ) -> Result<Response<DeleteReply>, Status> {
+        let cmd = Delete {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+            actor: Some(caller(&request)?.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::UserDelete,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(Response::new(DeleteReply {})),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn change_password(
+        &self,
+        request: Request<ChangePasswordRequest>,
+    ) -> Result<Response<ChangePasswordReply>, Status> {
+        refuse_impersonated(&request)?;
+        let user = caller(&request)?;
+        let cmd = ChangePassword {
+            tenant_id: user.tenant_id,
+            user_id: user.id,
+            old_password: SecretString::new(request.get_ref().old_password.clone()),
+            new_password: SecretString::new(request.get_ref().new_password.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::PasswordChange,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(Response::new(ChangePasswordReply {})),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn reset_password(
+        &self,
+        request: Request<ResetPasswordRequest>,
+    ) -> Result<Response<ResetPasswordReply>, Status> {
+        refuse_impersonated(&request)?;
+        let cmd = ResetPassword {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+            new_password: SecretString::new(request.get_ref().new_password.clone()),
+            actor: caller(&request)?.clone(),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::PasswordReset,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(Response::new(ResetPasswordReply {})),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    type WatchUsersStream = ReceiverStream<Result<UserEvent, Status>>;
+
+    async fn watch_users(
+        &self,
+        request: Request<WatchUsersRequest>,
+    ) -> Result<Response<Self::WatchUsersStream>, Status> {
+        let tenant_id = tenant_scope(&request, &request.get_ref().tenant_id)?;
+        let mut after = request.get_ref().after_seq;
+        let state = self.state.clone();
+        let interval = state.config().event.relay_interval();
         let (tx, rx) = mpsc::channel(8);
+        let mut published = state.event_bus.subscribe();
 
+        // Replays the events after the given position, then keeps reading the outbox for new
+        // ones until the watcher goes away
         tokio::spawn(async move {
-            for user in users {
-                match tx.send(Ok(user.into())).await {
-                    Ok(_) => continue,
+            loop {
+                let (events, next) = match (Watch {
+                    tenant_id,
+                    after,
+                    limit: WATCH_BATCH_SIZE,
+                })
+                .execute(state.clone())
+                .await
+                {
+                    Ok(result) => result,
                     Err(e) => {
-                        tracing::error!("user list channel sending error: {:?}", e)
+                        let _ = tx.send(Err(e.into())).await;
+                        return;
                     }
                 };
+                for event in events {
+                    if let Err(e) = tx.send(Ok(event.into())).await {
+                        tracing::debug!("user watch channel closed: {:?}", e);
+                        return;
+                    }
+                }
+                if next == after {
+                    tokio::select! {
+                        _ = wake(&mut published, interval) => {}
+                        _ = tx.closed() => return,
+                    }
+                }
+                after = next;
             }
         });
         Ok(Response::new(ReceiverStream::new(rx)))
     }
 
-    async fn who_am_i(
+    async fn impersonate(
         &self,
-        request: Request<WhoAmIRequest>,
+        request: Request<ImpersonateRequest>,
+    ) -> Result<Response<ImpersonateReply>, Status> {
+        refuse_impersonated(&request)?;
+        let cmd = Impersonate {
+            actor: caller(&request)?.clone(),
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::Impersonate,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(access_token) => Ok(Response::new(ImpersonateReply { access_token })),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn disable(
+        &self,
+        request: Request<DisableRequest>,
     ) -> Result<Response<UserReply>, Status> {
-        if let Some(user) = request.extensions().get::<DomainUser>() {
-            Ok(Response::new(user.clone().into()))
-        } else {
-            Err(Status::unauthenticated("user not found"))
+        let cmd = SetStatus {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+            enabled: false,
+            actor: Some(caller(&request)?.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::UserDisable,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(user) => Ok(Response::new(user.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn enable(&self, request: Request<EnableRequest>) -> Result<Response<UserReply>, Status> {
+        let cmd = SetStatus {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            user_id: parse_id(&request.get_ref().user_id, "user id")?,
+            enabled: true,
+            actor: Some(caller(&request)?.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::UserEnable,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(user) => Ok(Response::new(user.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<UserReply>, Status> {
+        let user = caller(&request)?;
+        let profile = request.get_ref().profile.clone().unwrap_or_default();
+        let attributes = match profile.attributes.as_str() {
+            "" => Map::new(),
+            json => serde_json::from_str(json)
+                .map_err(|_| Status::invalid_argument("attributes must be a JSON object"))?,
+        };
+        let cmd = UpdateProfile {
+            tenant_id: user.tenant_id,
+            user_id: user.id,
+            phone: profile.phone,
+            job_title: profile.job_title,
+            locale: profile.locale,
+            timezone: profile.timezone,
+            attributes,
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .record(
+                &self.state,
+                Action::ProfileUpdate,
+                cmd.user_id.to_string(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(user) => Ok(Response::new(user.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn import_users(
+        &self,
+        mut request: Request<Streaming<ImportUsersRequest>>,
+    ) -> Result<Response<ImportUsersReply>, Status> {
+        let options = match request.get_mut().message().await?.and_then(|r| r.item) {
+            Some(Item::Options(options)) => options,
+            _ => {
+                return Err(Status::invalid_argument(
+                    "the first message needs to carry the import options",
+                ))
+            }
+        };
+        let tenant_id = tenant_scope(&request, &options.tenant_id)?;
+        // The rows are held until the stream ends, the size of each message is capped by the server
+        let max_rows = self.state.config().import.max_rows;
+        let mut rows = vec![];
+        while let Some(message) = request.get_mut().message().await? {
+            match message.item {
+                Some(Item::Row(_)) if rows.len() == max_rows => {
+                    return Err(Status::resource_exhausted(format!(
+                        "an import takes at most {} rows",
+                        max_rows
+                    )))
+                }
+                Some(Item::Row(row)) => rows.push(CmdImportRow::from(row)),
+                _ => return Err(Status::invalid_argument("expected an import row")),
+            }
+        }
+
+        let emails: Vec<String> = rows.iter().map(|r| r.email.clone()).collect();
+        let cmd = Import {
+            tenant_id,
+            rows,
+            dry_run: options.dry_run,
+            upsert: options.upsert,
+            actor: caller(&request)?.clone(),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        let target = format!(
+            "{} rows{}",
+            emails.len(),
+            if cmd.dry_run { " (dry run)" } else { "" }
+        );
+        Audit::of(&request)
+            .record(&self.state, Action::UserImport, target, &result)
+            .await;
+
+        let mut reply = ImportUsersReply::default();
+        for (i, (outcome, email)) in result?.into_iter().zip(emails).enumerate() {
+            let mut row = ImportRowResult {
+                row: i as u32 + 1,
+                email,
+                ..Default::default()
+            };
+            match outcome {
+                RowOutcome::Created(user_id) => {
+                    row.set_outcome(ImportOutcome::Created);
+                    row.user_id = user_id.map(|id| id.to_string()).unwrap_or_default();
+                    reply.created += 1;
+                }
+                RowOutcome::Updated(user_id) => {
+                    row.set_outcome(ImportOutcome::Updated);
+                    row.user_id = user_id.to_string();
+                    reply.updated += 1;
+                }
+                RowOutcome::Failed(error) => {
+                    row.error = error;
+                    reply.failed += 1;
+                }
+            }
+            reply.results.push(row);
+        }
+        Ok(Response::new(reply))
+    }
+
+    type ExportUsersStream = ReceiverStream<Result<UserReply, Status>>;
+
+    async fn export_users(
+        &self,
+        request: Request<ExportUsersRequest>,
+    ) -> Result<Response<Self::ExportUsersStream>, Status> {
+        let users = List {
+            tenant_id: tenant_scope(&request, &request.get_ref().tenant_id)?,
+            include_inactive: request.get_ref().include_inactive,
+        }
+        .execute(self.state.clone())
+        .await?;
+        Ok(Response::new(stream_users(users)))
+    }
+
+    async fn setup(&self, request: Request<SetupRequest>) -> Result<Response<AddReply>, Status> {
+        let cmd = Setup {
+            setup_token: SecretString::new(request.get_ref().setup_token.clone()),
+            email: request.get_ref().email.clone(),
+            first_name: request.get_ref().first_name.clone(),
+            last_name: request.get_ref().last_name.clone(),
+            password: SecretString::new(request.get_ref().password.clone()),
+        };
+        let result = cmd.execute(self.state.clone()).await;
+        Audit::of(&request)
+            .with_tenant(&self.state, "")
+            .await
+            .record(
+                &self.state,
+                Action::Setup,
+                request.get_ref().email.clone(),
+                &result,
+            )
+            .await;
+        match result {
+            Ok(user_id) => Ok(Response::new(AddReply {
+                user_id: user_id.to_string(),
+            })),
+            Err(e) => Err(e.into()),
         }
     }
 }
@@ -1,43 +1,188 @@
-use crate::db::sqlite::user::Store as UserStore;
+use crate::cfg::Config;
+use crate::db::sqlite::connect_to;
+use crate::domain::health::Health;
+use crate::event::eviction::Eviction;
+use crate::event::relay::Relay;
+use crate::grpc::service::audit::Service as AuditService;
+use crate::grpc::service::group::Service as GroupService;
+use crate::grpc::service::health::Service as HealthService;
 use crate::grpc::service::jwt::Service as JwtService;
+use crate::grpc::service::reflection::Service as ReflectionService;
+use crate::grpc::service::role::Service as RoleService;
+use crate::grpc::service::tenant::Service as TenantService;
 use crate::grpc::service::user::Service as UserService;
 use crate::middleware::auth::AuthLayer;
+use crate::middleware::rate_limit::RateLimitLayer;
 use crate::state::State;
+use avocado_base::cfg::{watch, ConfigError};
+use avocado_base::log::LogConfig;
+use avocado_base::shutdown::{self, drain};
+use avocado_base::trace::TraceLayer;
+use avocado_proto::grpc::audit::audit_server::AuditServer;
+use avocado_proto::grpc::group::group_server::GroupServer;
+use avocado_proto::grpc::health::health_server::HealthServer;
 use avocado_proto::grpc::jwt::jwt_server::JwtServer;
+use avocado_proto::grpc::reflection::server_reflection_server::ServerReflectionServer;
+use avocado_proto::grpc::role::role_server::RoleServer;
+use avocado_proto::grpc::tenant::tenant_server::TenantServer;
 use avocado_proto::grpc::user::user_server::UserServer;
+use avocado_proto::grpc::FILE_DESCRIPTOR_SET;
 use std::future::Future;
-use std::net::SocketAddr;
 use std::time::Duration;
+use tokio::sync::oneshot;
+use tonic::server::NamedService;
 use tonic::transport::Server;
 
+pub mod admin;
 mod cfg;
 mod cmd;
 mod db;
 mod domain;
+mod event;
 pub mod grpc;
+mod metrics;
 mod middleware;
 mod state;
 
+/// How often the configuration file is checked for changes, SIGHUP reloads it right away.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reads the `log` section, which is needed before the subscriber is installed and the server
+/// starts.
+pub fn log_config() -> Result<LogConfig, ConfigError> {
+    LogConfig::load(&Config::loader())
+}
+
+/// Builds the server, which shuts down gracefully on SIGTERM or Ctrl-C.
 pub async fn run(
-    address: SocketAddr,
 ) -> Result<impl Future<Output = Result<(), tonic::transport::Error>>, Box<dyn std::error::Error>> {
-    let state = State::new(UserStore::new().await);
+    run_until(shutdown::signal()).await
+}
+
+/// Builds the server, which stops accepting connections once `shutdown` resolves. The requests in
+/// flight get until `server.drain_timeout_ms` to finish, then the pending events are published
+/// and the database is closed.
+pub async fn run_until(
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<impl Future<Output = Result<(), tonic::transport::Error>>, Box<dyn std::error::Error>> {
+    let loader = Config::loader();
+    let (config, settings) = loader.load::<Config>()?;
+    let pool = connect_to(config.database.url.as_deref()).await?;
+    let state = State::with_config(pool.clone(), config).await;
+    state.metrics.service.observe_pool(pool.clone());
+    let exporting = {
+        let (address, metrics) = (
+            state.config().metrics.address,
+            state.metrics.service.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = avocado_base::metrics::serve(address, metrics).await {
+                tracing::error!("failed to serve the metrics on {address}, {e}");
+            }
+        })
+    };
+    let reloaded = state.clone();
+    tokio::spawn(watch(
+        loader,
+        settings,
+        CONFIG_POLL_INTERVAL,
+        move |config| reloaded.reload(config),
+    ));
+    let relay = Relay {
+        outbox_store: state.outbox_store.clone(),
+        event_bus: state.event_bus.clone(),
+        interval: state.config().event.relay_interval(),
+        batch_size: state.config().event.relay_batch_size,
+    };
+    let relaying = tokio::spawn(relay.clone().run());
+    let evicting = tokio::spawn(
+        Eviction {
+            outbox_store: state.outbox_store.clone(),
+            principals: state.principals.clone(),
+            published: state.event_bus.subscribe(),
+            interval: state.config().event.relay_interval(),
+            batch_size: state.config().event.relay_batch_size,
+        }
+        .run(),
+    );
     let user_service = UserService {
         state: state.clone(),
     };
     let jwt_service = JwtService {
         state: state.clone(),
     };
+    let role_service = RoleService {
+        state: state.clone(),
+    };
+    let group_service = GroupService {
+        state: state.clone(),
+    };
+    let tenant_service = TenantService {
+        state: state.clone(),
+    };
+    let audit_service = AuditService {
+        state: state.clone(),
+    };
+    let health_service = HealthService {
+        state: state.clone(),
+    };
+    let reflection_service = ReflectionService::new(FILE_DESCRIPTOR_SET)?;
 
+    let config = state.config();
+    let health = state.health.clone();
     let layer = tower::ServiceBuilder::new()
+        .layer(TraceLayer)
+        .layer(state.metrics.service.layer(metrics::method))
         .timeout(Duration::from_secs(300))
-        .layer(AuthLayer { state })
+        .layer(RateLimitLayer::by_client(state.clone()))
+        .layer(AuthLayer {
+            state: state.clone(),
+        })
+        .layer(RateLimitLayer::by_user(state))
         .into_inner();
 
-    let server = Server::builder()
+    let (stopping_tx, stopping_rx) = oneshot::channel();
+    let stop = {
+        let health = health.clone();
+        async move {
+            shutdown.await;
+            tracing::info!("shutting down, draining the requests in flight");
+            health.stop_serving();
+            let _ = stopping_tx.send(());
+        }
+    };
+
+    let mut builder = Server::builder();
+    if let Some(tls) = &config.server.tls {
+        builder = builder.tls_config(tls.load()?)?;
+    }
+    let server = builder
         .layer(layer)
-        .add_service(UserServer::new(user_service))
-        .add_service(JwtServer::new(jwt_service))
-        .serve(address);
-    Ok(server)
+        .add_service(serving(&health, UserServer::new(user_service)))
+        .add_service(serving(&health, JwtServer::new(jwt_service)))
+        .add_service(serving(&health, RoleServer::new(role_service)))
+        .add_service(serving(&health, GroupServer::new(group_service)))
+        .add_service(serving(&health, TenantServer::new(tenant_service)))
+        .add_service(serving(&health, AuditServer::new(audit_service)))
+        .add_service(HealthServer::new(health_service))
+        .add_service(ServerReflectionServer::new(reflection_service))
+        .serve_with_shutdown(config.server.address, stop);
+    health.set_serving("", true);
+
+    Ok(async move {
+        drain(server, stopping_rx, config.server.drain_timeout()).await?;
+        relaying.abort();
+        evicting.abort();
+        exporting.abort();
+        relay.flush().await;
+        pool.close().await;
+        tracing::info!("shut down");
+        Ok(())
+    })
+}
+
+/// Registers the service as serving, for health checks to report on it.
+fn serving<S: NamedService>(health: &Health, service: S) -> S {
+    health.set_serving(S::NAME, true);
+    service
 }
@@ -1,11 +1,27 @@
-use avocado_base::log::init_subscriber;
-use avocado_user::run;
+use avocado_base::log::Builder;
+use avocado_user::{log_config, run};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    init_subscriber();
+    let _log = match log_config().and_then(|log| {
+        Builder::new("avocado-user", log)
+            .with_telemetry(env!("CARGO_PKG_VERSION"))
+            .init()
+    }) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("unable to start: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let address = "[::1]:50051".parse()?;
-    run(address).await?.await?;
+    let server = match run().await {
+        Ok(server) => server,
+        Err(e) => {
+            tracing::error!("unable to start: {}", e);
+            std::process::exit(1);
+        }
+    };
+    server.await?;
     Ok(())
 }
@@ -0,0 +1,126 @@
+use avocado_base::metrics::Metrics as ServiceMetrics;
+use avocado_proto::grpc::FILE_DESCRIPTOR_SET;
+use hyper::http::request::Parts;
+use once_cell::sync::Lazy;
+use prometheus::{IntCounterVec, Opts};
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use std::collections::HashSet;
+
+/// The paths of the methods served, the only ones labelling the requests.
+static METHODS: Lazy<HashSet<String>> = Lazy::new(|| {
+    let descriptors =
+        FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).expect("invalid file descriptor set");
+    let mut methods = HashSet::new();
+    for file in &descriptors.file {
+        for service in &file.service {
+            for method in &service.method {
+                methods.insert(format!(
+                    "/{}.{}/{}",
+                    file.package(),
+                    service.name(),
+                    method.name()
+                ));
+            }
+        }
+    }
+    methods
+});
+
+/// Labels the requests by the method called, when it is one served.
+pub(crate) fn method(parts: &Parts) -> Option<&str> {
+    let path = parts.uri.path();
+    METHODS.contains(path).then_some(path)
+}
+
+/// The metrics of the user service, its traffic is recorded by the layer of the server.
+#[derive(Debug, Clone)]
+pub(crate) struct Metrics {
+    pub(crate) service: ServiceMetrics,
+    logins: IntCounterVec,
+    token_refreshes: IntCounterVec,
+    principal_lookups: IntCounterVec,
+    auth_refusals: IntCounterVec,
+}
+
+fn result(success: bool) -> &'static str {
+    match success {
+        true => "success",
+        false => "failure",
+    }
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        let service = ServiceMetrics::new("user");
+        let counter = |name: &str, help: &str| {
+            service.register(IntCounterVec::new(Opts::new(name, help), &["result"]).unwrap())
+        };
+        Metrics {
+            logins: counter("logins_total", "Logins by result"),
+            token_refreshes: counter("token_refreshes_total", "Token refreshes by result"),
+            principal_lookups: counter(
+                "principal_cache_lookups_total",
+                "Lookups of the users of tokens in the principal cache by result",
+            ),
+            auth_refusals: service.register(
+                IntCounterVec::new(
+                    Opts::new(
+                        "auth_refusals_total",
+                        "Calls refused by the auth layer by reason",
+                    ),
+                    &["reason"],
+                )
+                .unwrap(),
+            ),
+            service,
+        }
+    }
+
+    pub(crate) fn login(&self, success: bool) {
+        self.logins.with_label_values(&[result(success)]).inc();
+    }
+
+    pub(crate) fn token_refresh(&self, success: bool) {
+        self.token_refreshes
+            .with_label_values(&[result(success)])
+            .inc();
+    }
+
+    pub(crate) fn principal_lookup(&self, hit: bool) {
+        let result = match hit {
+            true => "hit",
+            false => "miss",
+        };
+        self.principal_lookups.with_label_values(&[result]).inc();
+    }
+
+    /// Counts a refused call, by a reason out of a fixed set.
+    pub(crate) fn auth_refusal(&self, reason: &'static str) {
+        self.auth_refusals.with_label_values(&[reason]).inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::method;
+    use hyper::Request;
+
+    #[test]
+    fn test_method() {
+        let label = |path: &str| {
+            let (parts, _) = Request::get(path).body(()).unwrap().into_parts();
+            method(&parts).map(str::to_string)
+        };
+        assert_eq!(
+            label("/user.User/Login").as_deref(),
+            Some("/user.User/Login")
+        );
+        assert_eq!(
+            label("/grpc.health.v1.Health/Check").as_deref(),
+            Some("/grpc.health.v1.Health/Check")
+        );
+        assert_eq!(label("/user.User/Random"), None);
+        assert_eq!(label("/random"), None);
+    }
+}
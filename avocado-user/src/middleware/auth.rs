@@ -1,15 +1,22 @@
 use crate::cmd::jwt::who::Who;
 use crate::cmd::Command;
+use crate::domain::user::User;
+use crate::grpc::audit::Audit;
+use crate::middleware::client_ip;
+use crate::middleware::policy::Policy;
 use crate::state::State;
+use avocado_base::auth::{bearer_token, Challenge, WWW_AUTHENTICATE};
 use futures_util::future::BoxFuture;
 use hyper::Response;
-use hyper::StatusCode;
 use std::task::{Context, Poll};
-use tonic::body::{empty_body, BoxBody};
+use tonic::body::BoxBody;
 use tonic::transport::Body;
-use tonic::Code;
+use tonic::Status;
 use tower::{Layer, Service};
 
+/// The route the audited denials are counted under.
+const AUDITED_DENIALS: &str = "audited denials";
+
 #[derive(Debug, Clone)]
 pub(crate) struct AuthLayer {
     pub(crate) state: State,
@@ -54,38 +61,80 @@ where
         let state = self.state.clone();
 
         Box::pin(async move {
-            let path = req.uri().path();
-            if path != "/user.User/Login" && path != "/jwt.Jwt/Verify" {
-                match req.headers().get("auth").and_then(|t| t.to_str().ok()) {
-                    Some(token) => {
-                        let user = Who {
-                            token: token.to_string(),
-                        }
-                        .execute(state)
-                        .await;
-                        match user {
-                            Ok(u) => {
-                                req.extensions_mut().insert(u);
-                                let response = inner.call(req).await?;
-                                Ok(response)
-                            }
-                            Err(_) => Ok(unauthenticated_response()),
-                        }
+            let policy = Policy::of(req.uri().path());
+            if !policy.needs_authentication() {
+                return inner.call(req).await;
+            }
+            let token = match bearer_token(req.headers()) {
+                Ok(token) => token.to_string(),
+                Err(challenge) => return Ok(refuse(&state, &req, challenge).await),
+            };
+            match (Who { token }).execute(state.clone()).await {
+                Ok((u, impersonator)) => {
+                    if let Err(status) = policy.check(&u) {
+                        state.metrics.auth_refusal("permission_denied");
+                        // The real user is accountable for what they try with an impersonated token
+                        let actor = impersonator.as_ref().map_or(&u, |i| &i.0);
+                        audit_denial(&state, &req, Some(actor), status.message()).await;
+                        return Ok(status.to_http());
+                    }
+                    req.extensions_mut().insert(u);
+                    if let Some(impersonator) = impersonator {
+                        req.extensions_mut().insert(impersonator);
                     }
-                    None => Ok(unauthenticated_response()),
+                    inner.call(req).await
                 }
-            } else {
-                let response = inner.call(req).await?;
-                Ok(response)
+                Err(_) => Ok(refuse(&state, &req, Challenge::InvalidToken).await),
             }
         })
     }
 }
 
-fn unauthenticated_response() -> Response<BoxBody> {
-    Response::builder()
-        .status(StatusCode::UNAUTHORIZED)
-        .header("grpc-status", (Code::Unauthenticated as u8).to_string())
-        .body(empty_body())
-        .unwrap()
+/// Refuses a request without a valid token. Only invalid tokens are audited, requests without
+/// any are too cheap to send to be written down each.
+async fn refuse(
+    state: &State,
+    req: &hyper::Request<Body>,
+    challenge: Challenge,
+) -> Response<BoxBody> {
+    let reason = match challenge {
+        Challenge::Missing => "missing_token",
+        Challenge::InvalidRequest => "invalid_request",
+        Challenge::InvalidToken => "invalid_token",
+    };
+    state.metrics.auth_refusal(reason);
+    if challenge == Challenge::InvalidToken {
+        audit_denial(state, req, None, challenge.description()).await;
+    }
+    unauthenticated_response(challenge)
+}
+
+/// Audits a refused request, unless its client used up its `rate_limit.audited_denials`.
+async fn audit_denial(
+    state: &State,
+    req: &hyper::Request<Body>,
+    actor: Option<&User>,
+    reason: &str,
+) {
+    let limits = &state.config().rate_limit.audited_denials;
+    if let Some(ip) = client_ip(req) {
+        if limits
+            .client(state.rate_limiter.as_ref(), AUDITED_DENIALS, ip)
+            .is_err()
+        {
+            return;
+        }
+    }
+    Audit::of_call(req, actor)
+        .deny(state, req.uri().path(), reason)
+        .await
+}
+
+/// Refuses the request with a `www-authenticate` challenge saying how to authenticate.
+fn unauthenticated_response(challenge: Challenge) -> Response<BoxBody> {
+    let mut status = Status::unauthenticated(challenge.description());
+    if let Ok(value) = challenge.www_authenticate().parse() {
+        status.metadata_mut().insert(WWW_AUTHENTICATE, value);
+    }
+    status.to_http()
 }
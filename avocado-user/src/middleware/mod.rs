@@ -1 +1,19 @@
+use hyper::Request;
+use std::net::IpAddr;
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tonic::transport::Body;
+
 pub(crate) mod auth;
+pub(crate) mod policy;
+pub(crate) mod rate_limit;
+
+/// The address the request came from, over plain TCP or TLS.
+pub(crate) fn client_ip(req: &Request<Body>) -> Option<IpAddr> {
+    let extensions = req.extensions();
+    let tcp = extensions.get::<TcpConnectInfo>().or_else(|| {
+        extensions
+            .get::<TlsConnectInfo<TcpConnectInfo>>()
+            .map(|tls| tls.get_ref())
+    })?;
+    tcp.remote_addr().map(|address| address.ip())
+}
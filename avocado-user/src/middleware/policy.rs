@@ -0,0 +1,239 @@
+// The policies fail with the `Status` the service methods would have returned
+#![allow(clippy::result_large_err)]
+
+use crate::domain::role::permission;
+use crate::domain::user::User;
+use tonic::Status;
+
+/// What a call requires of the caller, checked by the auth middleware before the handler runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Policy {
+    /// The call authenticates the caller itself, or needs no authentication.
+    Public,
+    /// Any authenticated user may make the call, the handler decides what they may see or change.
+    Authenticated,
+    /// The authenticated user must hold the permission.
+    Permission(&'static str),
+    /// The authenticated user must be an admin of the platform tenant.
+    PlatformAdmin,
+    /// Nobody may make the call, for the methods missing from the registry.
+    Denied,
+}
+
+use Policy::{Authenticated, Denied, Permission, PlatformAdmin, Public};
+
+/// The policies by method path, or by service path for services whose methods all share one.
+const POLICIES: &[(&str, Policy)] = &[
+    ("/user.User/Login", Public),
+    ("/user.User/Setup", Public),
+    ("/user.User/Add", Permission(permission::USER_WRITE)),
+    ("/user.User/List", Permission(permission::USER_READ)),
+    ("/user.User/WhoAmI", Authenticated),
+    ("/user.User/AssignRole", Permission(permission::ROLE_WRITE)),
+    ("/user.User/RevokeRole", Permission(permission::ROLE_WRITE)),
+    ("/user.User/Update", Permission(permission::USER_WRITE)),
+    ("/user.User/Delete", Permission(permission::USER_WRITE)),
+    ("/user.User/ChangePassword", Authenticated),
+    (
+        "/user.User/ResetPassword",
+        Permission(permission::USER_WRITE),
+    ),
+    ("/user.User/WatchUsers", Permission(permission::USER_READ)),
+    (
+        "/user.User/Impersonate",
+        Permission(permission::USER_IMPERSONATE),
+    ),
+    ("/user.User/Disable", Permission(permission::USER_WRITE)),
+    ("/user.User/Enable", Permission(permission::USER_WRITE)),
+    ("/user.User/UpdateProfile", Authenticated),
+    ("/user.User/ImportUsers", Permission(permission::USER_WRITE)),
+    ("/user.User/ExportUsers", Permission(permission::USER_READ)),
+    ("/jwt.Jwt/Verify", Public),
+    ("/jwt.Jwt/Refresh", Authenticated),
+    ("/jwt.Jwt/RotateKey", PlatformAdmin),
+    ("/role.Role/Add", PlatformAdmin),
+    ("/role.Role/List", Permission(permission::ROLE_READ)),
+    ("/role.Role/Update", PlatformAdmin),
+    ("/role.Role/Delete", PlatformAdmin),
+    ("/group.Group/Add", Permission(permission::GROUP_WRITE)),
+    ("/group.Group/List", Permission(permission::GROUP_READ)),
+    ("/group.Group/Update", Permission(permission::GROUP_WRITE)),
+    ("/group.Group/Delete", Permission(permission::GROUP_WRITE)),
+    (
+        "/group.Group/AddMember",
+        Permission(permission::GROUP_WRITE),
+    ),
+    (
+        "/group.Group/RemoveMember",
+        Permission(permission::GROUP_WRITE),
+    ),
+    (
+        "/group.Group/ListMembers",
+        Permission(permission::GROUP_READ),
+    ),
+    // Everyone may list their own groups, the handler checks the permission for other users
+    ("/group.Group/ListUserGroups", Authenticated),
+    ("/tenant.Tenant/Add", PlatformAdmin),
+    ("/tenant.Tenant/List", PlatformAdmin),
+    (
+        "/audit.Audit/ListAuditEvents",
+        Permission(permission::AUDIT_READ),
+    ),
+    // Services for probes and tools, which only describe the server
+    ("/grpc.health.v1.Health/", Public),
+    ("/grpc.reflection.v1alpha.ServerReflection/", Public),
+];
+
+impl Policy {
+    /// The policy of the method at `path`. Methods missing from the registry are denied, so a
+    /// method added without a policy fails closed.
+    pub(crate) fn of(path: &str) -> Self {
+        Self::declared(path).unwrap_or(Denied)
+    }
+
+    fn declared(path: &str) -> Option<Self> {
+        let service = &path[..=path.rfind('/')?];
+        POLICIES
+            .iter()
+            .find(|(p, _)| *p == path || *p == service)
+            .map(|(_, policy)| *policy)
+    }
+
+    pub(crate) fn needs_authentication(&self) -> bool {
+        *self != Public
+    }
+
+    /// Ensures the authenticated user satisfies the policy.
+    pub(crate) fn check(&self, user: &User) -> Result<(), Status> {
+        match self {
+            Public | Authenticated => Ok(()),
+            Permission(permission) if user.has_permission(permission) => Ok(()),
+            Permission(permission) => Err(Status::permission_denied(format!(
+                "permission {} is required",
+                permission
+            ))),
+            PlatformAdmin if user.is_platform_admin() => Ok(()),
+            PlatformAdmin => Err(Status::permission_denied("platform admin is required")),
+            Denied => Err(Status::permission_denied("the method has no policy")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::role::{permission, Role};
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::{Profile, User, UserStatus};
+    use crate::middleware::policy::{Policy, POLICIES};
+    use avocado_proto::grpc::FILE_DESCRIPTOR_SET;
+    use prost::Message;
+    use prost_types::FileDescriptorSet;
+    use tonic::Code;
+    use ulid::Ulid;
+
+    fn user(tenant_id: Ulid, permissions: &[&str]) -> User {
+        User {
+            id: Ulid::new(),
+            tenant_id,
+            email: "someone@avocado.com".to_string(),
+            first_name: String::new(),
+            last_name: String::new(),
+            password_hash: String::new(),
+            roles: vec![Role {
+                id: Ulid::new(),
+                name: "custom".to_string(),
+                permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            }],
+            status: UserStatus::Active,
+            profile: Profile::default(),
+        }
+    }
+
+    #[test]
+    fn test_every_method_is_declared() {
+        let descriptors = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap();
+        for file in descriptors.file {
+            for service in &file.service {
+                for method in &service.method {
+                    let path = format!("/{}.{}/{}", file.package(), service.name(), method.name());
+                    assert!(Policy::declared(&path).is_some(), "{} has no policy", path);
+                }
+            }
+        }
+        for (path, _) in POLICIES {
+            assert_eq!(
+                POLICIES.iter().filter(|(p, _)| p == path).count(),
+                1,
+                "{} is declared twice",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let tenant_id = Ulid::new();
+        let nobody = user(tenant_id, &[]);
+        let reader = user(tenant_id, &[permission::USER_READ]);
+        let tenant_admin = user(tenant_id, &[permission::ALL]);
+        let platform_admin = user(PLATFORM_ID, &[permission::ALL]);
+
+        let cases = [
+            ("/user.User/Login", &nobody, false, None),
+            ("/jwt.Jwt/Verify", &nobody, false, None),
+            ("/grpc.health.v1.Health/Check", &nobody, false, None),
+            ("/grpc.health.v1.Health/Watch", &nobody, false, None),
+            ("/user.User/WhoAmI", &nobody, true, None),
+            (
+                "/user.User/Unknown",
+                &nobody,
+                true,
+                Some(Code::PermissionDenied),
+            ),
+            (
+                "/unknown.Unknown/Call",
+                &platform_admin,
+                true,
+                Some(Code::PermissionDenied),
+            ),
+            (
+                "/user.User/List",
+                &nobody,
+                true,
+                Some(Code::PermissionDenied),
+            ),
+            ("/user.User/List", &reader, true, None),
+            (
+                "/user.User/Add",
+                &reader,
+                true,
+                Some(Code::PermissionDenied),
+            ),
+            ("/user.User/Add", &tenant_admin, true, None),
+            (
+                "/tenant.Tenant/List",
+                &tenant_admin,
+                true,
+                Some(Code::PermissionDenied),
+            ),
+            ("/tenant.Tenant/List", &platform_admin, true, None),
+            (
+                "/role.Role/Add",
+                &reader,
+                true,
+                Some(Code::PermissionDenied),
+            ),
+            ("/role.Role/Add", &platform_admin, true, None),
+        ];
+        for (path, user, authenticated, denied) in cases {
+            let policy = Policy::of(path);
+            assert_eq!(policy.needs_authentication(), authenticated, "{}", path);
+            assert_eq!(
+                policy.check(user).err().map(|e| e.code()),
+                denied,
+                "{}",
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::cfg::RateLimit;
+use crate::domain::user::User;
+use crate::middleware::client_ip;
+use crate::state::State;
+use avocado_base::rate_limit::{Limited, Limits};
+use futures_util::future::BoxFuture;
+use hyper::Request;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::transport::Body;
+use tonic::Status;
+use tower::{Layer, Service};
+
+pub(crate) const LOGIN: &str = "/user.User/Login";
+pub(crate) const REFRESH: &str = "/jwt.Jwt/Refresh";
+
+type Route = (&'static str, fn(&RateLimit) -> &Limits);
+
+/// The limited calls and their limits.
+const ROUTES: [Route; 2] = [
+    (LOGIN, |rate_limit| &rate_limit.login),
+    (REFRESH, |rate_limit| &rate_limit.refresh),
+];
+
+/// What the calls are counted by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum By {
+    Client,
+    User,
+}
+
+/// Limits the calls by client, before the auth layer so the calls it refuses are counted too, or
+/// by authenticated user after it. The emails signing in are limited by the login itself.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimitLayer {
+    state: State,
+    by: By,
+}
+
+impl RateLimitLayer {
+    pub(crate) fn by_client(state: State) -> Self {
+        RateLimitLayer {
+            state,
+            by: By::Client,
+        }
+    }
+
+    pub(crate) fn by_user(state: State) -> Self {
+        RateLimitLayer {
+            state,
+            by: By::User,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimited<S> {
+    state: State,
+    by: By,
+    inner: S,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimited<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimited {
+            state: self.state.clone(),
+            by: self.by,
+            inner,
+        }
+    }
+}
+
+impl<S> Service<Request<Body>> for RateLimited<S>
+where
+    S: Service<Request<Body>, Response = hyper::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // See the auth layer for why the ready service is taken
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        match check(&self.state, self.by, &req) {
+            Ok(()) => Box::pin(inner.call(req)),
+            Err(limited) => {
+                tracing::warn!("refusing {}, {}", req.uri().path(), limited);
+                let response = Status::from(limited).to_http();
+                Box::pin(async move { Ok(response) })
+            }
+        }
+    }
+}
+
+fn check(state: &State, by: By, req: &Request<Body>) -> Result<(), Limited> {
+    let path = req.uri().path();
+    let config = state.config();
+    let limits = match ROUTES.iter().find(|(route, _)| *route == path) {
+        Some((_, limits)) => limits(&config.rate_limit),
+        None => return Ok(()),
+    };
+    match by {
+        By::Client => match client_ip(req) {
+            Some(ip) => limits.client(state.rate_limiter.as_ref(), path, ip),
+            None => Ok(()),
+        },
+        By::User => match req.extensions().get::<User>() {
+            Some(user) => limits.user(state.rate_limiter.as_ref(), path, &user.id.to_string()),
+            None => Ok(()),
+        },
+    }
+}
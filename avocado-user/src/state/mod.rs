@@ -1,18 +1,130 @@
 use crate::cfg::Config;
-use crate::db::UserStore;
-use std::sync::Arc;
+use crate::cmd::user::bootstrap::Bootstrap;
+use crate::cmd::Command;
+use crate::db::sqlite::audit::Store as SqliteAuditStore;
+use crate::db::sqlite::group::Store as SqliteGroupStore;
+use crate::db::sqlite::health::Store as SqliteHealthStore;
+use crate::db::sqlite::outbox::Store as SqliteOutboxStore;
+use crate::db::sqlite::role::Store as SqliteRoleStore;
+use crate::db::sqlite::tenant::Store as SqliteTenantStore;
+use crate::db::sqlite::user::Store as SqliteUserStore;
+use crate::db::{
+    AuditStore, GroupStore, HealthStore, OutboxStore, RoleStore, TenantStore, UserStore,
+};
+use crate::domain::health::Health;
+use crate::domain::jwt::{KeyRing, SigningKey};
+use crate::event::{bus, EventBus};
+use crate::metrics::Metrics;
+use crate::state::principals::{Forgetting, Principals};
+use avocado_base::cfg::Shared;
+use avocado_base::rate_limit::{Backend, Memory};
+use avocado_base::secret::SecretString;
+use chrono::Utc;
+use sqlx::{Pool, Sqlite};
+use std::sync::{Arc, Mutex, RwLock};
 
 #[derive(Clone, Debug)]
 pub(crate) struct State {
     pub(crate) user_store: Arc<dyn UserStore>,
-    pub(crate) config: Arc<Config>,
+    pub(crate) role_store: Arc<dyn RoleStore>,
+    pub(crate) group_store: Arc<dyn GroupStore>,
+    pub(crate) tenant_store: Arc<dyn TenantStore>,
+    pub(crate) audit_store: Arc<dyn AuditStore>,
+    pub(crate) outbox_store: Arc<dyn OutboxStore>,
+    pub(crate) health_store: Arc<dyn HealthStore>,
+    pub(crate) event_bus: Arc<dyn EventBus>,
+    /// Forgets the principals of the users, roles and memberships changed through the stores
+    /// above, and of those the outbox brings from other processes
+    pub(crate) principals: Arc<Principals>,
+    /// The services register themselves as serving when the server starts
+    pub(crate) health: Arc<Health>,
+    pub(crate) metrics: Metrics,
+    /// The buckets of the rate limits, the limits themselves are configured
+    pub(crate) rate_limiter: Arc<dyn Backend>,
+    /// Swapped as a whole when the configuration is reloaded
+    pub(crate) config: Arc<Shared<Config>>,
+    /// Starts with the configured key pair, rotated at runtime
+    pub(crate) keys: Arc<RwLock<KeyRing>>,
+    /// The one-time token to create the first admin with, set by the bootstrap when needed
+    pub(crate) setup_token: Arc<Mutex<Option<SecretString>>>,
 }
 
 impl State {
-    pub(crate) fn new(user_store: impl UserStore + 'static) -> Self {
-        State {
-            user_store: Arc::new(user_store),
-            config: Arc::new(Config::new()),
+    #[cfg(test)]
+    pub(crate) async fn new(pool: Pool<Sqlite>) -> Self {
+        Self::with_config(pool, Config::load().expect("invalid configuration")).await
+    }
+
+    pub(crate) async fn with_config(pool: Pool<Sqlite>, config: Config) -> Self {
+        // The keys are checked when the configuration is loaded
+        let key = SigningKey::from_pem(config.rsa.private_key(), config.rsa.public_key())
+            .expect("invalid rsa keys");
+        let principals = Arc::new(Principals::default());
+        let state = State {
+            user_store: Arc::new(Forgetting {
+                inner: Arc::new(SqliteUserStore::new(pool.clone())) as Arc<dyn UserStore>,
+                principals: principals.clone(),
+            }),
+            role_store: Arc::new(Forgetting {
+                inner: Arc::new(SqliteRoleStore::new(pool.clone())) as Arc<dyn RoleStore>,
+                principals: principals.clone(),
+            }),
+            group_store: Arc::new(Forgetting {
+                inner: Arc::new(SqliteGroupStore::new(pool.clone())) as Arc<dyn GroupStore>,
+                principals: principals.clone(),
+            }),
+            tenant_store: Arc::new(SqliteTenantStore::new(pool.clone())),
+            audit_store: Arc::new(SqliteAuditStore::new(pool.clone())),
+            outbox_store: Arc::new(SqliteOutboxStore::new(pool.clone())),
+            health_store: Arc::new(SqliteHealthStore::new(pool)),
+            event_bus: bus(&config.event),
+            principals,
+            health: Arc::new(Health::default()),
+            metrics: Metrics::new(),
+            rate_limiter: Arc::new(Memory::default()),
+            config: Arc::new(Shared::new(config)),
+            keys: Arc::new(RwLock::new(KeyRing::new(key))),
+            setup_token: Arc::new(Mutex::new(None)),
+        };
+        let config = state.config();
+        Bootstrap {
+            email: config.bootstrap.admin_email.clone(),
+            password: config.bootstrap.admin_password.clone(),
+        }
+        .execute(state.clone())
+        .await
+        .expect("unable to bootstrap the admin user");
+        state
+    }
+
+    /// The current configuration, later reloads don't change the returned one.
+    pub(crate) fn config(&self) -> Arc<Config> {
+        self.config.get()
+    }
+
+    /// Swaps in a reloaded configuration. A new key pair is rotated in, so tokens signed with
+    /// the previous one stay valid until they expire.
+    pub(crate) fn reload(&self, config: Config) {
+        let current = self.config();
+        if current.rsa.public_key() != config.rsa.public_key() {
+            // The keys are checked when the configuration is loaded
+            let key = SigningKey::from_pem(config.rsa.private_key(), config.rsa.public_key())
+                .expect("invalid rsa keys");
+            tracing::info!("signing tokens with key {}", key.kid);
+            self.keys.write().unwrap().rotate(
+                key,
+                Utc::now().timestamp(),
+                config.jwt.longest_token_lifetime(),
+            );
+        }
+        if current.event != config.event {
+            tracing::warn!("the event settings apply after a restart");
         }
+        if current.server != config.server || current.metrics != config.metrics {
+            tracing::warn!("the server and metrics settings apply after a restart");
+        }
+        self.config.set(config);
     }
 }
+
+pub(crate) mod principals;
@@ -0,0 +1,415 @@
+use crate::db::{GroupStore, RoleStore, UserStore};
+use crate::domain::event::DomainEvent;
+use crate::domain::group::{Group, GroupId};
+use crate::domain::role::{Role, RoleId};
+use crate::domain::tenant::TenantId;
+use crate::domain::user::{Impersonator, User, UserId};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// The users of a token, the impersonating one second.
+pub(crate) type Principal = (User, Option<Impersonator>);
+
+#[derive(Debug)]
+struct Entry {
+    principal: Principal,
+    expire_at: Instant,
+}
+
+impl Entry {
+    fn involves(&self, matches: impl Fn(&User) -> bool) -> bool {
+        matches(&self.principal.0)
+            || self
+                .principal
+                .1
+                .as_ref()
+                .is_some_and(|impersonator| matches(&impersonator.0))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// By the digest of the token, the tokens themselves aren't kept
+    by_token: HashMap<[u8; 32], Entry>,
+    /// Bumped by every invalidation, so a principal read before one isn't cached after it
+    generation: u64,
+}
+
+/// The principals of recently verified tokens, sparing the chatty callers a signature check and
+/// the reads of the users on every call. The stores of the state forget the principals of the
+/// users they change, and the eviction following the outbox those changed by other processes.
+#[derive(Debug, Default)]
+pub(crate) struct Principals {
+    entries: Mutex<Entries>,
+}
+
+fn digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token).into()
+}
+
+impl Principals {
+    pub(crate) fn get(&self, token: &str) -> Option<Principal> {
+        let mut entries = self.entries.lock().unwrap();
+        let key = digest(token);
+        match entries.by_token.get(&key) {
+            Some(entry) if entry.expire_at > Instant::now() => Some(entry.principal.clone()),
+            Some(_) => {
+                entries.by_token.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// To be read before resolving a principal, and passed back to `insert`.
+    pub(crate) fn generation(&self) -> u64 {
+        self.entries.lock().unwrap().generation
+    }
+
+    /// Caches the principal until `expire_at`, unless its users changed since `generation`. The
+    /// expired entries make room when the cache is full, then the ones expiring first.
+    pub(crate) fn insert(
+        &self,
+        token: &str,
+        principal: Principal,
+        expire_at: Instant,
+        generation: u64,
+        capacity: usize,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.generation != generation || capacity == 0 {
+            return;
+        }
+        if entries.by_token.len() >= capacity {
+            let now = Instant::now();
+            entries.by_token.retain(|_, entry| entry.expire_at > now);
+        }
+        while entries.by_token.len() >= capacity {
+            let first = entries
+                .by_token
+                .iter()
+                .min_by_key(|(_, entry)| entry.expire_at)
+                .map(|(key, _)| *key);
+            match first {
+                Some(key) => entries.by_token.remove(&key),
+                None => break,
+            };
+        }
+        entries.by_token.insert(
+            digest(token),
+            Entry {
+                principal,
+                expire_at,
+            },
+        );
+    }
+
+    fn forget(&self, matches: impl Fn(&User) -> bool) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.generation += 1;
+        entries
+            .by_token
+            .retain(|_, entry| !entry.involves(&matches));
+    }
+
+    pub(crate) fn forget_user(&self, user_id: &UserId) {
+        self.forget(|user| user.id == *user_id)
+    }
+
+    pub(crate) fn forget_role(&self, role_id: &RoleId) {
+        self.forget(|user| user.roles.iter().any(|r| r.id == *role_id))
+    }
+
+    /// Forgets the principals of the user or the role the event is about.
+    pub(crate) fn forget_event(&self, event: &DomainEvent) {
+        match event {
+            DomainEvent::RoleUpdated { role_id } | DomainEvent::RoleDeleted { role_id } => {
+                self.forget_role(role_id)
+            }
+            _ => {
+                if let Some(user_id) = event.user_id() {
+                    self.forget_user(user_id)
+                }
+            }
+        }
+    }
+}
+
+/// A store forgetting the cached principals of what it changes, without waiting for the eviction
+/// to read its events back.
+#[derive(Debug)]
+pub(crate) struct Forgetting<S> {
+    pub(crate) inner: S,
+    pub(crate) principals: Arc<Principals>,
+}
+
+#[tonic::async_trait]
+impl UserStore for Forgetting<Arc<dyn UserStore>> {
+    async fn insert(&self, user: User, events: &[DomainEvent]) -> Result<UserId> {
+        self.inner.insert(user, events).await
+    }
+
+    async fn get(&self, tenant_id: &TenantId, user_id: &UserId) -> Result<Option<User>> {
+        self.inner.get(tenant_id, user_id).await
+    }
+
+    async fn get_by_email(&self, tenant_id: &TenantId, email: &str) -> Result<Option<User>> {
+        self.inner.get_by_email(tenant_id, email).await
+    }
+
+    async fn list(&self, tenant_id: &TenantId, include_inactive: bool) -> Result<Vec<User>> {
+        self.inner.list(tenant_id, include_inactive).await
+    }
+
+    async fn delete(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let deleted = self.inner.delete(tenant_id, user_id, events).await;
+        self.principals.forget_user(user_id);
+        deleted
+    }
+
+    async fn update(
+        &self,
+        tenant_id: &TenantId,
+        user_id: &UserId,
+        user: User,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let updated = self.inner.update(tenant_id, user_id, user, events).await;
+        self.principals.forget_user(user_id);
+        updated
+    }
+}
+
+#[tonic::async_trait]
+impl RoleStore for Forgetting<Arc<dyn RoleStore>> {
+    async fn insert(&self, role: Role) -> Result<RoleId> {
+        self.inner.insert(role).await
+    }
+
+    async fn get(&self, role_id: &RoleId) -> Result<Option<Role>> {
+        self.inner.get(role_id).await
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Role>> {
+        self.inner.get_by_name(name).await
+    }
+
+    async fn list(&self) -> Result<Vec<Role>> {
+        self.inner.list().await
+    }
+
+    async fn update(&self, role: Role, events: &[DomainEvent]) -> Result<()> {
+        let role_id = role.id;
+        let updated = self.inner.update(role, events).await;
+        self.principals.forget_role(&role_id);
+        updated
+    }
+
+    async fn delete(&self, role_id: &RoleId, events: &[DomainEvent]) -> Result<()> {
+        let deleted = self.inner.delete(role_id, events).await;
+        self.principals.forget_role(role_id);
+        deleted
+    }
+}
+
+#[tonic::async_trait]
+impl GroupStore for Forgetting<Arc<dyn GroupStore>> {
+    async fn insert(&self, group: Group) -> Result<GroupId> {
+        self.inner.insert(group).await
+    }
+
+    async fn get(&self, tenant_id: &TenantId, group_id: &GroupId) -> Result<Option<Group>> {
+        self.inner.get(tenant_id, group_id).await
+    }
+
+    async fn get_by_name(&self, tenant_id: &TenantId, name: &str) -> Result<Option<Group>> {
+        self.inner.get_by_name(tenant_id, name).await
+    }
+
+    async fn list(&self, tenant_id: &TenantId) -> Result<Vec<Group>> {
+        self.inner.list(tenant_id).await
+    }
+
+    async fn update(&self, group: Group) -> Result<()> {
+        self.inner.update(group).await
+    }
+
+    async fn delete(
+        &self,
+        tenant_id: &TenantId,
+        group_id: &GroupId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let deleted = self.inner.delete(tenant_id, group_id, events).await;
+        events.iter().for_each(|e| self.principals.forget_event(e));
+        deleted
+    }
+
+    async fn add_member(
+        &self,
+        group_id: &GroupId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let added = self.inner.add_member(group_id, user_id, events).await;
+        self.principals.forget_user(user_id);
+        added
+    }
+
+    async fn remove_member(
+        &self,
+        group_id: &GroupId,
+        user_id: &UserId,
+        events: &[DomainEvent],
+    ) -> Result<()> {
+        let removed = self.inner.remove_member(group_id, user_id, events).await;
+        self.principals.forget_user(user_id);
+        removed
+    }
+
+    async fn members(&self, group_ids: &[GroupId]) -> Result<Vec<UserId>> {
+        self.inner.members(group_ids).await
+    }
+
+    async fn groups_of(&self, user_id: &UserId) -> Result<Vec<GroupId>> {
+        self.inner.groups_of(user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmd::jwt::who::Who;
+    use crate::cmd::role::add::Add as AddRole;
+    use crate::cmd::role::update::Update as UpdateRole;
+    use crate::cmd::user::add::Add;
+    use crate::cmd::user::login::Login;
+    use crate::cmd::user::set_status::SetStatus;
+    use crate::cmd::Command;
+    use crate::db::sqlite::connect;
+    use crate::domain::role::permission;
+    use crate::domain::tenant::PLATFORM_ID;
+    use crate::domain::user::{Profile, User, UserStatus};
+    use crate::state::principals::Principals;
+    use crate::state::State;
+    use avocado_base::secret::SecretString;
+    use std::time::{Duration, Instant};
+    use ulid::Ulid;
+
+    fn user() -> User {
+        User {
+            id: Ulid::new(),
+            tenant_id: PLATFORM_ID,
+            email: "cached@avocado.com".to_string(),
+            first_name: String::new(),
+            last_name: String::new(),
+            password_hash: String::new(),
+            roles: vec![],
+            status: UserStatus::Active,
+            profile: Profile::default(),
+        }
+    }
+
+    #[test]
+    fn test_cache() {
+        let principals = Principals::default();
+        let later = Instant::now() + Duration::from_secs(60);
+
+        principals.insert("a", (user(), None), later, 0, 2);
+        assert!(principals.get("a").is_some());
+        assert!(principals.get("b").is_none());
+
+        // Expired entries are dropped
+        principals.insert("b", (user(), None), Instant::now(), 0, 2);
+        assert!(principals.get("b").is_none());
+
+        // A full cache evicts the entry expiring first
+        principals.insert("b", (user(), None), later - Duration::from_secs(1), 0, 2);
+        principals.insert("c", (user(), None), later, 0, 2);
+        assert!(principals.get("a").is_some());
+        assert!(principals.get("b").is_none());
+        assert!(principals.get("c").is_some());
+
+        // A principal resolved before an invalidation isn't cached
+        let generation = principals.generation();
+        principals.forget_user(&Ulid::new());
+        principals.insert("d", (user(), None), later, generation, 2);
+        assert!(principals.get("d").is_none());
+
+        principals.insert("e", (user(), None), later, principals.generation(), 0);
+        assert!(principals.get("e").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forget() {
+        let state = State::new(connect().await).await;
+        let role_id = AddRole {
+            name: "viewer".to_string(),
+            permissions: vec![permission::USER_READ.to_string()],
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let user_id = Add {
+            tenant_id: PLATFORM_ID,
+            email: "cached@avocado.com".to_string(),
+            first_name: "Cached".to_string(),
+            last_name: "Test".to_string(),
+            password: SecretString::new("password".to_string()),
+            roles: vec!["viewer".to_string()],
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let (access_token, _) = Login {
+            tenant: "".to_string(),
+            email: "cached@avocado.com".to_string(),
+            password: SecretString::new("password".to_string()),
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        let who = Who {
+            token: access_token.clone(),
+        };
+
+        let (user, _) = who.execute(state.clone()).await.unwrap();
+        assert!(user.has_permission(permission::USER_READ));
+        assert!(state.principals.get(&access_token).is_some());
+
+        // Changing a role forgets the principals holding it
+        UpdateRole {
+            role_id,
+            name: "viewer".to_string(),
+            permissions: vec![permission::GROUP_READ.to_string()],
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert!(state.principals.get(&access_token).is_none());
+        let (user, _) = who.execute(state.clone()).await.unwrap();
+        assert!(!user.has_permission(permission::USER_READ));
+        assert!(user.has_permission(permission::GROUP_READ));
+
+        // Changing the user forgets their principals, disabled users are refused right away
+        SetStatus {
+            tenant_id: PLATFORM_ID,
+            user_id,
+            enabled: false,
+            actor: None,
+        }
+        .execute(state.clone())
+        .await
+        .unwrap();
+        assert!(state.principals.get(&access_token).is_none());
+        assert!(who.execute(state.clone()).await.is_err());
+    }
+}
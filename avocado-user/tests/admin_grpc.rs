@@ -0,0 +1,110 @@
+use crate::app::start_server;
+use avocado_proto::grpc::jwt::jwt_client::JwtClient;
+use avocado_proto::grpc::jwt::{RotateKeyRequest, VerifyRequest};
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{AddRequest, LoginRequest, ResetPasswordRequest, WhoAmIRequest};
+use tonic::metadata::MetadataValue;
+
+mod app;
+
+#[tokio::test]
+async fn admin_grpc_works() {
+    start_server().await;
+
+    let mut user_client = UserClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to user grpc server");
+    let mut jwt_client = JwtClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to jwt grpc server");
+    let login = |email: &str, password: &str| LoginRequest {
+        tenant: "".to_string(),
+        email: email.to_string(),
+        password: password.to_string(),
+    };
+    let old_token = user_client
+        .login(login("admin@avocado.com", "kIxv4NomLT0WwGKF"))
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token;
+    let access_token: MetadataValue<_> = old_token.parse().expect("cannot insert grpc auth header");
+
+    // Reset the password of a user
+    let mut request = tonic::Request::new(AddRequest {
+        email: "reset@avocado.com".to_string(),
+        first_name: "Reset".to_string(),
+        last_name: "Test".to_string(),
+        password: "password".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let user_id = user_client
+        .add(request)
+        .await
+        .expect("add user grpc call failed")
+        .into_inner()
+        .user_id;
+    let mut request = tonic::Request::new(ResetPasswordRequest {
+        user_id,
+        new_password: "new-password".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    user_client
+        .reset_password(request)
+        .await
+        .expect("reset password grpc call failed");
+    user_client
+        .login(login("reset@avocado.com", "new-password"))
+        .await
+        .expect("login with the reset password failed");
+
+    // Rotate the signing key, the tokens signed before stay valid
+    let mut request = tonic::Request::new(RotateKeyRequest {
+        private_key: include_str!("keys/rotated.pem").to_string(),
+        public_key: include_str!("keys/rotated.pub.pem").to_string(),
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let kid = jwt_client
+        .rotate_key(request)
+        .await
+        .expect("rotate key grpc call failed")
+        .into_inner()
+        .kid;
+    assert!(!kid.is_empty());
+    let mut request = tonic::Request::new(WhoAmIRequest {});
+    request.metadata_mut().insert("auth", access_token.clone());
+    user_client
+        .who_am_i(request)
+        .await
+        .expect("the token signed by the retired key is refused");
+    let new_token = user_client
+        .login(login("admin@avocado.com", "kIxv4NomLT0WwGKF"))
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token;
+    assert_ne!(new_token, old_token);
+    jwt_client
+        .verify(VerifyRequest { token: new_token })
+        .await
+        .expect("the token signed by the new key is refused");
+
+    // Only platform admins can rotate keys
+    let token: MetadataValue<_> = user_client
+        .login(login("reset@avocado.com", "new-password"))
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token
+        .parse()
+        .expect("cannot insert grpc auth header");
+    let mut request = tonic::Request::new(RotateKeyRequest::default());
+    request.metadata_mut().insert("auth", token);
+    let status = jwt_client
+        .rotate_key(request)
+        .await
+        .expect_err("rotate key should be refused");
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+}
@@ -1,9 +1,9 @@
 use avocado_base::log::init_subscriber;
-use avocado_proto::grpc::user::user_client::UserClient;
 use avocado_user::run;
 use once_cell::sync::Lazy;
 use sqlx::__rt::timeout;
 use std::time::Duration;
+use tokio::net::TcpStream;
 use tokio::time::sleep;
 
 static TRACING: Lazy<()> = Lazy::new(|| {
@@ -12,18 +12,31 @@ static TRACING: Lazy<()> = Lazy::new(|| {
     }
 });
 
+/// Points the server at the key pair and admin of the tests, no build comes with any.
+fn configure(keys: &str) {
+    std::env::set_var(
+        "AVOCADO_RSA__PRIVATE_KEY_FILE",
+        format!("{}/test.pem", keys),
+    );
+    std::env::set_var(
+        "AVOCADO_RSA__PUBLIC_KEY_FILE",
+        format!("{}/test.pub.pem", keys),
+    );
+    std::env::set_var("AVOCADO_BOOTSTRAP__ADMIN_PASSWORD", "kIxv4NomLT0WwGKF");
+}
+
 pub async fn start_server() {
     Lazy::force(&TRACING);
+    configure(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/keys"));
 
-    let address = "[::1]:50051".parse().expect("invalid socket address");
-    let server = run(address)
+    let server = run()
         .await
         .expect("failed to create avocado-user grpc router");
     tokio::spawn(server);
 
     async fn wait_server() {
         loop {
-            if UserClient::connect("http://[::1]:50051").await.is_ok() {
+            if TcpStream::connect("[::1]:50051").await.is_ok() {
                 break;
             }
             sleep(Duration::from_millis(300)).await;
@@ -0,0 +1,180 @@
+use crate::app::start_server;
+use avocado_proto::grpc::audit::audit_client::AuditClient;
+use avocado_proto::grpc::audit::{ListAuditEventsRequest, Outcome};
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{AddRequest, LoginRequest, WhoAmIRequest};
+use tonic::metadata::MetadataValue;
+use tonic::Code;
+
+mod app;
+
+#[tokio::test]
+async fn audit_grpc_works() {
+    std::env::set_var(
+        "AVOCADO_RATE_LIMIT__AUDITED_DENIALS__PER_CLIENT__BURST",
+        "3",
+    );
+    std::env::set_var(
+        "AVOCADO_RATE_LIMIT__AUDITED_DENIALS__PER_CLIENT__PER_MINUTE",
+        "1",
+    );
+    start_server().await;
+
+    let mut user_client = UserClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to user grpc server");
+
+    // Fail to login once, then login as admin
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: "admin@avocado.com".to_string(),
+        password: "wrong-password".to_string(),
+    });
+    assert!(user_client.login(request).await.is_err());
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: "admin@avocado.com".to_string(),
+        password: "kIxv4NomLT0WwGKF".to_string(),
+    });
+    let access_token = user_client
+        .login(request)
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token;
+
+    // Both logins are in the audit log, newest first
+    let mut audit_client = AuditClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to audit grpc server");
+    let access_token: MetadataValue<_> = access_token
+        .parse()
+        .expect("cannot insert grpc auth header");
+    let mut request = tonic::Request::new(ListAuditEventsRequest {
+        action: "user.login".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let reply = audit_client
+        .list_audit_events(request)
+        .await
+        .expect("list audit events grpc call failed")
+        .into_inner();
+    assert_eq!(reply.events.len(), 2);
+    assert!(reply.next_page_token.is_empty());
+    let login = reply.events.first().unwrap();
+    assert!(login.success);
+    assert_eq!(login.target, "admin@avocado.com");
+    assert_eq!(login.actor_id.len(), 26);
+    assert_eq!(login.source_ip, "::1");
+
+    let mut request = tonic::Request::new(ListAuditEventsRequest {
+        outcome: Outcome::Failure.into(),
+        page_size: 1,
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let reply = audit_client
+        .list_audit_events(request)
+        .await
+        .expect("list audit events grpc call failed")
+        .into_inner();
+    let failed_login = reply.events.first().unwrap();
+    assert!(!failed_login.success);
+    assert!(failed_login.actor_id.is_empty());
+    assert_eq!(failed_login.failure_reason, "fail to authenticate the user");
+
+    // Calls refused by the auth middleware are audited too, those with an invalid token apart
+    // from every tenant
+    let mut request = tonic::Request::new(WhoAmIRequest {});
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer invalid".parse().unwrap());
+    let status = user_client
+        .who_am_i(request)
+        .await
+        .expect_err("an invalid token should be refused");
+    assert_eq!(status.code(), Code::Unauthenticated);
+    // But not those without any token
+    let status = user_client
+        .who_am_i(WhoAmIRequest {})
+        .await
+        .expect_err("a call without a token should be refused");
+    assert_eq!(status.code(), Code::Unauthenticated);
+    let mut request = tonic::Request::new(AddRequest {
+        email: "auditor@avocado.com".to_string(),
+        first_name: "Audit".to_string(),
+        last_name: "Test".to_string(),
+        password: "password".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let auditor_id = user_client
+        .add(request)
+        .await
+        .expect("add user grpc call failed")
+        .into_inner()
+        .user_id;
+    let auditor_token: MetadataValue<_> = user_client
+        .login(tonic::Request::new(LoginRequest {
+            tenant: "".to_string(),
+            email: "auditor@avocado.com".to_string(),
+            password: "password".to_string(),
+        }))
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token
+        .parse()
+        .unwrap();
+    let mut request = tonic::Request::new(ListAuditEventsRequest::default());
+    request.metadata_mut().insert("auth", auditor_token);
+    let status = audit_client
+        .list_audit_events(request)
+        .await
+        .expect_err("a user without audit:read should be refused");
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let mut request = tonic::Request::new(ListAuditEventsRequest {
+        action: "auth.deny".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let reply = audit_client
+        .list_audit_events(request)
+        .await
+        .expect("list audit events grpc call failed")
+        .into_inner();
+    assert_eq!(reply.events.len(), 1);
+    let denied = reply.events.first().unwrap();
+    assert_eq!(denied.actor_id, auditor_id);
+    assert_eq!(denied.target, "/audit.Audit/ListAuditEvents");
+    assert_eq!(denied.failure_reason, "permission audit:read is required");
+
+    // Only a client's share of the refusals is audited
+    for _ in 0..3 {
+        let mut request = tonic::Request::new(WhoAmIRequest {});
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer invalid".parse().unwrap());
+        assert!(user_client.who_am_i(request).await.is_err());
+    }
+
+    let mut request = tonic::Request::new(ListAuditEventsRequest {
+        action: "auth.deny".to_string(),
+        without_tenant: true,
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token);
+    let reply = audit_client
+        .list_audit_events(request)
+        .await
+        .expect("list audit events grpc call failed")
+        .into_inner();
+    assert_eq!(reply.events.len(), 2);
+    let denied = reply.events.first().unwrap();
+    assert!(denied.tenant_id.is_empty());
+    assert!(denied.actor_id.is_empty());
+    assert_eq!(denied.target, "/user.User/WhoAmI");
+    assert_eq!(denied.source_ip, "::1");
+}
@@ -0,0 +1,81 @@
+use crate::app::start_server;
+use avocado_base::auth::Bearer;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{LoginRequest, WhoAmIRequest};
+use tonic::metadata::MetadataValue;
+use tonic::transport::Channel;
+use tonic::Code;
+
+mod app;
+
+#[tokio::test]
+async fn bearer_grpc_works() {
+    start_server().await;
+
+    let channel = Channel::from_static("http://[::1]:50051")
+        .connect()
+        .await
+        .expect("failed to connect to user grpc server");
+    let mut user_client = UserClient::new(channel.clone());
+
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: "admin@avocado.com".to_string(),
+        password: "kIxv4NomLT0WwGKF".to_string(),
+    });
+    let access_token = user_client
+        .login(request)
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token;
+
+    // The standard header, as sent by the interceptor
+    let mut bearer_client =
+        UserClient::with_interceptor(channel.clone(), Bearer::new(&access_token).unwrap());
+    let response = bearer_client
+        .who_am_i(WhoAmIRequest {})
+        .await
+        .expect("cannot get who I am with a bearer token");
+    assert_eq!(response.into_inner().email, "admin@avocado.com");
+
+    // No token
+    let status = user_client
+        .who_am_i(WhoAmIRequest {})
+        .await
+        .expect_err("a token should be required");
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(
+        status.metadata().get("www-authenticate").unwrap(),
+        "Bearer realm=\"avocado\""
+    );
+
+    // Another scheme than bearer
+    let mut request = tonic::Request::new(WhoAmIRequest {});
+    let basic: MetadataValue<_> = "Basic YWRtaW46cGFzc3dvcmQ=".parse().unwrap();
+    request.metadata_mut().insert("authorization", basic);
+    let status = user_client
+        .who_am_i(request)
+        .await
+        .expect_err("basic authentication should be refused");
+    assert_eq!(status.code(), Code::Unauthenticated);
+    let challenge = status.metadata().get("www-authenticate").unwrap();
+    assert!(challenge
+        .to_str()
+        .unwrap()
+        .contains("error=\"invalid_request\""));
+
+    // An invalid token
+    let mut bearer_client =
+        UserClient::with_interceptor(channel, Bearer::new("not-a-token").unwrap());
+    let status = bearer_client
+        .who_am_i(WhoAmIRequest {})
+        .await
+        .expect_err("an invalid token should be refused");
+    assert_eq!(status.code(), Code::Unauthenticated);
+    let challenge = status.metadata().get("www-authenticate").unwrap();
+    assert!(challenge
+        .to_str()
+        .unwrap()
+        .contains("error=\"invalid_token\""));
+}
@@ -0,0 +1,78 @@
+use crate::app::start_server;
+use avocado_base::auth::Bearer;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{AddRequest, LoginRequest};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use std::str::FromStr;
+use tonic::transport::Channel;
+
+mod app;
+
+async fn migrate(url: &str) -> String {
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_avocado-user-admin"))
+        .arg("migrate")
+        .env("AVOCADO_DATABASE__URL", url)
+        .output()
+        .await
+        .expect("unable to run the admin cli");
+    assert!(
+        output.status.success(),
+        "admin cli migrate failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[tokio::test]
+async fn database_grpc_works() {
+    let file = std::env::temp_dir().join(format!("avocado-user-{}.db", std::process::id()));
+    let url = format!("sqlite://{}", file.display());
+
+    // The database upgraded ahead of the start is the one the server opens
+    let migrated = migrate(&url).await;
+    std::env::set_var("AVOCADO_DATABASE__URL", &url);
+    start_server().await;
+
+    let channel = Channel::from_static("http://[::1]:50051")
+        .connect()
+        .await
+        .expect("failed to connect to user grpc server");
+    let access_token = UserClient::new(channel.clone())
+        .login(tonic::Request::new(LoginRequest {
+            tenant: "".to_string(),
+            email: "admin@avocado.com".to_string(),
+            password: "kIxv4NomLT0WwGKF".to_string(),
+        }))
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token;
+    UserClient::with_interceptor(channel, Bearer::new(&access_token).unwrap())
+        .add(AddRequest {
+            email: "stored@avocado.com".to_string(),
+            first_name: "Stored".to_string(),
+            last_name: "User".to_string(),
+            password: "password".to_string(),
+            ..Default::default()
+        })
+        .await
+        .expect("add user grpc call failed");
+
+    // The user is in the file, which is up to date already
+    let mut connection = SqliteConnectOptions::from_str(&url)
+        .unwrap()
+        .connect()
+        .await
+        .expect("unable to open the database file");
+    let (stored,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM user WHERE email = 'stored@avocado.com'")
+            .fetch_one(&mut connection)
+            .await
+            .expect("unable to query the users");
+    assert_eq!(stored, 1);
+    connection.close().await.unwrap();
+    assert_eq!(migrate(&url).await, migrated);
+
+    std::fs::remove_file(file).ok();
+}
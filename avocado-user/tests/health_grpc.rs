@@ -0,0 +1,95 @@
+use crate::app::start_server;
+use avocado_proto::grpc::health::health_check_response::ServingStatus;
+use avocado_proto::grpc::health::health_client::HealthClient;
+use avocado_proto::grpc::health::HealthCheckRequest;
+use avocado_proto::grpc::reflection::server_reflection_client::ServerReflectionClient;
+use avocado_proto::grpc::reflection::server_reflection_request::MessageRequest;
+use avocado_proto::grpc::reflection::server_reflection_response::MessageResponse;
+use avocado_proto::grpc::reflection::ServerReflectionRequest;
+use prost::Message;
+use prost_types::FileDescriptorProto;
+use tonic::Code;
+
+mod app;
+
+#[tokio::test]
+async fn health_grpc_works() {
+    start_server().await;
+
+    // Health checks need no token
+    let mut health_client = HealthClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to health grpc server");
+    for service in ["", "user.User", "jwt.Jwt"] {
+        let response = health_client
+            .check(HealthCheckRequest {
+                service: service.to_string(),
+            })
+            .await
+            .expect("health check grpc call failed")
+            .into_inner();
+        assert_eq!(response.status(), ServingStatus::Serving);
+    }
+    let status = health_client
+        .check(HealthCheckRequest {
+            service: "unknown.Service".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    // The failed call tells its request id too
+    assert!(status.metadata().get("x-request-id").is_some());
+
+    let mut stream = health_client
+        .watch(HealthCheckRequest {
+            service: "user.User".to_string(),
+        })
+        .await
+        .expect("health watch grpc call failed")
+        .into_inner();
+    let response = stream.message().await.unwrap().unwrap();
+    assert_eq!(response.status(), ServingStatus::Serving);
+
+    // Reflection neither
+    let mut reflection_client = ServerReflectionClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to reflection grpc server");
+    let requests = [
+        MessageRequest::ListServices("".to_string()),
+        MessageRequest::FileContainingSymbol("user.User.Login".to_string()),
+        MessageRequest::FileContainingSymbol("unknown.Service".to_string()),
+    ]
+    .map(|r| ServerReflectionRequest {
+        host: "".to_string(),
+        message_request: Some(r),
+    });
+    let mut responses = reflection_client
+        .server_reflection_info(tokio_stream::iter(requests))
+        .await
+        .expect("server reflection grpc call failed")
+        .into_inner();
+
+    match responses.message().await.unwrap().unwrap().message_response {
+        Some(MessageResponse::ListServicesResponse(list)) => {
+            let names: Vec<_> = list.service.into_iter().map(|s| s.name).collect();
+            assert!(names.contains(&"user.User".to_string()));
+            assert!(names.contains(&"grpc.health.v1.Health".to_string()));
+        }
+        r => panic!("unexpected reflection response {:?}", r),
+    }
+    match responses.message().await.unwrap().unwrap().message_response {
+        Some(MessageResponse::FileDescriptorResponse(files)) => {
+            let file = FileDescriptorProto::decode(&files.file_descriptor_proto[0][..]).unwrap();
+            assert_eq!(file.package(), "user");
+            assert!(file.service.iter().any(|s| s.name() == "User"));
+        }
+        r => panic!("unexpected reflection response {:?}", r),
+    }
+    match responses.message().await.unwrap().unwrap().message_response {
+        Some(MessageResponse::ErrorResponse(error)) => {
+            assert_eq!(error.error_code, Code::NotFound as i32);
+        }
+        r => panic!("unexpected reflection response {:?}", r),
+    }
+    assert!(responses.message().await.unwrap().is_none());
+}
@@ -0,0 +1,94 @@
+use crate::app::start_server;
+use avocado_proto::grpc::jwt::jwt_client::JwtClient;
+use avocado_proto::grpc::jwt::RefreshRequest;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{
+    AddRequest, ChangePasswordRequest, ImpersonateRequest, LoginRequest, WhoAmIRequest,
+};
+use tonic::metadata::MetadataValue;
+use tonic::Code;
+
+mod app;
+
+#[tokio::test]
+async fn impersonate_grpc_works() {
+    start_server().await;
+
+    let mut user_client = UserClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to user grpc server");
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: "admin@avocado.com".to_string(),
+        password: "kIxv4NomLT0WwGKF".to_string(),
+    });
+    let access_token: MetadataValue<_> = user_client
+        .login(request)
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token
+        .parse()
+        .expect("cannot insert grpc auth header");
+
+    let mut request = tonic::Request::new(AddRequest {
+        email: "customer@avocado.com".to_string(),
+        first_name: "Customer".to_string(),
+        last_name: "Test".to_string(),
+        password: "password".to_string(),
+        roles: vec!["user".to_string()],
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let user_id = user_client
+        .add(request)
+        .await
+        .expect("add user grpc call failed")
+        .into_inner()
+        .user_id;
+
+    let mut request = tonic::Request::new(ImpersonateRequest {
+        user_id: user_id.clone(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token);
+    let impersonated_token = user_client
+        .impersonate(request)
+        .await
+        .expect("impersonate grpc call failed")
+        .into_inner()
+        .access_token;
+    let impersonated: MetadataValue<_> = impersonated_token
+        .parse()
+        .expect("cannot insert grpc auth header");
+
+    // WhoAmI reports the impersonated user along with the real one
+    let mut request = tonic::Request::new(WhoAmIRequest {});
+    request.metadata_mut().insert("auth", impersonated.clone());
+    let reply = user_client
+        .who_am_i(request)
+        .await
+        .expect("cannot get who I am")
+        .into_inner();
+    assert_eq!(reply.id, user_id);
+    assert_eq!(reply.actor.unwrap().email, "admin@avocado.com");
+
+    // Sensitive operations are refused with an impersonated token
+    let mut request = tonic::Request::new(ChangePasswordRequest {
+        old_password: "password".to_string(),
+        new_password: "new-password".to_string(),
+    });
+    request.metadata_mut().insert("auth", impersonated.clone());
+    let status = user_client.change_password(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let mut jwt_client = JwtClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to jwt grpc server");
+    let mut request = tonic::Request::new(RefreshRequest {
+        refresh_token: impersonated_token,
+    });
+    request.metadata_mut().insert("auth", impersonated);
+    let status = jwt_client.refresh(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
@@ -0,0 +1,144 @@
+use crate::app::start_server;
+use avocado_proto::grpc::user::import_users_request::Item;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{
+    ExportUsersRequest, ImportOptions, ImportOutcome, ImportRow, ImportUsersRequest, LoginRequest,
+};
+use tonic::metadata::MetadataValue;
+
+mod app;
+
+fn row(email: &str, password: &str) -> ImportUsersRequest {
+    ImportUsersRequest {
+        item: Some(Item::Row(ImportRow {
+            email: email.to_string(),
+            first_name: "Import".to_string(),
+            last_name: "Test".to_string(),
+            password: password.to_string(),
+            roles: vec!["user".to_string()],
+        })),
+    }
+}
+
+#[tokio::test]
+async fn import_export_users_grpc_works() {
+    std::env::set_var("AVOCADO_IMPORT__MAX_ROWS", "2");
+    start_server().await;
+
+    let mut user_client = UserClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to user grpc server");
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: "admin@avocado.com".to_string(),
+        password: "kIxv4NomLT0WwGKF".to_string(),
+    });
+    let access_token: MetadataValue<_> = user_client
+        .login(request)
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token
+        .parse()
+        .expect("cannot insert grpc auth header");
+
+    let import_rows = |dry_run: bool, upsert: bool, emails: &[&str]| {
+        let options = ImportUsersRequest {
+            item: Some(Item::Options(ImportOptions {
+                dry_run,
+                upsert,
+                ..Default::default()
+            })),
+        };
+        let mut messages = vec![options];
+        messages.extend(emails.iter().map(|email| row(email, "password")));
+        let mut request = tonic::Request::new(tokio_stream::iter(messages));
+        request.metadata_mut().insert("auth", access_token.clone());
+        request
+    };
+    let import = |dry_run: bool, upsert: bool| {
+        import_rows(dry_run, upsert, &["import@avocado.com", "invalid"])
+    };
+
+    // A dry run reports the outcome without adding anyone
+    let reply = user_client
+        .import_users(import(true, false))
+        .await
+        .expect("import users grpc call failed")
+        .into_inner();
+    assert_eq!((reply.created, reply.updated, reply.failed), (1, 0, 1));
+    assert_eq!(reply.results[0].outcome(), ImportOutcome::Created);
+    assert!(reply.results[0].user_id.is_empty());
+    assert_eq!(reply.results[1].outcome(), ImportOutcome::Failed);
+    assert!(!reply.results[1].error.is_empty());
+
+    let reply = user_client
+        .import_users(import(false, false))
+        .await
+        .expect("import users grpc call failed")
+        .into_inner();
+    assert_eq!((reply.created, reply.updated, reply.failed), (1, 0, 1));
+    let user_id = reply.results[0].user_id.clone();
+    assert!(!user_id.is_empty());
+
+    // Existing emails fail unless upserting
+    let reply = user_client
+        .import_users(import(false, false))
+        .await
+        .expect("import users grpc call failed")
+        .into_inner();
+    assert_eq!(reply.results[0].outcome(), ImportOutcome::Failed);
+    let reply = user_client
+        .import_users(import(false, true))
+        .await
+        .expect("import users grpc call failed")
+        .into_inner();
+    assert_eq!(reply.results[0].outcome(), ImportOutcome::Updated);
+    assert_eq!(reply.results[0].user_id, user_id);
+
+    // Emails match whatever their case
+    let reply = user_client
+        .import_users(import_rows(false, true, &["Import@Avocado.com"]))
+        .await
+        .expect("import users grpc call failed")
+        .into_inner();
+    assert_eq!(reply.results[0].outcome(), ImportOutcome::Updated);
+    assert_eq!(reply.results[0].user_id, user_id);
+
+    // Imports larger than configured are refused as a whole
+    let status = user_client
+        .import_users(import_rows(
+            false,
+            false,
+            &["a@avocado.com", "b@avocado.com", "c@avocado.com"],
+        ))
+        .await
+        .expect_err("an import over the maximum should fail");
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    let mut request = tonic::Request::new(ExportUsersRequest::default());
+    request.metadata_mut().insert("auth", access_token.clone());
+    let mut stream = user_client
+        .export_users(request)
+        .await
+        .expect("export users grpc call failed")
+        .into_inner();
+    let mut emails = vec![];
+    while let Some(user) = stream.message().await.expect("export stream failed") {
+        emails.push(user.email);
+    }
+    assert!(emails.contains(&"admin@avocado.com".to_string()));
+    assert!(emails.contains(&"import@avocado.com".to_string()));
+
+    // Importing requires the options first
+    let mut request = tonic::Request::new(tokio_stream::iter(vec![row(
+        "import@avocado.com",
+        "password",
+    )]));
+    request.metadata_mut().insert("auth", access_token.clone());
+    let status = user_client
+        .import_users(request)
+        .await
+        .expect_err("import without options should fail");
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
@@ -19,6 +19,7 @@ async fn jwt_grpc_works() {
 
     // Login as admin
     let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
         email: "admin@avocado.com".to_string(),
         password: "kIxv4NomLT0WwGKF".to_string(),
     });
@@ -0,0 +1,50 @@
+use crate::app::start_server;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::LoginRequest;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+mod app;
+
+async fn scrape() -> String {
+    let mut stream = TcpStream::connect("[::1]:9090")
+        .await
+        .expect("failed to connect to metrics server");
+    stream
+        .write_all(b"GET /metrics HTTP/1.0\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.0 200"), "{}", response);
+    response
+}
+
+#[tokio::test]
+async fn metrics_grpc_works() {
+    start_server().await;
+
+    let mut user_client = UserClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to user grpc server");
+    for password in ["kIxv4NomLT0WwGKF", "wrong"] {
+        let _ = user_client
+            .login(LoginRequest {
+                tenant: "".to_string(),
+                email: "admin@avocado.com".to_string(),
+                password: password.to_string(),
+            })
+            .await;
+    }
+
+    let metrics = scrape().await;
+    assert!(metrics.contains(r#"avocado_logins_total{result="success",service="user"} 1"#));
+    assert!(metrics.contains(r#"avocado_logins_total{result="failure",service="user"} 1"#));
+    assert!(metrics.contains(
+        r#"avocado_requests_total{method="/user.User/Login",status="0",service="user"} 1"#
+    ));
+    assert!(metrics.contains(
+        r#"avocado_request_duration_seconds_count{method="/user.User/Login",service="user"} 2"#
+    ));
+    assert!(metrics.contains(r#"avocado_db_pool_connections{state="idle",service="user"}"#));
+}
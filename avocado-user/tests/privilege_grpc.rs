@@ -0,0 +1,219 @@
+use crate::app::start_server;
+use avocado_base::auth::Bearer;
+use avocado_proto::grpc::role::role_client::RoleClient;
+use avocado_proto::grpc::role::AddRequest as AddRoleRequest;
+use avocado_proto::grpc::user::import_users_request::Item;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{
+    AddRequest, AssignRoleRequest, DeleteRequest, DisableRequest, ImportOptions, ImportOutcome,
+    ImportRow, ImportUsersRequest, LoginRequest, ResetPasswordRequest, RevokeRoleRequest,
+    UpdateRequest, WhoAmIRequest,
+};
+use tonic::transport::Channel;
+use tonic::Code;
+
+mod app;
+
+async fn login(channel: &Channel, email: &str, password: &str) -> Bearer {
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: email.to_string(),
+        password: password.to_string(),
+    });
+    let access_token = UserClient::new(channel.clone())
+        .login(request)
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token;
+    Bearer::new(&access_token).unwrap()
+}
+
+fn add_request(email: &str, roles: &[&str]) -> AddRequest {
+    AddRequest {
+        email: email.to_string(),
+        first_name: "Privilege".to_string(),
+        last_name: "Test".to_string(),
+        password: "password".to_string(),
+        roles: roles.iter().map(|r| r.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn privilege_grpc_works() {
+    start_server().await;
+
+    let channel = Channel::from_static("http://[::1]:50051")
+        .connect()
+        .await
+        .expect("failed to connect to user grpc server");
+    let admin = login(&channel, "admin@avocado.com", "kIxv4NomLT0WwGKF").await;
+
+    // A user manager, who may write users but holds no other permission
+    RoleClient::with_interceptor(channel.clone(), admin.clone())
+        .add(AddRoleRequest {
+            name: "user-writer".to_string(),
+            permissions: vec!["user:write".to_string()],
+        })
+        .await
+        .expect("add role grpc call failed");
+    let mut admin_client = UserClient::with_interceptor(channel.clone(), admin.clone());
+    let admin_id = admin_client
+        .who_am_i(WhoAmIRequest {})
+        .await
+        .expect("cannot get who I am")
+        .into_inner()
+        .id;
+    admin_client
+        .add(add_request("writer@avocado.com", &["user-writer"]))
+        .await
+        .expect("add user grpc call failed");
+    let writer = login(&channel, "writer@avocado.com", "password").await;
+    let mut writer_client = UserClient::with_interceptor(channel.clone(), writer);
+
+    // They can add normal users and their peers, but no admin
+    let normal_id = writer_client
+        .add(add_request("normal@avocado.com", &[]))
+        .await
+        .expect("a normal user should be added")
+        .into_inner()
+        .user_id;
+    writer_client
+        .add(add_request("peer@avocado.com", &["user-writer"]))
+        .await
+        .expect("a user holding the writer's permissions should be added");
+    let status = writer_client
+        .add(add_request("escalated@avocado.com", &["admin"]))
+        .await
+        .expect_err("an admin should not be added by a user writer");
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    // They can reset the password of a normal user, but not take over the admin
+    writer_client
+        .reset_password(ResetPasswordRequest {
+            user_id: normal_id.clone(),
+            new_password: "new-password".to_string(),
+            ..Default::default()
+        })
+        .await
+        .expect("the password of a normal user should be reset");
+    login(&channel, "normal@avocado.com", "new-password").await;
+    let status = writer_client
+        .reset_password(ResetPasswordRequest {
+            user_id: admin_id.clone(),
+            new_password: "new-password".to_string(),
+            ..Default::default()
+        })
+        .await
+        .expect_err("the password of the admin should not be reset by a user writer");
+    assert_eq!(status.code(), Code::PermissionDenied);
+    login(&channel, "admin@avocado.com", "kIxv4NomLT0WwGKF").await;
+
+    // Nor lock the admin out, take over their email or delete them
+    let status = writer_client
+        .disable(DisableRequest {
+            user_id: admin_id.clone(),
+            ..Default::default()
+        })
+        .await
+        .expect_err("the admin should not be disabled by a user writer");
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let status = writer_client
+        .update(UpdateRequest {
+            user_id: admin_id.clone(),
+            email: "writer-owned@avocado.com".to_string(),
+            first_name: "Taken".to_string(),
+            last_name: "Over".to_string(),
+            ..Default::default()
+        })
+        .await
+        .expect_err("the email of the admin should not be changed by a user writer");
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let status = writer_client
+        .delete(DeleteRequest {
+            user_id: admin_id.clone(),
+            ..Default::default()
+        })
+        .await
+        .expect_err("the admin should not be deleted by a user writer");
+    assert_eq!(status.code(), Code::PermissionDenied);
+    login(&channel, "admin@avocado.com", "kIxv4NomLT0WwGKF").await;
+
+    // Nor can they take over the admin, or grant it, through an import
+    let row = |email: &str, roles: &[&str]| ImportUsersRequest {
+        item: Some(Item::Row(ImportRow {
+            email: email.to_string(),
+            first_name: "Import".to_string(),
+            last_name: "Test".to_string(),
+            password: "new-password".to_string(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        })),
+    };
+    let messages = vec![
+        ImportUsersRequest {
+            item: Some(Item::Options(ImportOptions {
+                upsert: true,
+                ..Default::default()
+            })),
+        },
+        row("admin@avocado.com", &["user"]),
+        row("imported@avocado.com", &["admin"]),
+    ];
+    let reply = writer_client
+        .import_users(tokio_stream::iter(messages))
+        .await
+        .expect("import users grpc call failed")
+        .into_inner();
+    assert_eq!((reply.created, reply.updated, reply.failed), (0, 0, 2));
+    assert_eq!(reply.results[0].outcome(), ImportOutcome::Failed);
+    login(&channel, "admin@avocado.com", "kIxv4NomLT0WwGKF").await;
+
+    // A role manager can assign the roles they hold, but not the admin, to themselves or others
+    let mut role_client = RoleClient::with_interceptor(channel.clone(), admin);
+    role_client
+        .add(AddRoleRequest {
+            name: "role-writer".to_string(),
+            permissions: vec!["role:write".to_string()],
+        })
+        .await
+        .expect("add role grpc call failed");
+    let role_writer_id = admin_client
+        .add(add_request("role-writer@avocado.com", &["role-writer"]))
+        .await
+        .expect("add user grpc call failed")
+        .into_inner()
+        .user_id;
+    let role_writer = login(&channel, "role-writer@avocado.com", "password").await;
+    let mut role_writer_client = UserClient::with_interceptor(channel.clone(), role_writer);
+    let reply = role_writer_client
+        .assign_role(AssignRoleRequest {
+            user_id: normal_id.clone(),
+            role: "role-writer".to_string(),
+            ..Default::default()
+        })
+        .await
+        .expect("a role the role writer holds should be assigned")
+        .into_inner();
+    assert!(reply.roles.contains(&"role-writer".to_string()));
+    for user_id in [role_writer_id, normal_id] {
+        let status = role_writer_client
+            .assign_role(AssignRoleRequest {
+                user_id,
+                role: "admin".to_string(),
+                ..Default::default()
+            })
+            .await
+            .expect_err("the admin role should not be assigned by a role writer");
+        assert_eq!(status.code(), Code::PermissionDenied);
+    }
+    let status = role_writer_client
+        .revoke_role(RevokeRoleRequest {
+            user_id: admin_id,
+            role: "admin".to_string(),
+            ..Default::default()
+        })
+        .await
+        .expect_err("the admin role should not be revoked by a role writer");
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
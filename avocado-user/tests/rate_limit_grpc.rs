@@ -0,0 +1,106 @@
+use crate::app::start_server;
+use avocado_base::auth::Bearer;
+use avocado_proto::grpc::jwt::jwt_client::JwtClient;
+use avocado_proto::grpc::jwt::RefreshRequest;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{LoginReply, LoginRequest};
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+
+mod app;
+
+async fn login(channel: &Channel, email: &str) -> Result<LoginReply, Status> {
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: email.to_string(),
+        password: "kIxv4NomLT0WwGKF".to_string(),
+    });
+    UserClient::new(channel.clone())
+        .login(request)
+        .await
+        .map(|response| response.into_inner())
+}
+
+#[tokio::test]
+async fn rate_limit_grpc_works() {
+    std::env::set_var("AVOCADO_RATE_LIMIT__LOGIN__PER_EMAIL__BURST", "2");
+    std::env::set_var("AVOCADO_RATE_LIMIT__LOGIN__PER_EMAIL__PER_MINUTE", "1");
+    std::env::set_var("AVOCADO_RATE_LIMIT__REFRESH__PER_USER__BURST", "1");
+    std::env::set_var("AVOCADO_RATE_LIMIT__REFRESH__PER_USER__PER_MINUTE", "1");
+    std::env::set_var("AVOCADO_RATE_LIMIT__REFRESH__PER_CLIENT__BURST", "5");
+    std::env::set_var("AVOCADO_RATE_LIMIT__REFRESH__PER_CLIENT__PER_MINUTE", "1");
+    start_server().await;
+
+    let channel = Channel::from_static("http://[::1]:50051")
+        .connect()
+        .await
+        .expect("failed to connect to user grpc server");
+
+    // The burst of an email counts its failed logins too, whatever its case
+    let reply = login(&channel, "admin@avocado.com")
+        .await
+        .expect("user login grpc call failed");
+    login(&channel, "Admin@Avocado.com")
+        .await
+        .expect("emails are case insensitive");
+    let status = login(&channel, "admin@avocado.com")
+        .await
+        .expect_err("the third login should be limited");
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    let retry_after: u64 = status
+        .metadata()
+        .get("retry-after")
+        .expect("a retry-after hint should be sent")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+
+    // Other emails aren't limited by it
+    let status = login(&channel, "nobody@avocado.com")
+        .await
+        .expect_err("an unknown email can't log in");
+    assert_ne!(status.code(), Code::ResourceExhausted);
+
+    // The refreshes of a user
+    let mut jwt_client =
+        JwtClient::with_interceptor(channel.clone(), Bearer::new(&reply.access_token).unwrap());
+    let reply = jwt_client
+        .refresh(RefreshRequest {
+            refresh_token: reply.refresh_token,
+        })
+        .await
+        .expect("jwt token refresh grpc call failed")
+        .into_inner();
+    let status = jwt_client
+        .refresh(RefreshRequest {
+            refresh_token: reply.refresh_token,
+        })
+        .await
+        .expect_err("the second refresh should be limited");
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.metadata().get("retry-after").is_some());
+
+    // The refreshes of a client, counted before their tokens are verified
+    let mut flooding_client = JwtClient::with_interceptor(channel, Bearer::new("invalid").unwrap());
+    let mut codes = vec![];
+    for _ in 0..4 {
+        let status = flooding_client
+            .refresh(RefreshRequest {
+                refresh_token: "invalid".to_string(),
+            })
+            .await
+            .expect_err("an invalid token should be refused");
+        codes.push(status.code());
+    }
+    assert_eq!(
+        codes,
+        [
+            Code::Unauthenticated,
+            Code::Unauthenticated,
+            Code::Unauthenticated,
+            Code::ResourceExhausted
+        ]
+    );
+}
@@ -0,0 +1,58 @@
+use avocado_proto::grpc::health::health_check_response::ServingStatus;
+use avocado_proto::grpc::health::health_client::HealthClient;
+use avocado_proto::grpc::health::HealthCheckRequest;
+use avocado_user::run_until;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn shutdown_grpc_works() {
+    let keys = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/keys");
+    std::env::set_var(
+        "AVOCADO_RSA__PRIVATE_KEY_FILE",
+        format!("{}/test.pem", keys),
+    );
+    std::env::set_var(
+        "AVOCADO_RSA__PUBLIC_KEY_FILE",
+        format!("{}/test.pub.pem", keys),
+    );
+    std::env::set_var("AVOCADO_SERVER__DRAIN_TIMEOUT_MS", "2000");
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = run_until(async {
+        let _ = shutdown_rx.await;
+    })
+    .await
+    .expect("failed to create avocado-user grpc router");
+    let server = tokio::spawn(server);
+    while TcpStream::connect("[::1]:50051").await.is_err() {
+        sleep(Duration::from_millis(300)).await;
+    }
+
+    let mut health_client = HealthClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to health grpc server");
+    let mut stream = health_client
+        .watch(HealthCheckRequest {
+            service: "".to_string(),
+        })
+        .await
+        .expect("health watch grpc call failed")
+        .into_inner();
+    let response = stream.message().await.unwrap().unwrap();
+    assert_eq!(response.status(), ServingStatus::Serving);
+
+    // Requests in flight see the server going away while they drain
+    shutdown_tx.send(()).unwrap();
+    let response = stream.message().await.unwrap().unwrap();
+    assert_eq!(response.status(), ServingStatus::NotServing);
+
+    // The watch never ends by itself, so it is dropped at the deadline
+    timeout(Duration::from_secs(10), server)
+        .await
+        .expect("the server did not stop after draining")
+        .unwrap()
+        .expect("the server failed");
+    assert!(TcpStream::connect("[::1]:50051").await.is_err());
+}
@@ -0,0 +1,99 @@
+use crate::app::start_server;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::LoginRequest;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+
+mod app;
+
+fn cert(name: &str) -> String {
+    std::fs::read_to_string(format!(
+        "{}/tests/certs/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
+    .expect("unable to read test certificate")
+}
+
+async fn login(tls: ClientTlsConfig) -> Result<(), tonic::Status> {
+    let channel = Channel::from_static("https://[::1]:50051")
+        .tls_config(tls.domain_name("localhost"))
+        .expect("invalid client tls config")
+        .connect()
+        .await
+        .map_err(|e| tonic::Status::unavailable(e.to_string()))?;
+    UserClient::new(channel)
+        .login(tonic::Request::new(LoginRequest {
+            tenant: "".to_string(),
+            email: "admin@avocado.com".to_string(),
+            password: "kIxv4NomLT0WwGKF".to_string(),
+        }))
+        .await
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn tls_grpc_works() {
+    let certs = format!("{}/tests/certs", env!("CARGO_MANIFEST_DIR"));
+    std::env::set_var(
+        "AVOCADO_SERVER__TLS__CERT_FILE",
+        format!("{}/server.pem", certs),
+    );
+    std::env::set_var(
+        "AVOCADO_SERVER__TLS__KEY_FILE",
+        format!("{}/server.key", certs),
+    );
+    std::env::set_var(
+        "AVOCADO_SERVER__TLS__CLIENT_CA_FILE",
+        format!("{}/ca.pem", certs),
+    );
+    start_server().await;
+
+    // Clients with a certificate from the CA get through
+    let ca = Certificate::from_pem(cert("ca.pem"));
+    login(
+        ClientTlsConfig::new()
+            .ca_certificate(ca.clone())
+            .identity(Identity::from_pem(cert("client.pem"), cert("client.key"))),
+    )
+    .await
+    .expect("user login over mutual tls failed");
+
+    // The admin CLI as well
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_avocado-user-admin"))
+        .args(["--endpoint", "https://[::1]:50051", "--domain", "localhost"])
+        .arg("--ca")
+        .arg(format!("{}/ca.pem", certs))
+        .arg("--cert")
+        .arg(format!("{}/client.pem", certs))
+        .arg("--key")
+        .arg(format!("{}/client.key", certs))
+        .args(["login", "--email", "admin@avocado.com"])
+        .env("AVOCADO_USER_PASSWORD", "kIxv4NomLT0WwGKF")
+        .output()
+        .await
+        .expect("unable to run the admin cli");
+    assert!(
+        output.status.success(),
+        "admin cli login over mutual tls failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!output.stdout.is_empty());
+
+    // Clients without one are turned away
+    assert!(login(ClientTlsConfig::new().ca_certificate(ca))
+        .await
+        .is_err());
+
+    // Plaintext clients as well
+    let mut user_client = UserClient::connect("http://[::1]:50051").await;
+    if let Ok(client) = &mut user_client {
+        assert!(client
+            .login(tonic::Request::new(LoginRequest {
+                tenant: "".to_string(),
+                email: "admin@avocado.com".to_string(),
+                password: "kIxv4NomLT0WwGKF".to_string(),
+            }))
+            .await
+            .is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::app::start_server;
 use avocado_proto::grpc::user::user_client::UserClient;
-use avocado_proto::grpc::user::{LoginRequest, WhoAmIRequest};
+use avocado_proto::grpc::user::{LoginRequest, SetupRequest, WhoAmIRequest};
 use tonic::metadata::MetadataValue;
 
 mod app;
@@ -15,6 +15,7 @@ async fn user_grpc_works() {
 
     // Login as admin
     let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
         email: "admin@avocado.com".to_string(),
         password: "kIxv4NomLT0WwGKF".to_string(),
     });
@@ -34,4 +35,16 @@ async fn user_grpc_works() {
         .expect("cannot get who I am");
     let email = response.into_inner().email;
     assert_eq!(email, "admin@avocado.com");
+    // The setup needs no token, but is closed once an admin exists
+    let status = user_client
+        .setup(SetupRequest {
+            setup_token: "token".to_string(),
+            email: "root@avocado.com".to_string(),
+            first_name: "Root".to_string(),
+            last_name: "Admin".to_string(),
+            password: "password".to_string(),
+        })
+        .await
+        .expect_err("setup should be refused");
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
 }
@@ -0,0 +1,118 @@
+use crate::app::start_server;
+use avocado_proto::grpc::user::user_client::UserClient;
+use avocado_proto::grpc::user::{
+    AddRequest, LoginRequest, UpdateRequest, UserEventType, WatchUsersRequest,
+};
+use std::time::Duration;
+use tonic::metadata::MetadataValue;
+
+mod app;
+
+#[tokio::test]
+async fn watch_users_grpc_works() {
+    // The watchers are woken by the events published on the bus
+    std::env::set_var("AVOCADO_EVENT__BUS", "broadcast");
+    start_server().await;
+
+    let mut user_client = UserClient::connect("http://[::1]:50051")
+        .await
+        .expect("failed to connect to user grpc server");
+    let request = tonic::Request::new(LoginRequest {
+        tenant: "".to_string(),
+        email: "admin@avocado.com".to_string(),
+        password: "kIxv4NomLT0WwGKF".to_string(),
+    });
+    let access_token: MetadataValue<_> = user_client
+        .login(request)
+        .await
+        .expect("user login grpc call failed")
+        .into_inner()
+        .access_token
+        .parse()
+        .expect("cannot insert grpc auth header");
+
+    let mut request = tonic::Request::new(AddRequest {
+        email: "watch@avocado.com".to_string(),
+        first_name: "Watch".to_string(),
+        last_name: "Test".to_string(),
+        password: "password".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let user_id = user_client
+        .add(request)
+        .await
+        .expect("add user grpc call failed")
+        .into_inner()
+        .user_id;
+
+    // The events before watching are replayed
+    let mut request = tonic::Request::new(WatchUsersRequest::default());
+    request.metadata_mut().insert("auth", access_token.clone());
+    let mut stream = user_client
+        .watch_users(request)
+        .await
+        .expect("watch users grpc call failed")
+        .into_inner();
+    let created = tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("no user event received")
+        .unwrap()
+        .unwrap();
+    assert_eq!(created.r#type(), UserEventType::Created);
+    assert_eq!(created.user_id, user_id);
+    assert_eq!(created.email, "watch@avocado.com");
+    drop(stream);
+
+    // Resuming after the created event only delivers the later changes
+    let mut request = tonic::Request::new(UpdateRequest {
+        user_id: user_id.clone(),
+        email: "watch@avocado.com".to_string(),
+        first_name: "Watched".to_string(),
+        last_name: "Test".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    user_client
+        .update(request)
+        .await
+        .expect("update user grpc call failed");
+    let mut request = tonic::Request::new(WatchUsersRequest {
+        after_seq: created.seq,
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token.clone());
+    let mut stream = user_client
+        .watch_users(request)
+        .await
+        .expect("watch users grpc call failed")
+        .into_inner();
+    let updated = tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("no user event received")
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.r#type(), UserEventType::Updated);
+    assert!(updated.seq > created.seq);
+    assert_eq!(updated.first_name, "Watched");
+
+    // The changes made while watching are delivered as they happen
+    let mut request = tonic::Request::new(UpdateRequest {
+        user_id: user_id.clone(),
+        email: "watch@avocado.com".to_string(),
+        first_name: "Rewatched".to_string(),
+        last_name: "Test".to_string(),
+        ..Default::default()
+    });
+    request.metadata_mut().insert("auth", access_token);
+    user_client
+        .update(request)
+        .await
+        .expect("update user grpc call failed");
+    let updated = tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("no user event received")
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.first_name, "Rewatched");
+}